@@ -38,11 +38,11 @@ contract MockLayerZeroEndpoint {
     }
 
     function send(
-        MessagingParams calldata /*_params*/,
+        MessagingParams calldata _params,
         address /*_refundAddress*/
     ) external payable returns (MessagingReceipt memory receipt) {
-        // Mock implementation - just emit an event and return mock receipt
-        emit PacketSent("mock payload", "mock options", address(this));
+        // Mock implementation - emit the message as sent and return mock receipt
+        emit PacketSent(_params.message, _params.options, address(this));
         receipt = MessagingReceipt({
             guid: bytes32(uint256(1)),
             nonce: 1,
@@ -19,7 +19,13 @@ contract SolanaControllerDAOV2 is OApp, ReentrancyGuard, Pausable {
     uint8 public constant COMMAND_TRANSFER_AUTHORITY = 2;
     uint8 public constant COMMAND_EMERGENCY_PAUSE = 3;
     uint8 public constant COMMAND_EMERGENCY_UNPAUSE = 4;
+    uint8 public constant COMMAND_SET_MIN_VERSION = 5;
+    uint8 public constant COMMAND_ENVELOPE = 6;
+    uint8 public constant COMMAND_COMPOSE = 7;
+    uint8 public constant COMMAND_RECEIPT = 9;
+    uint8 public constant COMMAND_SET_DELEGATE = 10;
     uint8 public constant MESSAGE_VERSION = 1;
+    uint8 public constant MESSAGE_VERSION_ATTESTED = 2;
 
     // Cross-chain message structure
     struct CrossChainMessage {
@@ -30,6 +36,15 @@ contract SolanaControllerDAOV2 is OApp, ReentrancyGuard, Pausable {
         bytes payload;
     }
 
+    // What Solana reported after running a message
+    struct ExecutionReceipt {
+        bytes32 guid;
+        uint32 resultCode; // 0 if the command executed, otherwise its error code
+        bytes32 merkleRoot;
+        uint64 processedMessages;
+        bool received;
+    }
+
     // Governance state (same as before)
     struct Proposal {
         uint256 id;
@@ -71,6 +86,12 @@ contract SolanaControllerDAOV2 is OApp, ReentrancyGuard, Pausable {
     
     // Message replay protection
     mapping(uint64 => bool) public processedNonces;
+
+    // DAO signer attestations (abi.encode(bytes[] signatures)) per proposal
+    mapping(uint256 => bytes) public proposalAttestations;
+
+    // Execution receipts from Solana, by message nonce
+    mapping(uint64 => ExecutionReceipt) public executionReceipts;
     
     // Enhanced security
     bool public emergencyPaused;
@@ -90,6 +111,7 @@ contract SolanaControllerDAOV2 is OApp, ReentrancyGuard, Pausable {
     event EmergencyPauseToggled(bool paused);
     event EmergencyAdminChanged(address indexed oldAdmin, address indexed newAdmin);
     event MessageReceived(uint32 indexed srcEid, bytes32 indexed sender, bytes message);
+    event ExecutionReceiptReceived(uint64 indexed nonce, bytes32 guid, uint32 resultCode, bytes32 merkleRoot, uint64 processedMessages);
     event BatchMessageSent(uint32 indexed dstEid, uint256 messageCount);
     event RateLimitExceeded(uint32 indexed eid, uint256 attempts);
 
@@ -172,6 +194,27 @@ contract SolanaControllerDAOV2 is OApp, ReentrancyGuard, Pausable {
         return _createProposal(_description, message);
     }
 
+    /**
+     * @dev Create a proposal executing several commands atomically on Solana
+     */
+    function createEnvelopeProposal(
+        string calldata _description,
+        uint8[] calldata _commands,
+        bytes[] calldata _payloads
+    ) external onlyMember returns (uint256) {
+        require(_commands.length == _payloads.length, "Length mismatch");
+
+        CrossChainMessage memory message = CrossChainMessage({
+            command: COMMAND_ENVELOPE,
+            nonce: messageNonce++,
+            timestamp: int64(uint64(block.timestamp)),
+            version: MESSAGE_VERSION,
+            payload: abi.encode(_commands, _payloads)
+        });
+
+        return _createProposal(_description, message);
+    }
+
     /**
      * @dev Create an emergency pause proposal
      */
@@ -189,6 +232,25 @@ contract SolanaControllerDAOV2 is OApp, ReentrancyGuard, Pausable {
         return _createProposal(_description, message);
     }
 
+    /**
+     * @dev Create a proposal replacing the Solana OApp's endpoint delegate.
+     * Needs DAO signer attestations once Solana sets a signer threshold.
+     */
+    function createSetDelegateProposal(
+        string calldata _description,
+        bytes32 _delegate
+    ) external onlyMember returns (uint256) {
+        CrossChainMessage memory message = CrossChainMessage({
+            command: COMMAND_SET_DELEGATE,
+            nonce: messageNonce++,
+            timestamp: int64(uint64(block.timestamp)),
+            version: MESSAGE_VERSION,
+            payload: abi.encode(_delegate)
+        });
+
+        return _createProposal(_description, message);
+    }
+
     /**
      * @dev Internal function to create proposals
      */
@@ -247,14 +309,25 @@ contract SolanaControllerDAOV2 is OApp, ReentrancyGuard, Pausable {
 
         proposal.executed = true;
 
-        // Encode the cross-chain message for LayerZero
-        bytes memory encodedMessage = abi.encode(
-            proposal.message.command,
-            proposal.message.nonce,
-            proposal.message.timestamp,
-            proposal.message.version,
-            proposal.message.payload
-        );
+        // Encode the cross-chain message for LayerZero, with the attestation
+        // in the v2 extensions section when signers have attached one
+        bytes memory attestation = proposalAttestations[_proposalId];
+        bytes memory encodedMessage = attestation.length == 0
+            ? abi.encode(
+                proposal.message.command,
+                proposal.message.nonce,
+                proposal.message.timestamp,
+                proposal.message.version,
+                proposal.message.payload
+            )
+            : abi.encode(
+                proposal.message.command,
+                proposal.message.nonce,
+                proposal.message.timestamp,
+                MESSAGE_VERSION_ATTESTED,
+                proposal.message.payload,
+                attestation
+            );
 
         // Validate message
         require(_validateMessage(encodedMessage), "Invalid message format");
@@ -279,6 +352,34 @@ contract SolanaControllerDAOV2 is OApp, ReentrancyGuard, Pausable {
         emit CrossChainCommandSent(_proposalId, proposal.message.command, proposal.message.nonce);
     }
 
+    /**
+     * @dev Hash DAO signers sign (with eth_sign) to attest a proposal on Solana
+     * @param _receiver The Solana OApp store address
+     */
+    function getAttestationHash(uint256 _proposalId, bytes32 _receiver) public view returns (bytes32) {
+        CrossChainMessage storage message = proposals[_proposalId].message;
+        return keccak256(abi.encode(
+            _receiver,
+            message.command,
+            message.nonce,
+            message.timestamp,
+            message.payload
+        ));
+    }
+
+    /**
+     * @dev Attach DAO signer signatures to a proposal before execution
+     */
+    function attachAttestation(uint256 _proposalId, bytes[] calldata _signatures) external onlyMember {
+        require(_proposalId < proposalCount, "Unknown proposal");
+        require(!proposals[_proposalId].executed, "Already executed");
+        for (uint256 i = 0; i < _signatures.length; i++) {
+            require(_signatures[i].length == 65, "Invalid signature length");
+        }
+
+        proposalAttestations[_proposalId] = abi.encode(_signatures);
+    }
+
     /**
      * @dev Check if a proposal passed (same as before)
      */
@@ -535,8 +636,53 @@ contract SolanaControllerDAOV2 is OApp, ReentrancyGuard, Pausable {
         // Emit event for message reception
         emit MessageReceived(_origin.srcEid, _origin.sender, _message);
         
-        // This DAO primarily sends messages, but can log received confirmations
-        // In a full implementation, you might decode and process acknowledgments here
+        // Solana only sends v1 messages; receipts acknowledge executed proposals
+        (uint8 command, , , , bytes memory payload) = abi.decode(_message, (uint8, uint64, int64, uint8, bytes));
+        if (command == COMMAND_RECEIPT) {
+            _recordReceipt(payload);
+        }
+    }
+
+    /**
+     * @dev Record an execution receipt: abi.encode(bytes32 guid, uint64 nonce,
+     * uint32 resultCode, bytes32 merkleRoot, uint64 processedMessages)
+     *
+     * A recorded receipt is final, except that a failed message retried on
+     * Solana is reported again, from the same packet
+     */
+    function _recordReceipt(bytes memory _payload) internal {
+        (bytes32 guid, uint64 nonce, uint32 resultCode, bytes32 merkleRoot, uint64 processedMessages) =
+            abi.decode(_payload, (bytes32, uint64, uint32, bytes32, uint64));
+        
+        ExecutionReceipt storage recorded = executionReceipts[nonce];
+        if (recorded.received) {
+            require(recorded.guid == guid && recorded.resultCode != 0, "Receipt already recorded");
+        }
+        
+        executionReceipts[nonce] = ExecutionReceipt({
+            guid: guid,
+            resultCode: resultCode,
+            merkleRoot: merkleRoot,
+            processedMessages: processedMessages,
+            received: true
+        });
+        emit ExecutionReceiptReceived(nonce, guid, resultCode, merkleRoot, processedMessages);
+    }
+
+    /**
+     * @dev Whether Solana confirmed a proposal's message, and with which result
+     */
+    function getProposalReceipt(uint256 _proposalId) external view returns (
+        bool received,
+        bool succeeded,
+        uint32 resultCode
+    ) {
+        Proposal storage proposal = proposals[_proposalId];
+        if (!proposal.executed) {
+            return (false, false, 0);
+        }
+        ExecutionReceipt storage receipt = executionReceipts[proposal.message.nonce];
+        return (receipt.received, receipt.received && receipt.resultCode == 0, receipt.resultCode);
     }
 
     /**
@@ -0,0 +1,212 @@
+import { expect } from "chai";
+import { ethers } from "hardhat";
+import { time, takeSnapshot } from "@nomicfoundation/hardhat-network-helpers";
+import * as fs from "fs";
+import * as path from "path";
+
+// Golden vectors shared with the Solana program's codec tests
+const FIXTURES_DIR = path.join(__dirname, "../../../fixtures/messages");
+
+const MESSAGE_V1 = ["uint8", "uint64", "int64", "uint8", "bytes"];
+const MESSAGE_V2 = [...MESSAGE_V1, "bytes"];
+
+const COMMAND_ENVELOPE = 6;
+const COMMAND_COMPOSE = 7;
+const COMMAND_LEGACY = 8;
+const COMMAND_RECEIPT = 9;
+const COMMAND_SET_DELEGATE = 10;
+
+// v2 layout carrying a Borsh `CrossChainMessage` under COMMAND_LEGACY
+const MESSAGE_VERSION_BORSH = 3;
+
+interface Fixture {
+  name: string;
+  hex: string;
+  expected: any;
+}
+
+function loadFixtures(): Fixture[] {
+  return fs
+    .readdirSync(FIXTURES_DIR)
+    .filter((file) => file.endsWith(".json"))
+    .sort()
+    .map((file) => {
+      const name = path.basename(file, ".json");
+      return {
+        name,
+        hex: "0x" + fs.readFileSync(path.join(FIXTURES_DIR, `${name}.hex`), "utf8").trim(),
+        expected: JSON.parse(fs.readFileSync(path.join(FIXTURES_DIR, file), "utf8")),
+      };
+    });
+}
+
+const coder = ethers.AbiCoder.defaultAbiCoder();
+
+/**
+ * Decode `data` as `types`, accepting only the canonical abi.encode layout
+ * like the Solana codec does
+ */
+function decodeCanonical(types: string[], data: string): any[] {
+  let values;
+  try {
+    values = coder.decode(types, data);
+  } catch {
+    throw new Error("InvalidLzMessage");
+  }
+  if (coder.encode(types, values) !== data) {
+    throw new Error("InvalidLzMessage");
+  }
+  return values.toArray();
+}
+
+function decodeCommand(command: number, payload: string): any {
+  switch (command) {
+    case 0: {
+      const [uri, name, symbol] = decodeCanonical(["string", "string", "string"], payload);
+      return { type: "UpdateCollectionMetadata", uri, name, symbol };
+    }
+    case 1: {
+      const [leafIndices, newUris] = decodeCanonical(["uint32[]", "string[]"], payload);
+      if (leafIndices.length !== newUris.length) throw new Error("InvalidLzMessage");
+      return {
+        type: "BatchUpdateCnfts",
+        updates: leafIndices.map((leafIndex: bigint, i: number) => ({
+          leafIndex: Number(leafIndex),
+          newUri: newUris[i],
+        })),
+      };
+    }
+    case 2: {
+      const [newAuthority] = decodeCanonical(["bytes32"], payload);
+      return { type: "TransferAuthority", newAuthority };
+    }
+    case 3:
+      decodeCanonical([], payload);
+      return { type: "EmergencyPause" };
+    case 4:
+      decodeCanonical([], payload);
+      return { type: "EmergencyUnpause" };
+    case 5: {
+      const [minVersion] = decodeCanonical(["uint8"], payload);
+      return { type: "SetMinVersion", minVersion: Number(minVersion) };
+    }
+    case COMMAND_ENVELOPE: {
+      const [commands, payloads] = decodeCanonical(["uint8[]", "bytes[]"], payload);
+      if (commands.length !== payloads.length) throw new Error("InvalidLzMessage");
+      if (commands.some((c: bigint) => Number(c) === COMMAND_ENVELOPE)) throw new Error("NestedEnvelope");
+      if (commands.some((c: bigint) => Number(c) === COMMAND_LEGACY)) throw new Error("UnsupportedCommand");
+      return {
+        type: "Envelope",
+        commands: commands.map((c: bigint, i: number) => decodeCommand(Number(c), payloads[i])),
+      };
+    }
+    case COMMAND_COMPOSE: {
+      const [composeType, composeMsg] = decodeCanonical(["uint8", "bytes"], payload);
+      return { type: "Compose", composeType: Number(composeType), composeMsg };
+    }
+    case COMMAND_RECEIPT: {
+      const [guid, nonce, resultCode, merkleRoot, processedMessages] = decodeCanonical(
+        ["bytes32", "uint64", "uint32", "bytes32", "uint64"],
+        payload
+      );
+      return {
+        type: "Receipt",
+        guid,
+        nonce: nonce.toString(),
+        resultCode: Number(resultCode),
+        merkleRoot,
+        processedMessages: processedMessages.toString(),
+      };
+    }
+    case COMMAND_SET_DELEGATE: {
+      const [delegate] = decodeCanonical(["bytes32"], payload);
+      return { type: "SetDelegate", delegate };
+    }
+    case COMMAND_LEGACY:
+      // Borsh, so only the program can look inside
+      return { type: "Legacy", message: payload };
+    default:
+      throw new Error("UnknownCommand");
+  }
+}
+
+function decodeMessage(hex: string): any {
+  // version is the fourth head word
+  const version = hex.length >= 2 + 4 * 64 ? Number(BigInt("0x" + hex.slice(2 + 3 * 64, 2 + 4 * 64))) : -1;
+  if (version !== 1 && version !== 2 && version !== MESSAGE_VERSION_BORSH) {
+    throw new Error(version === -1 ? "InvalidLzMessage" : "UnsupportedVersion");
+  }
+
+  const [command, nonce, timestamp, , payload, extensions = "0x"] = decodeCanonical(
+    version === 1 ? MESSAGE_V1 : MESSAGE_V2,
+    hex
+  );
+  if ((Number(command) === COMMAND_LEGACY) !== (version === MESSAGE_VERSION_BORSH)) {
+    throw new Error("UnsupportedCommand");
+  }
+  const attestation = extensions === "0x" ? null : decodeCanonical(["bytes[]"], extensions)[0];
+
+  return {
+    version,
+    command: Number(command),
+    nonce: nonce.toString(),
+    timestamp: timestamp.toString(),
+    payload,
+    extensions,
+    decoded: decodeCommand(Number(command), payload),
+    attestation,
+  };
+}
+
+describe("Message golden vectors", function () {
+  const fixtures = loadFixtures();
+
+  it("Should find the shared fixtures", function () {
+    expect(fixtures.length).to.be.greaterThan(0);
+  });
+
+  for (const { name, hex, expected } of fixtures) {
+    if (!expected.valid) {
+      it(`Should reject ${name}`, function () {
+        expect(() => decodeMessage(hex)).to.throw(expected.error);
+      });
+      continue;
+    }
+
+    it(`Should decode ${name}`, function () {
+      const { description, valid, dao, ...fields } = expected;
+      expect(decodeMessage(hex)).to.deep.equal(fields);
+    });
+
+    if (expected.dao === "emergencyUpdate") {
+      it(`Should emit ${name} from the DAO`, async function () {
+        const snapshot = await takeSnapshot();
+        const [owner] = await ethers.getSigners();
+
+        const MockEndpointFactory = await ethers.getContractFactory("MockLayerZeroEndpoint");
+        const endpoint = await MockEndpointFactory.deploy();
+        const DAOV2Factory = await ethers.getContractFactory("SolanaControllerDAOV2");
+        const dao = await DAOV2Factory.deploy(
+          await endpoint.getAddress(),
+          owner.address,
+          40168,
+          "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"
+        );
+
+        await time.setNextBlockTimestamp(BigInt(expected.timestamp));
+        const tx = await dao.emergencyUpdate(expected.command, expected.payload, {
+          value: ethers.parseEther("0.01"),
+        });
+        const receipt = await tx.wait();
+
+        const sent = receipt.logs
+          .filter((log: any) => log.address === endpoint.target)
+          .map((log: any) => endpoint.interface.parseLog(log))
+          .find((log: any) => log?.name === "PacketSent");
+        expect(sent?.args.encodedPayload).to.equal(hex);
+
+        await snapshot.restore();
+      });
+    }
+  }
+});
@@ -114,6 +114,15 @@ describe("SolanaControllerDAOV2", function () {
       expect(await dao.messageNonce()).to.equal(2);
     });
 
+    it("Should create set delegate proposal", async function () {
+      const delegate = ethers.hexlify(ethers.randomBytes(32));
+      await dao.connect(member1).createSetDelegateProposal("Rotate delegate", delegate);
+
+      const proposal = await dao.getProposal(0);
+      expect(proposal.command).to.equal(await dao.COMMAND_SET_DELEGATE());
+      expect(proposal.command).to.equal(10);
+    });
+
     it("Should allow voting on V2 proposals", async function () {
       // Create proposal
       await dao.connect(member1).createUpdateMetadataProposal(
@@ -168,6 +177,114 @@ describe("SolanaControllerDAOV2", function () {
       expect(proposal.executed).to.be.true;
     });
 
+    it("Should record execution receipts from Solana", async function () {
+      await dao.connect(member1).createEmergencyPauseProposal("Emergency pause");
+      await dao.connect(member1).vote(0, true);
+      await dao.connect(member2).vote(0, true);
+      await dao.connect(member3).vote(0, true);
+      await time.increase(VOTING_PERIOD + 1);
+      await dao.executeProposal(0, { value: ethers.parseEther("0.01") });
+
+      const proposal = await dao.getProposal(0);
+      expect((await dao.getProposalReceipt(0)).received).to.be.false;
+
+      // Receipt for the proposal's message, as the Solana program sends it
+      const coder = ethers.AbiCoder.defaultAbiCoder();
+      const guid = ethers.hexlify(ethers.randomBytes(32));
+      const merkleRoot = ethers.hexlify(ethers.randomBytes(32));
+      const payload = coder.encode(
+        ["bytes32", "uint64", "uint32", "bytes32", "uint64"],
+        [guid, proposal.nonce, 0, merkleRoot, 7]
+      );
+      const message = coder.encode(
+        ["uint8", "uint64", "int64", "uint8", "bytes"],
+        [9, 1, await time.latest(), 1, payload]
+      );
+
+      // Deliver it as the endpoint would
+      const endpointAddress = await mockEndpointContract.getAddress();
+      await ethers.provider.send("hardhat_impersonateAccount", [endpointAddress]);
+      await ethers.provider.send("hardhat_setBalance", [endpointAddress, "0xde0b6b3a7640000"]);
+      const endpoint = await ethers.getSigner(endpointAddress);
+      const origin = {
+        srcEid: SOLANA_EID,
+        sender: ethers.zeroPadValue(MOCK_SOLANA_CONTROLLER_RAW, 32),
+        nonce: 1,
+      };
+      await expect(
+        dao.connect(endpoint).lzReceive(origin, guid, message, owner.address, "0x")
+      )
+        .to.emit(dao, "ExecutionReceiptReceived")
+        .withArgs(proposal.nonce, guid, 0, merkleRoot, 7);
+
+      const result = await dao.getProposalReceipt(0);
+      expect(result.received).to.be.true;
+      expect(result.succeeded).to.be.true;
+      expect((await dao.executionReceipts(proposal.nonce)).merkleRoot).to.equal(merkleRoot);
+
+      // A second receipt for the same message cannot replace the first
+      const otherGuid = ethers.hexlify(ethers.randomBytes(32));
+      for (const receiptGuid of [guid, otherGuid]) {
+        const replay = coder.encode(
+          ["uint8", "uint64", "int64", "uint8", "bytes"],
+          [9, 2, await time.latest(), 1, coder.encode(
+            ["bytes32", "uint64", "uint32", "bytes32", "uint64"],
+            [receiptGuid, proposal.nonce, 6000, merkleRoot, 8]
+          )]
+        );
+        await expect(
+          dao.connect(endpoint).lzReceive({ ...origin, nonce: 2 }, otherGuid, replay, owner.address, "0x")
+        ).to.be.revertedWith("Receipt already recorded");
+      }
+    });
+
+    it("Should update the receipt of a message retried on Solana", async function () {
+      await dao.connect(member1).createEmergencyPauseProposal("Emergency pause");
+      await dao.connect(member1).vote(0, true);
+      await dao.connect(member2).vote(0, true);
+      await dao.connect(member3).vote(0, true);
+      await time.increase(VOTING_PERIOD + 1);
+      await dao.executeProposal(0, { value: ethers.parseEther("0.01") });
+      const proposal = await dao.getProposal(0);
+
+      const endpointAddress = await mockEndpointContract.getAddress();
+      await ethers.provider.send("hardhat_impersonateAccount", [endpointAddress]);
+      await ethers.provider.send("hardhat_setBalance", [endpointAddress, "0xde0b6b3a7640000"]);
+      const endpoint = await ethers.getSigner(endpointAddress);
+
+      // The message is stored on Solana, then retried from the same packet
+      const coder = ethers.AbiCoder.defaultAbiCoder();
+      const guid = ethers.hexlify(ethers.randomBytes(32));
+      const merkleRoot = ethers.hexlify(ethers.randomBytes(32));
+      const deliver = async (nonce: number, resultCode: number) => {
+        const payload = coder.encode(
+          ["bytes32", "uint64", "uint32", "bytes32", "uint64"],
+          [guid, proposal.nonce, resultCode, merkleRoot, nonce]
+        );
+        const message = coder.encode(
+          ["uint8", "uint64", "int64", "uint8", "bytes"],
+          [9, nonce, await time.latest(), 1, payload]
+        );
+        const origin = {
+          srcEid: SOLANA_EID,
+          sender: ethers.zeroPadValue(MOCK_SOLANA_CONTROLLER_RAW, 32),
+          nonce,
+        };
+        return dao.connect(endpoint).lzReceive(origin, ethers.hexlify(ethers.randomBytes(32)), message, owner.address, "0x");
+      };
+
+      await deliver(1, 6000);
+      expect((await dao.getProposalReceipt(0)).succeeded).to.be.false;
+
+      await expect(deliver(2, 0)).to.emit(dao, "ExecutionReceiptReceived");
+      const result = await dao.getProposalReceipt(0);
+      expect(result.received).to.be.true;
+      expect(result.succeeded).to.be.true;
+
+      // Once it executed, the receipt is final
+      await expect(deliver(3, 6000)).to.be.revertedWith("Receipt already recorded");
+    });
+
     it("Should quote command fees correctly", async function () {
       const payload = ethers.AbiCoder.defaultAbiCoder().encode(
         ["string", "string", "string"],
@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Invalid nonce: Packets must be verified in order and cleared once verified")]
+    InvalidNonce,
+
+    #[msg("Invalid payload hash: Packet does not match what was verified")]
+    InvalidPayloadHash,
+
+    #[msg("Packet already cleared")]
+    AlreadyCleared,
+
+    #[msg("Compose message already received")]
+    ComposeAlreadyReceived,
+
+    #[msg("Insufficient fee: Native fee is below the quote")]
+    InsufficientFee,
+
+    #[msg("Unauthorized: Signer does not match the instruction parameters")]
+    Unauthorized,
+}
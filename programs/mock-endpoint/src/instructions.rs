@@ -0,0 +1,386 @@
+use anchor_lang::prelude::*;
+use crate::error::ErrorCode;
+use crate::state::*;
+
+/// Parameters of `init_endpoint`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct InitEndpointParams {
+    pub eid: u32,
+    pub native_fee: u64,
+    pub lz_token_fee: u64,
+}
+
+/// Parameters of `register_oapp`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RegisterOAppParams {
+    pub delegate: Pubkey,
+}
+
+/// Parameters of `set_delegate`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SetDelegateParams {
+    pub delegate: Pubkey,
+}
+
+/// Parameters of `init_nonce`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct InitNonceParams {
+    pub local_oapp: Pubkey,
+    pub remote_eid: u32,
+    pub remote_oapp: [u8; 32],
+}
+
+/// Parameters of `verify`, standing in for the DVNs and receive library
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct VerifyParams {
+    pub receiver: Pubkey,
+    pub src_eid: u32,
+    pub sender: [u8; 32],
+    pub nonce: u64,
+    pub guid: [u8; 32],
+    pub message: Vec<u8>,
+}
+
+/// Parameters of `clear`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ClearParams {
+    pub receiver: Pubkey,
+    pub src_eid: u32,
+    pub sender: [u8; 32],
+    pub nonce: u64,
+    pub guid: [u8; 32],
+    pub message: Vec<u8>,
+}
+
+/// Parameters of `send_compose`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SendComposeParams {
+    pub to: Pubkey,
+    pub guid: [u8; 32],
+    pub index: u16,
+    pub message: Vec<u8>,
+}
+
+/// Parameters of `clear_compose`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ClearComposeParams {
+    pub from: Pubkey,
+    pub guid: [u8; 32],
+    pub index: u16,
+    pub message: Vec<u8>,
+}
+
+/// Parameters of `send`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SendParams {
+    pub dst_eid: u32,
+    pub receiver: [u8; 32],
+    pub message: Vec<u8>,
+    pub options: Vec<u8>,
+    pub native_fee: u64,
+    pub lz_token_fee: u64,
+}
+
+/// Parameters of `quote`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct QuoteParams {
+    pub sender: Pubkey,
+    pub dst_eid: u32,
+    pub receiver: [u8; 32],
+    pub message: Vec<u8>,
+    pub options: Vec<u8>,
+    pub pay_in_lz_token: bool,
+}
+
+#[derive(Accounts)]
+pub struct InitEndpoint<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = EndpointSettings::LEN,
+        seeds = [EndpointSettings::SEEDS],
+        bump
+    )]
+    pub endpoint_config: Account<'info, EndpointSettings>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterOApp<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub oapp: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = OAppRegistry::LEN,
+        seeds = [OAppRegistry::SEEDS, oapp.key().as_ref()],
+        bump
+    )]
+    pub oapp_registry: Account<'info, OAppRegistry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetDelegate<'info> {
+    pub oapp: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [OAppRegistry::SEEDS, oapp.key().as_ref()],
+        bump = oapp_registry.bump
+    )]
+    pub oapp_registry: Account<'info, OAppRegistry>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: InitNonceParams)]
+pub struct InitNonce<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = Nonce::LEN,
+        seeds = [
+            Nonce::SEEDS,
+            params.local_oapp.as_ref(),
+            &params.remote_eid.to_be_bytes(),
+            &params.remote_oapp
+        ],
+        bump
+    )]
+    pub nonce: Account<'info, Nonce>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: VerifyParams)]
+pub struct Verify<'info> {
+    #[account(
+        has_one = admin,
+        seeds = [EndpointSettings::SEEDS],
+        bump = endpoint_config.bump
+    )]
+    pub endpoint_config: Account<'info, EndpointSettings>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            Nonce::SEEDS,
+            params.receiver.as_ref(),
+            &params.src_eid.to_be_bytes(),
+            &params.sender
+        ],
+        bump = nonce.bump
+    )]
+    pub nonce: Account<'info, Nonce>,
+
+    /// Fails if a packet with this guid was already verified
+    #[account(
+        init,
+        payer = admin,
+        space = PayloadHash::LEN,
+        seeds = [PayloadHash::SEEDS, &params.guid],
+        bump
+    )]
+    pub payload_hash: Account<'info, PayloadHash>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: ClearParams)]
+pub struct Clear<'info> {
+    #[account(address = params.receiver @ ErrorCode::Unauthorized)]
+    pub receiver: Signer<'info>,
+
+    #[account(
+        seeds = [EndpointSettings::SEEDS],
+        bump = endpoint_config.bump
+    )]
+    pub endpoint_config: Account<'info, EndpointSettings>,
+
+    /// CHECK: Any receive library is accepted; only the address is checked
+    #[account(
+        seeds = [ReceiveLibrary::SEEDS, receiver.key().as_ref()],
+        bump
+    )]
+    pub receive_library: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            Nonce::SEEDS,
+            receiver.key().as_ref(),
+            &params.src_eid.to_be_bytes(),
+            &params.sender
+        ],
+        bump = nonce.bump
+    )]
+    pub nonce: Account<'info, Nonce>,
+
+    #[account(
+        mut,
+        seeds = [PayloadHash::SEEDS, &params.guid],
+        bump = payload_hash.bump
+    )]
+    pub payload_hash: Account<'info, PayloadHash>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: SendComposeParams)]
+pub struct SendCompose<'info> {
+    pub from: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Fails if the compose was already queued
+    #[account(
+        init,
+        payer = payer,
+        space = ComposeQueue::LEN,
+        seeds = [
+            ComposeQueue::SEEDS,
+            params.to.as_ref(),
+            &params.guid,
+            &params.index.to_be_bytes()
+        ],
+        bump
+    )]
+    pub compose_queue: Account<'info, ComposeQueue>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: ClearComposeParams)]
+pub struct ClearCompose<'info> {
+    pub to: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            ComposeQueue::SEEDS,
+            to.key().as_ref(),
+            &params.guid,
+            &params.index.to_be_bytes()
+        ],
+        bump = compose_queue.bump
+    )]
+    pub compose_queue: Account<'info, ComposeQueue>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: SendParams)]
+pub struct Send<'info> {
+    pub sender: Signer<'info>,
+
+    #[account(mut)]
+    pub fee_payer: Signer<'info>,
+
+    #[account(
+        seeds = [EndpointSettings::SEEDS],
+        bump = endpoint_config.bump
+    )]
+    pub endpoint_config: Account<'info, EndpointSettings>,
+
+    /// CHECK: Any send library is accepted; only the address is checked
+    #[account(
+        seeds = [SendLibraryConfig::SEEDS, sender.key().as_ref(), &params.dst_eid.to_be_bytes()],
+        bump
+    )]
+    pub send_library: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            Nonce::SEEDS,
+            sender.key().as_ref(),
+            &params.dst_eid.to_be_bytes(),
+            &params.receiver
+        ],
+        bump = nonce.bump
+    )]
+    pub nonce: Account<'info, Nonce>,
+}
+
+#[derive(Accounts)]
+pub struct Quote<'info> {
+    #[account(
+        seeds = [EndpointSettings::SEEDS],
+        bump = endpoint_config.bump
+    )]
+    pub endpoint_config: Account<'info, EndpointSettings>,
+}
+
+#[event]
+pub struct OAppRegistered {
+    pub oapp: Pubkey,
+    pub delegate: Pubkey,
+}
+
+#[event]
+pub struct DelegateSet {
+    pub oapp: Pubkey,
+    pub delegate: Pubkey,
+}
+
+#[event]
+pub struct PacketVerified {
+    pub receiver: Pubkey,
+    pub src_eid: u32,
+    pub sender: [u8; 32],
+    pub nonce: u64,
+    pub guid: [u8; 32],
+}
+
+#[event]
+pub struct PacketDelivered {
+    pub receiver: Pubkey,
+    pub src_eid: u32,
+    pub nonce: u64,
+    pub guid: [u8; 32],
+}
+
+#[event]
+pub struct ComposeSent {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub guid: [u8; 32],
+    pub index: u16,
+    pub message: Vec<u8>,
+}
+
+#[event]
+pub struct ComposeDelivered {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub guid: [u8; 32],
+    pub index: u16,
+}
+
+#[event]
+pub struct PacketSent {
+    pub sender: Pubkey,
+    pub dst_eid: u32,
+    pub receiver: [u8; 32],
+    pub nonce: u64,
+    pub guid: [u8; 32],
+    pub message: Vec<u8>,
+    pub options: Vec<u8>,
+    pub native_fee: u64,
+}
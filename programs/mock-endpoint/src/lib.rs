@@ -0,0 +1,197 @@
+//! Stand-in for the LayerZero V2 endpoint in local tests
+//!
+//! Implements the endpoint instructions the omnichain controller calls, with
+//! the same names, parameters and account addresses, so its CPIs reach this
+//! program unchanged. Every call is recorded in the endpoint's PDAs: packets
+//! must be verified (by the endpoint admin, standing in for the DVNs) before
+//! they can be cleared, clearing checks the payload hash and the path nonce,
+//! and composes and sends are recorded the same way. Fees are quoted and
+//! checked but not collected.
+
+// Anchor's generated IDL instructions still call the deprecated `AccountInfo::realloc`
+#![allow(deprecated)]
+
+pub mod error;
+pub mod instructions;
+pub mod state;
+
+use anchor_lang::prelude::*;
+
+pub use instructions::*;
+pub use state::*;
+
+use error::ErrorCode;
+
+declare_id!("GktWVrUXZixxorGRCpFKS6USENaHUrduBmCBmS47yUPf");
+
+#[program]
+pub mod mock_endpoint {
+    use super::*;
+
+    /// Create the endpoint settings
+    pub fn init_endpoint(ctx: Context<InitEndpoint>, params: InitEndpointParams) -> Result<()> {
+        let settings = &mut ctx.accounts.endpoint_config;
+        settings.admin = ctx.accounts.admin.key();
+        settings.eid = params.eid;
+        settings.native_fee = params.native_fee;
+        settings.lz_token_fee = params.lz_token_fee;
+        settings.bump = ctx.bumps.endpoint_config;
+        Ok(())
+    }
+
+    /// Register the signing OApp and its delegate
+    pub fn register_oapp(ctx: Context<RegisterOApp>, params: RegisterOAppParams) -> Result<()> {
+        let registry = &mut ctx.accounts.oapp_registry;
+        registry.delegate = params.delegate;
+        registry.bump = ctx.bumps.oapp_registry;
+
+        emit!(OAppRegistered {
+            oapp: ctx.accounts.oapp.key(),
+            delegate: params.delegate,
+        });
+        Ok(())
+    }
+
+    /// Replace the delegate of the signing OApp
+    pub fn set_delegate(ctx: Context<SetDelegate>, params: SetDelegateParams) -> Result<()> {
+        ctx.accounts.oapp_registry.delegate = params.delegate;
+
+        emit!(DelegateSet {
+            oapp: ctx.accounts.oapp.key(),
+            delegate: params.delegate,
+        });
+        Ok(())
+    }
+
+    /// Create the nonce account of a path
+    pub fn init_nonce(ctx: Context<InitNonce>, _params: InitNonceParams) -> Result<()> {
+        let nonce = &mut ctx.accounts.nonce;
+        nonce.inbound_nonce = 0;
+        nonce.outbound_nonce = 0;
+        nonce.bump = ctx.bumps.nonce;
+        Ok(())
+    }
+
+    /// Verify an inbound packet so its receiver can clear it
+    ///
+    /// Nonces of a path must be verified in order.
+    pub fn verify(ctx: Context<Verify>, params: VerifyParams) -> Result<()> {
+        ctx.accounts.nonce.verify(params.nonce)?;
+
+        let payload_hash = &mut ctx.accounts.payload_hash;
+        payload_hash.receiver = params.receiver;
+        payload_hash.src_eid = params.src_eid;
+        payload_hash.sender = params.sender;
+        payload_hash.nonce = params.nonce;
+        payload_hash.hash = state::payload_hash(&params.guid, &params.message);
+        payload_hash.cleared = false;
+        payload_hash.bump = ctx.bumps.payload_hash;
+
+        emit!(PacketVerified {
+            receiver: params.receiver,
+            src_eid: params.src_eid,
+            sender: params.sender,
+            nonce: params.nonce,
+            guid: params.guid,
+        });
+        Ok(())
+    }
+
+    /// Clear a verified packet, once
+    pub fn clear(ctx: Context<Clear>, params: ClearParams) -> Result<()> {
+        let payload_hash = &mut ctx.accounts.payload_hash;
+        let verified = payload_hash.receiver == params.receiver
+            && payload_hash.src_eid == params.src_eid
+            && payload_hash.sender == params.sender
+            && payload_hash.nonce == params.nonce
+            && payload_hash.hash == state::payload_hash(&params.guid, &params.message);
+        if !verified {
+            return Err(ErrorCode::InvalidPayloadHash.into());
+        }
+        if payload_hash.cleared {
+            return Err(ErrorCode::AlreadyCleared.into());
+        }
+        if params.nonce > ctx.accounts.nonce.inbound_nonce {
+            return Err(ErrorCode::InvalidNonce.into());
+        }
+        payload_hash.cleared = true;
+
+        emit!(PacketDelivered {
+            receiver: params.receiver,
+            src_eid: params.src_eid,
+            nonce: params.nonce,
+            guid: params.guid,
+        });
+        Ok(())
+    }
+
+    /// Queue a compose message for `params.to`
+    pub fn send_compose(ctx: Context<SendCompose>, params: SendComposeParams) -> Result<()> {
+        let queue = &mut ctx.accounts.compose_queue;
+        queue.from = ctx.accounts.from.key();
+        queue.hash = state::payload_hash(&params.guid, &params.message);
+        queue.received = false;
+        queue.bump = ctx.bumps.compose_queue;
+
+        emit!(ComposeSent {
+            from: queue.from,
+            to: params.to,
+            guid: params.guid,
+            index: params.index,
+            message: params.message,
+        });
+        Ok(())
+    }
+
+    /// Clear a queued compose message, once
+    pub fn clear_compose(ctx: Context<ClearCompose>, params: ClearComposeParams) -> Result<()> {
+        let queue = &mut ctx.accounts.compose_queue;
+        if queue.from != params.from || queue.hash != state::payload_hash(&params.guid, &params.message) {
+            return Err(ErrorCode::InvalidPayloadHash.into());
+        }
+        if queue.received {
+            return Err(ErrorCode::ComposeAlreadyReceived.into());
+        }
+        queue.received = true;
+
+        emit!(ComposeDelivered {
+            from: params.from,
+            to: ctx.accounts.to.key(),
+            guid: params.guid,
+            index: params.index,
+        });
+        Ok(())
+    }
+
+    /// Send a packet, taking the next outbound nonce of the path
+    ///
+    /// `params.native_fee` must cover the quote.
+    pub fn send(ctx: Context<Send>, params: SendParams) -> Result<MessagingReceipt> {
+        let settings = &ctx.accounts.endpoint_config;
+        let fee = settings.quote(params.lz_token_fee > 0);
+        if params.native_fee < fee.native_fee || params.lz_token_fee < fee.lz_token_fee {
+            return Err(ErrorCode::InsufficientFee.into());
+        }
+
+        let sender = ctx.accounts.sender.key();
+        let nonce = ctx.accounts.nonce.next_outbound()?;
+        let guid = state::guid(nonce, settings.eid, &sender, params.dst_eid, &params.receiver);
+
+        emit!(PacketSent {
+            sender,
+            dst_eid: params.dst_eid,
+            receiver: params.receiver,
+            nonce,
+            guid,
+            message: params.message,
+            options: params.options,
+            native_fee: fee.native_fee,
+        });
+        Ok(MessagingReceipt { guid, nonce, fee })
+    }
+
+    /// Quote the fee of a send
+    pub fn quote(ctx: Context<Quote>, params: QuoteParams) -> Result<MessagingFee> {
+        Ok(ctx.accounts.endpoint_config.quote(params.pay_in_lz_token))
+    }
+}
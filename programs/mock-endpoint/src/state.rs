@@ -0,0 +1,280 @@
+use anchor_lang::prelude::*;
+use solana_keccak_hasher as keccak;
+
+/// Endpoint-wide settings, at the address the controller derives as the
+/// endpoint config
+#[account]
+pub struct EndpointSettings {
+    /// May verify packets and change fees
+    pub admin: Pubkey,
+    /// Endpoint ID of this chain
+    pub eid: u32,
+    /// Flat native fee charged per send
+    pub native_fee: u64,
+    /// Flat fee when paying in LZ token
+    pub lz_token_fee: u64,
+    pub bump: u8,
+}
+
+impl EndpointSettings {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // admin
+        4 + // eid
+        8 + // native_fee
+        8 + // lz_token_fee
+        1; // bump
+
+    pub const SEEDS: &'static [u8] = b"EndpointConfig";
+
+    pub fn find_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEEDS], &crate::ID)
+    }
+
+    /// Fee of sending a message
+    pub fn quote(&self, pay_in_lz_token: bool) -> MessagingFee {
+        MessagingFee {
+            native_fee: self.native_fee,
+            lz_token_fee: if pay_in_lz_token { self.lz_token_fee } else { 0 },
+        }
+    }
+}
+
+/// An OApp registered with `register_oapp`
+#[account]
+pub struct OAppRegistry {
+    /// May configure the OApp on the endpoint
+    pub delegate: Pubkey,
+    pub bump: u8,
+}
+
+impl OAppRegistry {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // delegate
+        1; // bump
+
+    pub const SEEDS: &'static [u8] = b"OApp";
+
+    pub fn find_pda(oapp: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEEDS, oapp.as_ref()], &crate::ID)
+    }
+}
+
+/// Nonces of one path between a local OApp and a remote peer
+#[account]
+pub struct Nonce {
+    /// Highest nonce verified from the remote peer
+    pub inbound_nonce: u64,
+    /// Last nonce sent to the remote peer
+    pub outbound_nonce: u64,
+    pub bump: u8,
+}
+
+impl Nonce {
+    pub const LEN: usize = 8 + // discriminator
+        8 + // inbound_nonce
+        8 + // outbound_nonce
+        1; // bump
+
+    pub const SEEDS: &'static [u8] = b"Nonce";
+
+    pub fn find_pda(local_oapp: &Pubkey, remote_eid: u32, remote_oapp: &[u8; 32]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[Self::SEEDS, local_oapp.as_ref(), &remote_eid.to_be_bytes(), remote_oapp],
+            &crate::ID,
+        )
+    }
+
+    /// Record a verified inbound nonce
+    ///
+    /// Packets are verified in order; the receiver may still clear them in
+    /// any order.
+    pub fn verify(&mut self, nonce: u64) -> Result<()> {
+        if self.inbound_nonce.checked_add(1) != Some(nonce) {
+            return Err(crate::error::ErrorCode::InvalidNonce.into());
+        }
+        self.inbound_nonce = nonce;
+        Ok(())
+    }
+
+    /// Take the next outbound nonce
+    pub fn next_outbound(&mut self) -> Result<u64> {
+        self.outbound_nonce = self
+            .outbound_nonce
+            .checked_add(1)
+            .ok_or(crate::error::ErrorCode::InvalidNonce)?;
+        Ok(self.outbound_nonce)
+    }
+}
+
+/// A verified packet, waiting for its receiver to clear it
+#[account]
+pub struct PayloadHash {
+    pub receiver: Pubkey,
+    pub src_eid: u32,
+    pub sender: [u8; 32],
+    pub nonce: u64,
+    /// `payload_hash(guid, message)` of the verified packet
+    pub hash: [u8; 32],
+    /// Set once the receiver cleared the packet
+    pub cleared: bool,
+    pub bump: u8,
+}
+
+impl PayloadHash {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // receiver
+        4 + // src_eid
+        32 + // sender
+        8 + // nonce
+        32 + // hash
+        1 + // cleared
+        1; // bump
+
+    pub const SEEDS: &'static [u8] = b"PayloadHash";
+
+    pub fn find_pda(guid: &[u8; 32]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEEDS, guid], &crate::ID)
+    }
+}
+
+/// A compose message queued with `send_compose`
+#[account]
+pub struct ComposeQueue {
+    /// OApp that queued the compose
+    pub from: Pubkey,
+    /// `payload_hash(guid, message)` of the compose message
+    pub hash: [u8; 32],
+    /// Set once the recipient cleared the compose
+    pub received: bool,
+    pub bump: u8,
+}
+
+impl ComposeQueue {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // from
+        32 + // hash
+        1 + // received
+        1; // bump
+
+    pub const SEEDS: &'static [u8] = b"ComposeQueue";
+
+    pub fn find_pda(to: &Pubkey, guid: &[u8; 32], index: u16) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[Self::SEEDS, to.as_ref(), guid, &index.to_be_bytes()],
+            &crate::ID,
+        )
+    }
+}
+
+/// Library the endpoint accepts an OApp's packets from
+///
+/// The mock accepts every library, so the account only has to be at the
+/// right address.
+pub struct ReceiveLibrary;
+
+impl ReceiveLibrary {
+    pub const SEEDS: &'static [u8] = b"ReceiveLibrary";
+
+    pub fn find_pda(receiver: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEEDS, receiver.as_ref()], &crate::ID)
+    }
+}
+
+/// Library the endpoint sends an OApp's packets with
+///
+/// Like `ReceiveLibrary`, only its address is checked.
+pub struct SendLibraryConfig;
+
+impl SendLibraryConfig {
+    pub const SEEDS: &'static [u8] = b"SendLibraryConfig";
+
+    pub fn find_pda(sender: &Pubkey, dst_eid: u32) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[Self::SEEDS, sender.as_ref(), &dst_eid.to_be_bytes()],
+            &crate::ID,
+        )
+    }
+}
+
+/// Fee of sending a message, returned by `quote`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MessagingFee {
+    pub native_fee: u64,
+    pub lz_token_fee: u64,
+}
+
+/// What `send` returns
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MessagingReceipt {
+    pub guid: [u8; 32],
+    pub nonce: u64,
+    pub fee: MessagingFee,
+}
+
+/// Hash the endpoint keeps for a packet, `keccak256(guid || message)`
+pub fn payload_hash(guid: &[u8; 32], message: &[u8]) -> [u8; 32] {
+    keccak::hashv(&[guid, message]).to_bytes()
+}
+
+/// Guid of an outbound packet, as the LayerZero V2 endpoints compute it
+pub fn guid(nonce: u64, src_eid: u32, sender: &Pubkey, dst_eid: u32, receiver: &[u8; 32]) -> [u8; 32] {
+    keccak::hashv(&[
+        &nonce.to_be_bytes(),
+        &src_eid.to_be_bytes(),
+        sender.as_ref(),
+        &dst_eid.to_be_bytes(),
+        receiver,
+    ])
+    .to_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nonce() -> Nonce {
+        Nonce { inbound_nonce: 0, outbound_nonce: 0, bump: 255 }
+    }
+
+    #[test]
+    fn test_nonces_are_verified_in_order() {
+        let mut path = nonce();
+        assert!(path.verify(2).is_err());
+        path.verify(1).unwrap();
+        path.verify(2).unwrap();
+        assert!(path.verify(2).is_err());
+        assert_eq!(path.inbound_nonce, 2);
+        assert_eq!(path.outbound_nonce, 0);
+    }
+
+    #[test]
+    fn test_outbound_nonces_increase() {
+        let mut path = nonce();
+        assert_eq!(path.next_outbound().unwrap(), 1);
+        assert_eq!(path.next_outbound().unwrap(), 2);
+
+        path.outbound_nonce = u64::MAX;
+        assert!(path.next_outbound().is_err());
+    }
+
+    #[test]
+    fn test_payload_hash_covers_guid_and_message() {
+        let hash = payload_hash(&[1; 32], b"message");
+        assert_ne!(hash, payload_hash(&[2; 32], b"message"));
+        assert_ne!(hash, payload_hash(&[1; 32], b"messagf"));
+        assert_eq!(hash, keccak::hash(&[&[1u8; 32][..], b"message"].concat()).to_bytes());
+    }
+
+    #[test]
+    fn test_quote() {
+        let settings = EndpointSettings {
+            admin: Pubkey::default(),
+            eid: 40168,
+            native_fee: 5_000,
+            lz_token_fee: 7,
+            bump: 255,
+        };
+        assert_eq!(settings.quote(false), MessagingFee { native_fee: 5_000, lz_token_fee: 0 });
+        assert_eq!(settings.quote(true), MessagingFee { native_fee: 5_000, lz_token_fee: 7 });
+    }
+}
@@ -0,0 +1,219 @@
+//! The controller's endpoint CPIs must reach the mock unchanged: same
+//! discriminators, same Borsh layout and the same account addresses, in the
+//! order and with the writability the mock's instructions expect.
+
+use anchor_lang::prelude::*;
+use anchor_lang::{Discriminator, InstructionData};
+use mock_endpoint::state::*;
+use omnichain_controller::cpi::endpoint::{self, EndpointInstruction};
+use omnichain_controller::instructions::{LzAccount, ReceiveExtras, ReceiveLayout, COMPOSE_INDEX};
+use omnichain_controller::{OAppStore, ReceiptConfig};
+
+const STORE: Pubkey = Pubkey::new_from_array([8; 32]);
+const SRC_EID: u32 = 30101;
+const SENDER: [u8; 32] = [1; 32];
+const GUID: [u8; 32] = [2; 32];
+
+/// Split controller instruction data into its discriminator and body
+fn split<T: EndpointInstruction>(params: &T) -> (Vec<u8>, Vec<u8>) {
+    let mut data = params.data();
+    let body = data.split_off(8);
+    (data, body)
+}
+
+fn layout(extras: ReceiveExtras) -> ReceiveLayout {
+    ReceiveLayout::new(&mock_endpoint::ID, &STORE, SRC_EID, &SENDER, &GUID, extras)
+}
+
+/// Compare the controller's accounts with the mock's metas
+fn assert_same_accounts(controller: &[&LzAccount], mock: &[AccountMeta]) {
+    assert_eq!(controller.len(), mock.len());
+    for (ours, theirs) in controller.iter().zip(mock) {
+        // A signing default pubkey is the executor's slot
+        if ours.pubkey != Pubkey::default() || !ours.is_signer {
+            assert_eq!(ours.pubkey, theirs.pubkey);
+        }
+        assert_eq!(ours.is_writable, theirs.is_writable, "{}", theirs.pubkey);
+    }
+}
+
+#[test]
+fn test_clear_matches_mock() {
+    let message = b"message".to_vec();
+    let (discriminator, body) = split(&endpoint::ClearParams {
+        receiver: STORE,
+        src_eid: SRC_EID,
+        sender: SENDER,
+        nonce: 7,
+        guid: GUID,
+        message: &message,
+    });
+    assert_eq!(discriminator, mock_endpoint::instruction::Clear::DISCRIMINATOR);
+    let params = mock_endpoint::instruction::Clear::try_from_slice(&body).unwrap().params;
+    assert_eq!(params.receiver, STORE);
+    assert_eq!(params.nonce, 7);
+    assert_eq!(params.message, message);
+
+    let mock = mock_endpoint::accounts::Clear {
+        receiver: STORE,
+        endpoint_config: EndpointSettings::find_pda().0,
+        receive_library: ReceiveLibrary::find_pda(&STORE).0,
+        nonce: Nonce::find_pda(&STORE, SRC_EID, &SENDER).0,
+        payload_hash: PayloadHash::find_pda(&GUID).0,
+    }
+    .to_account_metas(None);
+    let layout = layout(ReceiveExtras::None);
+    let ours: Vec<_> = layout.accounts().collect();
+    assert_same_accounts(&ours, &mock[1..]);
+}
+
+#[test]
+fn test_send_compose_matches_mock() {
+    let (discriminator, body) = split(&endpoint::SendComposeParams {
+        to: STORE,
+        guid: GUID,
+        index: COMPOSE_INDEX,
+        message: b"compose",
+    });
+    assert_eq!(discriminator, mock_endpoint::instruction::SendCompose::DISCRIMINATOR);
+    let params = mock_endpoint::instruction::SendCompose::try_from_slice(&body).unwrap().params;
+    assert_eq!((params.to, params.index), (STORE, COMPOSE_INDEX));
+
+    let mock = mock_endpoint::accounts::SendCompose {
+        from: STORE,
+        payer: Pubkey::new_unique(),
+        compose_queue: ComposeQueue::find_pda(&STORE, &GUID, COMPOSE_INDEX).0,
+        system_program: anchor_lang::system_program::ID,
+    }
+    .to_account_metas(None);
+    let layout = layout(ReceiveExtras::Compose);
+    let extras: Vec<_> = layout.accounts().skip(ReceiveLayout::CLEAR_ACCOUNTS).collect();
+    assert_same_accounts(&extras, &mock[1..]);
+}
+
+#[test]
+fn test_clear_compose_matches_mock() {
+    let (discriminator, body) = split(&endpoint::ClearComposeParams {
+        from: STORE,
+        guid: GUID,
+        index: COMPOSE_INDEX,
+        message: b"compose",
+    });
+    assert_eq!(discriminator, mock_endpoint::instruction::ClearCompose::DISCRIMINATOR);
+    let params = mock_endpoint::instruction::ClearCompose::try_from_slice(&body).unwrap().params;
+    assert_eq!(params.from, STORE);
+    assert_eq!(params.message, b"compose");
+}
+
+#[test]
+fn test_set_delegate_matches_mock() {
+    let delegate = Pubkey::new_unique();
+    let (discriminator, body) = split(&endpoint::SetDelegateParams { delegate });
+    assert_eq!(discriminator, mock_endpoint::instruction::SetDelegate::DISCRIMINATOR);
+    let params = mock_endpoint::instruction::SetDelegate::try_from_slice(&body).unwrap().params;
+    assert_eq!(params.delegate, delegate);
+
+    let mock = mock_endpoint::accounts::SetDelegate {
+        oapp: STORE,
+        oapp_registry: OAppRegistry::find_pda(&STORE).0,
+    }
+    .to_account_metas(None);
+    let layout = layout(ReceiveExtras::SetDelegate);
+    let extras: Vec<_> = layout.accounts().skip(ReceiveLayout::CLEAR_ACCOUNTS).collect();
+    assert_same_accounts(&extras, &mock[1..]);
+}
+
+#[test]
+fn test_register_oapp_matches_mock() {
+    let delegate = Pubkey::new_unique();
+    let (discriminator, body) = split(&endpoint::RegisterOAppParams { delegate });
+    assert_eq!(discriminator, mock_endpoint::instruction::RegisterOapp::DISCRIMINATOR);
+    let params = mock_endpoint::instruction::RegisterOapp::try_from_slice(&body).unwrap().params;
+    assert_eq!(params.delegate, delegate);
+}
+
+#[test]
+fn test_receipt_send_matches_mock() {
+    let (discriminator, body) = split(&endpoint::SendParams {
+        dst_eid: SRC_EID,
+        receiver: SENDER,
+        message: b"receipt",
+        options: &[],
+        native_fee: 5_000,
+        lz_token_fee: 0,
+    });
+    assert_eq!(discriminator, mock_endpoint::instruction::Send::DISCRIMINATOR);
+    let params = mock_endpoint::instruction::Send::try_from_slice(&body).unwrap().params;
+    assert_eq!((params.dst_eid, params.receiver, params.native_fee), (SRC_EID, SENDER, 5_000));
+
+    // An all-zero store decodes with empty strings and no pending admin
+    let mut store = OAppStore::deserialize(&mut &[0u8; OAppStore::LEN][..]).unwrap();
+    store.endpoint = mock_endpoint::ID;
+    store.send_receipts = true;
+
+    let mock = mock_endpoint::accounts::Send {
+        sender: STORE,
+        fee_payer: Pubkey::new_unique(),
+        endpoint_config: EndpointSettings::find_pda().0,
+        send_library: SendLibraryConfig::find_pda(&STORE, SRC_EID).0,
+        nonce: Nonce::find_pda(&STORE, SRC_EID, &SENDER).0,
+    }
+    .to_account_metas(None);
+    // The admin configures the accounts the mock's send takes after the
+    // sender and the fee payer
+    let config = ReceiptConfig {
+        store: STORE,
+        dst_eid: SRC_EID,
+        send_accounts: mock[2..]
+            .iter()
+            .map(|meta| LzAccount {
+                pubkey: meta.pubkey,
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            })
+            .collect(),
+        bump: 255,
+    };
+    let layout = layout(ReceiveExtras::None)
+        .with_receipt(&store, &STORE, SRC_EID, Some(&config))
+        .unwrap();
+    // The receipt group is the tree, the fee vault and the enforced options,
+    // then the send accounts
+    let send: Vec<_> = layout.accounts().skip(ReceiveLayout::CLEAR_ACCOUNTS + 3).collect();
+    assert_same_accounts(&send, &mock[2..]);
+}
+
+#[test]
+fn test_quote_matches_mock() {
+    let (discriminator, body) = split(&endpoint::QuoteParams {
+        sender: STORE,
+        dst_eid: SRC_EID,
+        receiver: SENDER,
+        message: b"message",
+        options: &[0, 3],
+        pay_in_lz_token: true,
+    });
+    assert_eq!(discriminator, mock_endpoint::instruction::Quote::DISCRIMINATOR);
+    let params = mock_endpoint::instruction::Quote::try_from_slice(&body).unwrap().params;
+    assert_eq!(params.options, [0, 3]);
+    assert!(params.pay_in_lz_token);
+
+    // The controller parses the fee the mock returns
+    let fee = MessagingFee { native_fee: 5_000, lz_token_fee: 7 };
+    let parsed = endpoint::MessagingFee::from_return_data(
+        &mock_endpoint::ID,
+        Some((mock_endpoint::ID, fee.try_to_vec().unwrap())),
+    )
+    .unwrap();
+    assert_eq!((parsed.native_fee, parsed.lz_token_fee), (5_000, 7));
+}
+
+#[test]
+fn test_instruction_data_round_trips() {
+    // The mock's own instruction data is what the controller builds
+    let data = mock_endpoint::instruction::SetDelegate {
+        params: mock_endpoint::SetDelegateParams { delegate: STORE },
+    }
+    .data();
+    assert_eq!(data, endpoint::SetDelegateParams { delegate: STORE }.data());
+}
@@ -0,0 +1,630 @@
+//! Packets delivered through the mock endpoint the way the executor does:
+//! the endpoint admin verifies a packet, `lz_receive_types` lists the
+//! accounts and `lz_receive` clears the packet before running it.
+
+mod harness;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::system_program;
+use anchor_lang::{Discriminator, InstructionData};
+use harness::{Account, Runtime, UNIX_TIMESTAMP};
+use mock_endpoint::state::*;
+use omnichain_controller::instructions::lz_receive::COMPOSE_INDEX;
+use omnichain_controller::instructions::{DaoConfigV1, LzAccount, LzReceiveParams, OAppStoreV1, PeerConfigV1};
+use omnichain_controller::state::oapp_store::TreeConfig;
+use omnichain_controller::{
+    CollectionMetadata, ComposePayload, DeliveryMode, FailedMessage, LzComposeTypes, LzReceiveTypes, MessageCodec,
+    OAppStore, PeerConfig,
+};
+
+const SRC_EID: u32 = 30101;
+const DAO: [u8; 20] = [7; 20];
+const SOL: u64 = 1_000_000_000;
+
+/// The DAO's address, left-padded like every EVM peer
+fn peer() -> [u8; 32] {
+    let mut peer = [0; 32];
+    peer[12..].copy_from_slice(&DAO);
+    peer
+}
+
+struct Cluster {
+    runtime: Runtime,
+    admin: Pubkey,
+    executor: Pubkey,
+    store: Pubkey,
+}
+
+/// Endpoint and controller set up with the DAO as a trusted peer
+fn setup() -> Cluster {
+    let mut runtime = Runtime::new();
+    let admin = Pubkey::new_unique();
+    let executor = Pubkey::new_unique();
+    runtime.fund(&admin, 10 * SOL);
+    runtime.fund(&executor, SOL);
+
+    let endpoint_config = EndpointSettings::find_pda().0;
+    runtime
+        .process(&Instruction {
+            program_id: mock_endpoint::ID,
+            accounts: mock_endpoint::accounts::InitEndpoint {
+                endpoint_config,
+                admin,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: mock_endpoint::instruction::InitEndpoint {
+                params: mock_endpoint::InitEndpointParams {
+                    eid: 30168,
+                    native_fee: 0,
+                    lz_token_fee: 0,
+                },
+            }
+            .data(),
+        })
+        .unwrap();
+
+    let store = OAppStore::find_pda().0;
+    let mut accounts = omnichain_controller::accounts::InitOAppStore {
+        store,
+        lz_receive_types: LzReceiveTypes::find_pda(&store).0,
+        lz_compose_types: LzComposeTypes::find_pda(&store).0,
+        admin,
+        endpoint: mock_endpoint::ID,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    // What the endpoint's register_oapp takes after the payer and the OApp
+    accounts.extend([
+        AccountMeta::new(OAppRegistry::find_pda(&store).0, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+    ]);
+    runtime
+        .process(&Instruction {
+            program_id: omnichain_controller::ID,
+            accounts,
+            data: omnichain_controller::instruction::InitOappStore {
+                params: omnichain_controller::InitOAppStoreParams {
+                    endpoint: mock_endpoint::ID,
+                    delegate: admin,
+                    authorized_dao: DAO,
+                    ethereum_eid: SRC_EID,
+                    collection_metadata: CollectionMetadata {
+                        name: "Collection".to_string(),
+                        symbol: "COLL".to_string(),
+                        uri: "https://example.com/collection.json".to_string(),
+                        mint_authority: admin,
+                        update_authority: admin,
+                        tree_config: TreeConfig {
+                            max_depth: 14,
+                            max_buffer_size: 64,
+                            tree_creator: admin,
+                            tree_delegate: admin,
+                            merkle_tree: Pubkey::default(),
+                        },
+                    },
+                },
+            }
+            .data(),
+        })
+        .unwrap();
+
+    runtime.process(&set_peer(&store, &admin, peer())).unwrap();
+
+    runtime
+        .process(&Instruction {
+            program_id: mock_endpoint::ID,
+            accounts: mock_endpoint::accounts::InitNonce {
+                payer: admin,
+                nonce: Nonce::find_pda(&store, SRC_EID, &peer()).0,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: mock_endpoint::instruction::InitNonce {
+                _params: mock_endpoint::InitNonceParams {
+                    local_oapp: store,
+                    remote_eid: SRC_EID,
+                    remote_oapp: peer(),
+                },
+            }
+            .data(),
+        })
+        .unwrap();
+
+    Cluster {
+        runtime,
+        admin,
+        executor,
+        store,
+    }
+}
+
+/// `set_peer` for the DAO's endpoint, with ordered delivery
+fn set_peer(store: &Pubkey, admin: &Pubkey, peer_address: [u8; 32]) -> Instruction {
+    Instruction {
+        program_id: omnichain_controller::ID,
+        accounts: omnichain_controller::accounts::SetPeer {
+            peer_config: PeerConfig::find_pda(store, SRC_EID).0,
+            store: *store,
+            payer: *admin,
+            admin: *admin,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: omnichain_controller::instruction::SetPeer {
+            src_eid: SRC_EID,
+            peer_address,
+            delivery_mode: DeliveryMode::Ordered,
+        }
+        .data(),
+    }
+}
+
+impl Cluster {
+    fn params(&self, nonce: u64, guid: [u8; 32], message: &[u8]) -> LzReceiveParams {
+        LzReceiveParams {
+            src_eid: SRC_EID,
+            sender: peer(),
+            nonce,
+            guid,
+            message: message.to_vec(),
+        }
+    }
+
+    /// Verify a packet as the endpoint admin
+    fn verify(&mut self, params: &LzReceiveParams) {
+        let instruction = Instruction {
+            program_id: mock_endpoint::ID,
+            accounts: mock_endpoint::accounts::Verify {
+                endpoint_config: EndpointSettings::find_pda().0,
+                admin: self.admin,
+                nonce: Nonce::find_pda(&self.store, SRC_EID, &params.sender).0,
+                payload_hash: PayloadHash::find_pda(&params.guid).0,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: mock_endpoint::instruction::Verify {
+                params: mock_endpoint::VerifyParams {
+                    receiver: self.store,
+                    src_eid: params.src_eid,
+                    sender: params.sender,
+                    nonce: params.nonce,
+                    guid: params.guid,
+                    message: params.message.clone(),
+                },
+            }
+            .data(),
+        };
+        self.runtime.process(&instruction).unwrap();
+    }
+
+    /// Build `lz_receive` from the accounts `lz_receive_types` returns
+    fn lz_receive(&self, params: &LzReceiveParams) -> Instruction {
+        let types = Instruction {
+            program_id: omnichain_controller::ID,
+            accounts: omnichain_controller::accounts::LzReceiveTypesContext {
+                store: self.store,
+                lz_receive_types: LzReceiveTypes::find_pda(&self.store).0,
+                receipt_config: None,
+            }
+            .to_account_metas(None),
+            data: omnichain_controller::instruction::LzReceiveTypes { params: params.clone() }.data(),
+        };
+        let return_data = self.runtime.simulate(&types).unwrap().expect("no accounts returned");
+        let accounts = Vec::<LzAccount>::try_from_slice(&return_data).unwrap();
+
+        Instruction {
+            program_id: omnichain_controller::ID,
+            accounts: accounts
+                .into_iter()
+                .map(|account| {
+                    // A signing default pubkey is the executor's slot
+                    if account.pubkey == Pubkey::default() && account.is_signer {
+                        AccountMeta::new(self.executor, true)
+                    } else if account.is_writable {
+                        AccountMeta::new(account.pubkey, account.is_signer)
+                    } else {
+                        AccountMeta::new_readonly(account.pubkey, account.is_signer)
+                    }
+                })
+                .collect(),
+            data: omnichain_controller::instruction::LzReceive {
+                src_eid: params.src_eid,
+                sender: params.sender,
+                nonce: params.nonce,
+                guid: params.guid,
+                message: params.message.clone(),
+            }
+            .data(),
+        }
+    }
+
+    fn payload_hash(&self, guid: &[u8; 32]) -> PayloadHash {
+        self.runtime.get(&PayloadHash::find_pda(guid).0)
+    }
+
+    fn store(&self) -> OAppStore {
+        self.runtime.get(&self.store)
+    }
+
+    fn peer_config(&self) -> PeerConfig {
+        self.runtime.get(&PeerConfig::find_pda(&self.store, SRC_EID).0)
+    }
+
+    /// Store failed messages instead of failing `lz_receive`
+    fn enable_failed_messages(&mut self) {
+        let instruction = Instruction {
+            program_id: omnichain_controller::ID,
+            accounts: omnichain_controller::accounts::SetFailedMessageMode {
+                store: self.store,
+                admin: self.admin,
+            }
+            .to_account_metas(None),
+            data: omnichain_controller::instruction::SetFailedMessageMode { enabled: true }.data(),
+        };
+        self.runtime.process(&instruction).unwrap();
+    }
+
+    /// `migrate` as the admin, with `peers` as the old peers
+    fn migrate(&self, peers: &[Pubkey]) -> Instruction {
+        let mut accounts = omnichain_controller::accounts::Migrate {
+            store: self.store,
+            admin: self.admin,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        accounts.extend(peers.iter().map(|peer| AccountMeta::new(*peer, false)));
+        Instruction {
+            program_id: omnichain_controller::ID,
+            accounts,
+            data: omnichain_controller::instruction::Migrate {}.data(),
+        }
+    }
+
+    /// `set_delegate` as the admin, with `registry` as the endpoint account
+    fn set_delegate(&self, delegate: Pubkey, registry: Pubkey) -> Instruction {
+        let mut accounts = omnichain_controller::accounts::SetDelegate {
+            store: self.store,
+            admin: self.admin,
+            endpoint: mock_endpoint::ID,
+            event_authority: Pubkey::find_program_address(&[b"__event_authority"], &omnichain_controller::ID).0,
+            program: omnichain_controller::ID,
+        }
+        .to_account_metas(None);
+        accounts.push(AccountMeta::new(registry, false));
+        Instruction {
+            program_id: omnichain_controller::ID,
+            accounts,
+            data: omnichain_controller::instruction::SetDelegate { delegate }.data(),
+        }
+    }
+}
+
+/// An emergency pause from the DAO with DAO nonce `dao_nonce`
+fn pause_message(dao_nonce: u64) -> Vec<u8> {
+    MessageCodec::encode_message(MessageCodec::COMMAND_EMERGENCY_PAUSE, dao_nonce, UNIX_TIMESTAMP, &[]).unwrap()
+}
+
+/// A compose from the DAO with DAO nonce `dao_nonce`
+fn compose_message(dao_nonce: u64) -> Vec<u8> {
+    let payload = ComposePayload {
+        compose_type: 1,
+        compose_msg: b"compose",
+    }
+    .encode();
+    MessageCodec::encode_message(MessageCodec::COMMAND_COMPOSE, dao_nonce, UNIX_TIMESTAMP, &payload).unwrap()
+}
+
+/// A controller account written in an old layout, padded to `len` the way
+/// `init` leaves it
+fn old_account(discriminator: &[u8], value: &impl AnchorSerialize, len: usize) -> Account {
+    let mut data = discriminator.to_vec();
+    value.serialize(&mut data).unwrap();
+    data.resize(len, 0);
+    Account {
+        lamports: Rent::default().minimum_balance(len),
+        data,
+        owner: omnichain_controller::ID,
+        executable: false,
+    }
+}
+
+fn controller_error(error: omnichain_controller::error::ErrorCode) -> ProgramError {
+    ProgramError::Custom(error.into())
+}
+
+#[test]
+fn test_init_oapp_store_registers_with_endpoint() {
+    let cluster = setup();
+
+    let registry: OAppRegistry = cluster.runtime.get(&OAppRegistry::find_pda(&cluster.store).0);
+    assert_eq!(registry.delegate, cluster.admin);
+    assert_eq!(cluster.store().endpoint, mock_endpoint::ID);
+}
+
+#[test]
+fn test_lz_receive_clears_verified_packet() {
+    let mut cluster = setup();
+    let params = cluster.params(1, [1; 32], &pause_message(1));
+    cluster.verify(&params);
+    assert!(!cluster.payload_hash(&params.guid).cleared);
+
+    let instruction = cluster.lz_receive(&params);
+    cluster.runtime.process(&instruction).unwrap();
+
+    assert!(cluster.payload_hash(&params.guid).cleared);
+    let store = cluster.store();
+    assert!(store.paused);
+    assert_eq!((store.nonce, store.processed_messages), (1, 1));
+    assert_eq!(cluster.peer_config().inbound_nonce, 1);
+}
+
+#[test]
+fn test_lz_receive_needs_verified_packet() {
+    let cluster = setup();
+    let params = cluster.params(1, [1; 32], &pause_message(1));
+
+    // Nothing was verified, so the endpoint has no payload hash to clear
+    let instruction = cluster.lz_receive(&params);
+    let mut runtime = cluster.runtime;
+    assert!(runtime.process(&instruction).is_err());
+    assert!(runtime.account(&PayloadHash::find_pda(&params.guid).0).is_none());
+}
+
+#[test]
+fn test_replayed_packet_fails() {
+    let mut cluster = setup();
+    let params = cluster.params(1, [1; 32], &pause_message(1));
+    cluster.verify(&params);
+    let instruction = cluster.lz_receive(&params);
+    cluster.runtime.process(&instruction).unwrap();
+
+    // The endpoint refuses to clear the packet twice, which fails the
+    // transaction with the endpoint's error
+    assert_eq!(
+        cluster.runtime.process(&instruction),
+        Err(ProgramError::Custom(mock_endpoint::error::ErrorCode::AlreadyCleared.into()))
+    );
+    assert_eq!(cluster.store().processed_messages, 1);
+}
+
+#[test]
+fn test_dao_nonce_replayed_in_new_packet_fails() {
+    let mut cluster = setup();
+    let first = cluster.params(1, [1; 32], &pause_message(1));
+    cluster.verify(&first);
+    let instruction = cluster.lz_receive(&first);
+    cluster.runtime.process(&instruction).unwrap();
+
+    // The same DAO message, delivered again under a new packet
+    let second = cluster.params(2, [2; 32], &pause_message(1));
+    cluster.verify(&second);
+    let instruction = cluster.lz_receive(&second);
+    assert_eq!(
+        cluster.runtime.process(&instruction),
+        Err(controller_error(omnichain_controller::error::ErrorCode::InvalidNonce))
+    );
+
+    // The failed receive reverted the clear with it
+    assert!(!cluster.payload_hash(&second.guid).cleared);
+    assert_eq!(cluster.store().processed_messages, 1);
+}
+
+#[test]
+fn test_compose_is_queued_for_lz_compose() {
+    let mut cluster = setup();
+    let params = cluster.params(1, [1; 32], &compose_message(1));
+    cluster.verify(&params);
+
+    let instruction = cluster.lz_receive(&params);
+    cluster.runtime.process(&instruction).unwrap();
+
+    let queue: ComposeQueue = cluster
+        .runtime
+        .get(&ComposeQueue::find_pda(&cluster.store, &params.guid, COMPOSE_INDEX).0);
+    assert_eq!(queue.from, cluster.store);
+    assert!(!queue.received);
+}
+
+#[test]
+fn test_failed_compose_cpi_is_not_stored() {
+    let mut cluster = setup();
+    cluster.enable_failed_messages();
+    // Passes every check, then cannot pay for the compose queue
+    cluster.executor = Pubkey::new_unique();
+    let params = cluster.params(1, [1; 32], &compose_message(1));
+    cluster.verify(&params);
+
+    let instruction = cluster.lz_receive(&params);
+    assert_eq!(cluster.runtime.process(&instruction), Err(ProgramError::InsufficientFunds));
+
+    // The whole transaction failed, so the packet can be delivered again
+    let failed_message = FailedMessage::find_pda(&cluster.store, &params.guid).0;
+    assert!(cluster.runtime.account(&failed_message).is_none());
+    assert!(!cluster.payload_hash(&params.guid).cleared);
+    assert_eq!(cluster.store().processed_messages, 0);
+}
+
+#[test]
+fn test_compose_into_occupied_queue_is_stored() {
+    let mut cluster = setup();
+    cluster.enable_failed_messages();
+    let params = cluster.params(1, [1; 32], &compose_message(1));
+    cluster.verify(&params);
+    let queue = ComposeQueue::find_pda(&cluster.store, &params.guid, COMPOSE_INDEX).0;
+    let occupied = Account {
+        lamports: 1,
+        data: vec![1; ComposeQueue::LEN],
+        owner: mock_endpoint::ID,
+        executable: false,
+    };
+    cluster.runtime.set_account(&queue, occupied);
+
+    let instruction = cluster.lz_receive(&params);
+    cluster.runtime.process(&instruction).unwrap();
+
+    let failed_message: FailedMessage = cluster
+        .runtime
+        .get(&FailedMessage::find_pda(&cluster.store, &params.guid).0);
+    assert_eq!(
+        failed_message.error_code,
+        u32::from(omnichain_controller::error::ErrorCode::ComposeAlreadyQueued)
+    );
+    assert!(cluster.payload_hash(&params.guid).cleared);
+}
+
+#[test]
+fn test_set_delegate_updates_endpoint() {
+    let mut cluster = setup();
+    let delegate = Pubkey::new_unique();
+    let registry = OAppRegistry::find_pda(&cluster.store).0;
+
+    let instruction = cluster.set_delegate(delegate, registry);
+    cluster.runtime.process(&instruction).unwrap();
+
+    let registry: OAppRegistry = cluster.runtime.get(&registry);
+    assert_eq!(registry.delegate, delegate);
+    assert_eq!(cluster.store().delegate, delegate);
+}
+
+#[test]
+fn test_set_delegate_needs_registered_oapp() {
+    let cluster = setup();
+
+    // Refused before the endpoint is called
+    let instruction = cluster.set_delegate(Pubkey::new_unique(), Pubkey::new_unique());
+    let mut runtime = cluster.runtime;
+    assert_eq!(
+        runtime.process(&instruction),
+        Err(controller_error(omnichain_controller::error::ErrorCode::OAppNotRegistered))
+    );
+}
+
+#[test]
+fn test_set_same_peer_keeps_nonces() {
+    let mut cluster = setup();
+    let params = cluster.params(1, [1; 32], &pause_message(1));
+    cluster.verify(&params);
+    let instruction = cluster.lz_receive(&params);
+    cluster.runtime.process(&instruction).unwrap();
+
+    let instruction = set_peer(&cluster.store, &cluster.admin, peer());
+    cluster.runtime.process(&instruction).unwrap();
+
+    let peer_config = cluster.peer_config();
+    assert_eq!((peer_config.inbound_nonce, peer_config.dao_nonce), (1, 1));
+}
+
+#[test]
+fn test_rotated_peer_starts_over() {
+    let mut cluster = setup();
+    let params = cluster.params(1, [1; 32], &pause_message(1));
+    cluster.verify(&params);
+    let instruction = cluster.lz_receive(&params);
+    cluster.runtime.process(&instruction).unwrap();
+
+    let mut rotated = peer();
+    rotated[31] ^= 1;
+    let instruction = set_peer(&cluster.store, &cluster.admin, rotated);
+    cluster.runtime.process(&instruction).unwrap();
+
+    let peer_config = cluster.peer_config();
+    assert_eq!(peer_config.peer_address, rotated);
+    assert_eq!((peer_config.inbound_nonce, peer_config.dao_nonce), (0, 0));
+}
+
+#[test]
+fn test_removed_peer_is_closed() {
+    let mut cluster = setup();
+    let peer_config = PeerConfig::find_pda(&cluster.store, SRC_EID).0;
+    let instruction = Instruction {
+        program_id: omnichain_controller::ID,
+        accounts: omnichain_controller::accounts::RemovePeer {
+            peer_config,
+            store: cluster.store,
+            admin: cluster.admin,
+        }
+        .to_account_metas(None),
+        data: omnichain_controller::instruction::RemovePeer { src_eid: SRC_EID }.data(),
+    };
+    cluster.runtime.process(&instruction).unwrap();
+    assert_eq!(cluster.runtime.account(&peer_config).unwrap().lamports, 0);
+
+    // Nothing from the removed peer gets through
+    let params = cluster.params(1, [1; 32], &pause_message(1));
+    cluster.verify(&params);
+    let instruction = cluster.lz_receive(&params);
+    assert!(cluster.runtime.process(&instruction).is_err());
+    assert!(!cluster.payload_hash(&params.guid).cleared);
+}
+
+#[test]
+fn test_migrate_moves_first_layout() {
+    let mut cluster = setup();
+    let store = cluster.store();
+    let old_store = OAppStoreV1 {
+        admin: store.admin,
+        endpoint: store.endpoint,
+        delegate: store.delegate,
+        bump: store.bump,
+        collection_metadata: store.collection_metadata,
+        dao_config: DaoConfigV1 {
+            authorized_dao: DAO,
+            ethereum_eid: SRC_EID,
+            voting_period: 86400,
+            quorum: 10,
+        },
+        nonce: 3,
+        processed_messages: 3,
+    };
+    let store_account = old_account(OAppStore::DISCRIMINATOR, &old_store, OAppStoreV1::LEN);
+    cluster.runtime.set_account(&cluster.store, store_account);
+    let (peer_config, bump) = PeerConfig::find_pda(&cluster.store, SRC_EID);
+    let old_peer = PeerConfigV1 {
+        src_eid: SRC_EID,
+        peer_address: peer(),
+        trusted: true,
+        bump,
+    };
+    let peer_account = old_account(PeerConfig::DISCRIMINATOR, &old_peer, PeerConfigV1::LEN);
+    cluster.runtime.set_account(&peer_config, peer_account);
+
+    let instruction = cluster.migrate(&[peer_config]);
+    cluster.runtime.process(&instruction).unwrap();
+
+    let store = cluster.store();
+    assert_eq!(cluster.runtime.account(&cluster.store).unwrap().data.len(), OAppStore::LEN);
+    assert_eq!((store.nonce, store.processed_messages), (3, 3));
+    assert_eq!(store.dao_config.authorized_dao, DAO);
+    assert!(store.dao_config.signers.is_empty());
+    let migrated = cluster.peer_config();
+    assert_eq!(migrated.delivery_mode, DeliveryMode::Unordered);
+    // DAO nonces up to the store's last one stay executed
+    assert_eq!((migrated.dao_nonce, migrated.dao_nonce_bitmap), (3, u64::MAX));
+
+    // The migrated accounts take the next DAO message
+    let params = cluster.params(1, [1; 32], &pause_message(4));
+    cluster.verify(&params);
+    let instruction = cluster.lz_receive(&params);
+    cluster.runtime.process(&instruction).unwrap();
+    assert!(cluster.store().paused);
+
+    // Each account is migrated once
+    assert_eq!(
+        cluster.runtime.process(&cluster.migrate(&[peer_config])),
+        Err(controller_error(omnichain_controller::error::ErrorCode::InvalidMigrationAccount))
+    );
+}
+
+#[test]
+fn test_migrate_needs_admin() {
+    let mut cluster = setup();
+    cluster.admin = Pubkey::new_unique();
+    cluster.runtime.fund(&cluster.admin, SOL);
+
+    assert_eq!(
+        cluster.runtime.process(&cluster.migrate(&[])),
+        Err(controller_error(omnichain_controller::error::ErrorCode::InsufficientAuthority))
+    );
+}
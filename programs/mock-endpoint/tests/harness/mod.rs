@@ -0,0 +1,374 @@
+//! In-process runtime for the end-to-end tests
+//!
+//! Runs the controller and the mock endpoint natively through their Anchor
+//! `entry` functions, the way solana-program-test's `processor!` does. CPIs
+//! reach the syscall stubs installed here, which check the caller's signer
+//! and writable privileges (PDA signers included), then dispatch to the
+//! callee. The system program instructions Anchor's `init` makes are
+//! emulated. An instruction that fails leaves every account untouched.
+//!
+//! Where it differs from the real runtime:
+//!
+//! - A failed CPI fails the instruction with the callee's error, as on chain,
+//!   but the caller still gets the error back and runs on until it returns.
+//! - Nothing checks rent exemption or account sizes, beyond the growth
+//!   limit `realloc` checks itself.
+//! - Nothing checks that only an account's owner changed its data or
+//!   lamports, or that lamports balance across the instruction.
+//! - Loaders, compute units and the CPI depth limit are not modelled.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Once;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE, SUCCESS};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_stubs::{self, SyscallStubs};
+use anchor_lang::system_program;
+
+/// Time every instruction runs at
+pub const UNIX_TIMESTAMP: i64 = 1_750_000_000;
+
+type Entry = for<'info> fn(&Pubkey, &'info [AccountInfo<'info>], &[u8]) -> ProgramResult;
+
+thread_local! {
+    /// Programs of the running instruction, innermost CPI last
+    static CALL_STACK: RefCell<Vec<Pubkey>> = const { RefCell::new(Vec::new()) };
+    static RETURN_DATA: RefCell<Option<(Pubkey, Vec<u8>)>> = const { RefCell::new(None) };
+    /// First CPI failure of the running instruction
+    static ABORTED: RefCell<Option<ProgramError>> = const { RefCell::new(None) };
+}
+
+/// An account as stored between instructions
+#[derive(Clone, Debug, Default)]
+pub struct Account {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+/// Accounts an instruction left and the data it returned
+struct Executed {
+    accounts: HashMap<Pubkey, Account>,
+    return_data: Option<Vec<u8>>,
+}
+
+/// Accounts of a local cluster with both programs deployed
+pub struct Runtime {
+    accounts: HashMap<Pubkey, Account>,
+}
+
+impl Runtime {
+    pub fn new() -> Self {
+        static STUBS: Once = Once::new();
+        STUBS.call_once(|| {
+            program_stubs::set_syscall_stubs(Box::new(Stubs));
+        });
+
+        // Loaders are not modelled, so programs have no owner
+        let program = Account {
+            lamports: 1,
+            executable: true,
+            ..Account::default()
+        };
+        let accounts = [omnichain_controller::ID, mock_endpoint::ID, system_program::ID]
+            .into_iter()
+            .map(|program_id| (program_id, program.clone()))
+            .collect();
+        Runtime { accounts }
+    }
+
+    /// Give a system account `lamports`
+    pub fn fund(&mut self, key: &Pubkey, lamports: u64) {
+        self.accounts.entry(*key).or_default().lamports += lamports;
+    }
+
+    /// Replace the account at `key`
+    pub fn set_account(&mut self, key: &Pubkey, account: Account) {
+        self.accounts.insert(*key, account);
+    }
+
+    pub fn account(&self, key: &Pubkey) -> Option<&Account> {
+        self.accounts.get(key)
+    }
+
+    /// Deserialize the Anchor account at `key`
+    pub fn get<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+        let account = self.account(key).unwrap_or_else(|| panic!("no account {key}"));
+        T::try_deserialize(&mut &account.data[..]).unwrap()
+    }
+
+    /// Run `instruction` and keep its changes if it succeeds
+    ///
+    /// The signer flags of the instruction's accounts stand in for the
+    /// transaction signatures.
+    pub fn process(&mut self, instruction: &Instruction) -> std::result::Result<(), ProgramError> {
+        let executed = self.execute(instruction)?;
+        self.accounts.extend(executed.accounts);
+        Ok(())
+    }
+
+    /// Run `instruction` without keeping its changes and return what it
+    /// returned
+    pub fn simulate(&self, instruction: &Instruction) -> std::result::Result<Option<Vec<u8>>, ProgramError> {
+        Ok(self.execute(instruction)?.return_data)
+    }
+
+    fn execute(&self, instruction: &Instruction) -> std::result::Result<Executed, ProgramError> {
+        // An account listed twice is one account with the privileges of both
+        let mut unique: HashMap<Pubkey, AccountInfo<'static>> = HashMap::new();
+        for meta in &instruction.accounts {
+            let info = unique.entry(meta.pubkey).or_insert_with(|| {
+                let account = self.accounts.get(&meta.pubkey).cloned().unwrap_or_default();
+                account_info(meta.pubkey, account)
+            });
+            info.is_signer |= meta.is_signer;
+            info.is_writable |= meta.is_writable;
+        }
+        let infos: Vec<_> = instruction
+            .accounts
+            .iter()
+            .map(|meta| unique[&meta.pubkey].clone())
+            .collect();
+
+        RETURN_DATA.with(|return_data| return_data.take());
+        ABORTED.with(|aborted| aborted.take());
+        let result = run(&instruction.program_id, Box::leak(infos.into_boxed_slice()), &instruction.data);
+        // A failed CPI fails the instruction, whatever the caller made of it
+        if let Some(error) = ABORTED.with(|aborted| aborted.take()) {
+            return Err(error);
+        }
+        result?;
+        let return_data = RETURN_DATA.with(|return_data| return_data.take()).map(|(_, data)| data);
+
+        let accounts = unique
+            .into_iter()
+            .map(|(key, info)| {
+                let account = Account {
+                    lamports: info.lamports(),
+                    data: info.data.borrow().to_vec(),
+                    owner: *info.owner,
+                    executable: info.executable,
+                };
+                (key, account)
+            })
+            .collect();
+        Ok(Executed { accounts, return_data })
+    }
+}
+
+/// Account info over storage that lives as long as the test
+fn account_info(key: Pubkey, account: Account) -> AccountInfo<'static> {
+    AccountInfo::new(
+        serialized_key(key, account.data.len()),
+        false,
+        false,
+        Box::leak(Box::new(account.lamports)),
+        serialized_data(&account.data),
+        Box::leak(Box::new(account.owner)),
+        account.executable,
+        0,
+    )
+}
+
+/// A key preceded by the account's data length, where `realloc` reads it
+fn serialized_key(key: Pubkey, data_len: usize) -> &'static Pubkey {
+    let buffer = Box::leak(Box::new([0u8; 4 + 32]));
+    buffer[..4].copy_from_slice(&(data_len as u32).to_le_bytes());
+    buffer[4..].copy_from_slice(key.as_ref());
+    // SAFETY: `Pubkey` is 32 bytes with an alignment of 1
+    unsafe { &*(buffer[4..].as_ptr() as *const Pubkey) }
+}
+
+/// Account data laid out the way the loader serializes it: an aligned
+/// length in front, which `realloc` rewrites, and room to grow behind
+fn serialized_data(data: &[u8]) -> &'static mut [u8] {
+    let words = (8 + data.len() + MAX_PERMITTED_DATA_INCREASE).div_ceil(8);
+    let buffer = Box::leak(vec![0u64; words].into_boxed_slice());
+    buffer[0] = data.len() as u64;
+    // SAFETY: the words are leaked, so their bytes live as long as the test
+    let bytes = unsafe { std::slice::from_raw_parts_mut(buffer.as_mut_ptr() as *mut u8, words * 8) };
+    bytes[8..8 + data.len()].copy_from_slice(data);
+    &mut bytes[8..8 + data.len()]
+}
+
+fn program_entry(program_id: &Pubkey) -> Option<Entry> {
+    if *program_id == omnichain_controller::ID {
+        Some(omnichain_controller::entry)
+    } else if *program_id == mock_endpoint::ID {
+        Some(mock_endpoint::entry)
+    } else {
+        None
+    }
+}
+
+fn run<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>], data: &[u8]) -> ProgramResult {
+    if *program_id == system_program::ID {
+        return system_instruction(accounts, data);
+    }
+    let entry = program_entry(program_id).ok_or(ProgramError::IncorrectProgramId)?;
+
+    CALL_STACK.with(|stack| stack.borrow_mut().push(*program_id));
+    let result = entry(program_id, accounts, data);
+    CALL_STACK.with(|stack| stack.borrow_mut().pop());
+    result
+}
+
+/// Pass the caller's accounts on to the callee of a CPI
+///
+/// Callee and caller share the account storage, so the callee's changes are
+/// the caller's once it returns.
+fn invoke<'a>(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo<'a>],
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let caller = CALL_STACK.with(|stack| *stack.borrow().last().expect("CPI outside of a program"));
+    let signers = signers_seeds
+        .iter()
+        .map(|seeds| Pubkey::create_program_address(seeds, &caller))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|_| ProgramError::InvalidSeeds)?;
+
+    let mut accounts = Vec::with_capacity(instruction.accounts.len());
+    for meta in &instruction.accounts {
+        let mut account = account_infos
+            .iter()
+            .find(|info| info.key == &meta.pubkey)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?
+            .clone();
+        if meta.is_signer && !account.is_signer && !signers.contains(&meta.pubkey) {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if meta.is_writable && !account.is_writable {
+            return Err(ProgramError::Immutable);
+        }
+        account.is_signer = meta.is_signer;
+        account.is_writable = meta.is_writable;
+        accounts.push(account);
+    }
+    run(&instruction.program_id, Box::leak(accounts.into_boxed_slice()), &instruction.data)
+}
+
+/// The system program instructions Anchor makes
+fn system_instruction(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let u64_at = |offset: usize| {
+        data.get(offset..offset + 8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+            .ok_or(ProgramError::InvalidInstructionData)
+    };
+    let pubkey_at = |offset: usize| {
+        data.get(offset..offset + 32)
+            .map(|bytes| Pubkey::try_from(bytes).unwrap())
+            .ok_or(ProgramError::InvalidInstructionData)
+    };
+    let tag = data
+        .get(..4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(ProgramError::InvalidInstructionData)?;
+
+    match (tag, accounts) {
+        // CreateAccount { lamports, space, owner }
+        (0, [from, to, ..]) => {
+            if to.lamports() > 0 {
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            transfer(from, to, u64_at(4)?)?;
+            allocate(to, u64_at(12)? as usize)?;
+            assign(to, &pubkey_at(20)?)
+        }
+        // Assign { owner }
+        (1, [account, ..]) => assign(account, &pubkey_at(4)?),
+        // Transfer { lamports }
+        (2, [from, to, ..]) => transfer(from, to, u64_at(4)?),
+        // Allocate { space }
+        (8, [account, ..]) => allocate(account, u64_at(4)? as usize),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn transfer(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    if !from.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if from.owner != &system_program::ID || !from.data_is_empty() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let remaining = from.lamports().checked_sub(lamports).ok_or(ProgramError::InsufficientFunds)?;
+    **from.try_borrow_mut_lamports()? = remaining;
+    **to.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
+
+fn allocate(account: &AccountInfo, space: usize) -> ProgramResult {
+    if !account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if account.owner != &system_program::ID || !account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    *account.try_borrow_mut_data()? = serialized_data(&vec![0; space]);
+    Ok(())
+}
+
+fn assign(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+    if !account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if account.owner != &system_program::ID {
+        return Err(ProgramError::IllegalOwner);
+    }
+    account.assign(owner);
+    Ok(())
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let result = invoke(instruction, account_infos, signers_seeds);
+        if let Err(error) = &result {
+            ABORTED.with(|aborted| {
+                aborted.borrow_mut().get_or_insert_with(|| error.clone());
+            });
+        }
+        result
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            unix_timestamp: UNIX_TIMESTAMP,
+            ..Clock::default()
+        };
+        // SAFETY: `Clock::get` passes a `Clock` to fill
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        // SAFETY: `Rent::get` passes a `Rent` to fill
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        RETURN_DATA.with(|return_data| return_data.borrow().clone())
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        let program_id = CALL_STACK.with(|stack| *stack.borrow().last().expect("return data outside of a program"));
+        let return_data = (!data.is_empty()).then(|| (program_id, data.to_vec()));
+        RETURN_DATA.with(|cell| *cell.borrow_mut() = return_data);
+    }
+
+    fn sol_get_stack_height(&self) -> u64 {
+        CALL_STACK.with(|stack| stack.borrow().len() as u64)
+    }
+}
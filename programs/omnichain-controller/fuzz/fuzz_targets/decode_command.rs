@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../tests/harness/mod.rs"]
+mod harness;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((command, payload)) = data.split_first() {
+        harness::decode_command(*command, payload);
+    }
+});
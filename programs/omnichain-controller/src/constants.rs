@@ -6,6 +6,11 @@ pub const TREE_AUTHORITY_SEED: &[u8] = b"tree_authority";
 pub const ETHEREUM_MAINNET_EID: u32 = 101;
 pub const ETHEREUM_SEPOLIA_EID: u32 = 161;
 
+// LayerZero message library config types (see `set_config`)
+pub const CONFIG_TYPE_EXECUTOR: u32 = 1;
+pub const CONFIG_TYPE_SEND_ULN: u32 = 2;
+pub const CONFIG_TYPE_RECEIVE_ULN: u32 = 3;
+
 // Validation constants
 pub const MAX_URI_LENGTH: usize = 200;
 pub const MAX_BATCH_SIZE: usize = 100;
@@ -13,6 +18,7 @@ pub const MAX_MINT_BATCH_SIZE: usize = 50;
 pub const MAX_BURN_BATCH_SIZE: usize = 100;
 pub const MAX_TRANSFER_BATCH_SIZE: usize = 100;
 pub const MESSAGE_TIMEOUT_SECONDS: i64 = 3600; // 1 hour
+pub const ADMIN_TRANSFER_WINDOW_SECONDS: i64 = 7 * 24 * 3600; // 1 week
 
 // Metadata limits
 pub const MAX_NAME_LENGTH: usize = 32;
@@ -1,151 +1,674 @@
 use anchor_lang::prelude::*;
 
 /// CPI helper functions for LayerZero endpoint interactions
+///
+/// Instruction data is the endpoint's 8-byte Anchor discriminator followed
+/// by the Borsh parameters, as the LayerZero V2 Solana endpoint defines them.
 pub mod endpoint {
     use super::*;
+    use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+
+    /// Parameters of an endpoint instruction
+    pub trait EndpointInstruction: AnchorSerialize {
+        /// `sha256("global:<instruction name>")[..8]`
+        const DISCRIMINATOR: [u8; 8];
+
+        /// Instruction data: discriminator, then the Borsh parameters
+        fn data(&self) -> Vec<u8> {
+            let mut data = Self::DISCRIMINATOR.to_vec();
+            self.serialize(&mut data).expect("serializing to a Vec cannot fail");
+            data
+        }
+    }
+
+    /// Parameters of `register_oapp`
+    #[derive(AnchorSerialize, Clone, Copy, Debug)]
+    pub struct RegisterOAppParams {
+        pub delegate: Pubkey,
+    }
+
+    impl EndpointInstruction for RegisterOAppParams {
+        const DISCRIMINATOR: [u8; 8] = [129, 89, 71, 68, 11, 82, 210, 125];
+    }
+
+    /// Parameters of `clear`
+    ///
+    /// Borrows the message so clearing does not copy the payload.
+    #[derive(AnchorSerialize, Clone, Copy, Debug)]
+    pub struct ClearParams<'a> {
+        pub receiver: Pubkey,
+        pub src_eid: u32,
+        pub sender: [u8; 32],
+        pub nonce: u64,
+        pub guid: [u8; 32],
+        pub message: &'a [u8],
+    }
+
+    impl EndpointInstruction for ClearParams<'_> {
+        const DISCRIMINATOR: [u8; 8] = [250, 39, 28, 213, 123, 163, 133, 5];
+    }
+
+    /// Parameters of `send_compose`
+    #[derive(AnchorSerialize, Clone, Copy, Debug)]
+    pub struct SendComposeParams<'a> {
+        pub to: Pubkey,
+        pub guid: [u8; 32],
+        pub index: u16,
+        pub message: &'a [u8],
+    }
+
+    impl EndpointInstruction for SendComposeParams<'_> {
+        const DISCRIMINATOR: [u8; 8] = [75, 38, 228, 168, 43, 39, 238, 229];
+    }
+
+    /// Parameters of `clear_compose`
+    #[derive(AnchorSerialize, Clone, Copy, Debug)]
+    pub struct ClearComposeParams<'a> {
+        pub from: Pubkey,
+        pub guid: [u8; 32],
+        pub index: u16,
+        pub message: &'a [u8],
+    }
+
+    impl EndpointInstruction for ClearComposeParams<'_> {
+        const DISCRIMINATOR: [u8; 8] = [118, 1, 18, 142, 95, 175, 21, 125];
+    }
+
+    /// Parameters of `send`
+    #[derive(AnchorSerialize, Clone, Copy, Debug)]
+    pub struct SendParams<'a> {
+        pub dst_eid: u32,
+        pub receiver: [u8; 32],
+        pub message: &'a [u8],
+        pub options: &'a [u8],
+        /// Most native fee the endpoint may charge
+        pub native_fee: u64,
+        pub lz_token_fee: u64,
+    }
+
+    impl EndpointInstruction for SendParams<'_> {
+        const DISCRIMINATOR: [u8; 8] = [102, 251, 20, 187, 65, 75, 12, 69];
+    }
+
+    /// Parameters of `quote`
+    #[derive(AnchorSerialize, Clone, Copy, Debug)]
+    pub struct QuoteParams<'a> {
+        pub sender: Pubkey,
+        pub dst_eid: u32,
+        pub receiver: [u8; 32],
+        pub message: &'a [u8],
+        pub options: &'a [u8],
+        pub pay_in_lz_token: bool,
+    }
+
+    impl EndpointInstruction for QuoteParams<'_> {
+        const DISCRIMINATOR: [u8; 8] = [149, 42, 109, 247, 134, 146, 213, 123];
+    }
+
+    /// Parameters of `set_send_library`
+    ///
+    /// A default `new_lib` falls back to the endpoint's default library.
+    #[derive(AnchorSerialize, Clone, Copy, Debug)]
+    pub struct SetSendLibraryParams {
+        pub sender: Pubkey,
+        pub eid: u32,
+        pub new_lib: Pubkey,
+    }
+
+    impl EndpointInstruction for SetSendLibraryParams {
+        const DISCRIMINATOR: [u8; 8] = [251, 118, 78, 158, 134, 149, 129, 5];
+    }
+
+    /// Parameters of `set_receive_library`
+    ///
+    /// The old library stays valid for `grace_period` slots.
+    #[derive(AnchorSerialize, Clone, Copy, Debug)]
+    pub struct SetReceiveLibraryParams {
+        pub receiver: Pubkey,
+        pub eid: u32,
+        pub new_lib: Pubkey,
+        pub grace_period: u64,
+    }
+
+    impl EndpointInstruction for SetReceiveLibraryParams {
+        const DISCRIMINATOR: [u8; 8] = [223, 172, 180, 105, 165, 161, 147, 228];
+    }
+
+    /// Parameters of `set_config`
+    ///
+    /// The endpoint forwards `config` to the message library.
+    #[derive(AnchorSerialize, Clone, Copy, Debug)]
+    pub struct SetConfigParams<'a> {
+        pub oapp: Pubkey,
+        pub eid: u32,
+        pub config_type: u32,
+        pub config: &'a [u8],
+    }
+
+    impl EndpointInstruction for SetConfigParams<'_> {
+        const DISCRIMINATOR: [u8; 8] = [108, 158, 154, 175, 212, 98, 52, 66];
+    }
+
+    /// Parameters of `set_delegate`
+    #[derive(AnchorSerialize, Clone, Copy, Debug)]
+    pub struct SetDelegateParams {
+        pub delegate: Pubkey,
+    }
+
+    impl EndpointInstruction for SetDelegateParams {
+        const DISCRIMINATOR: [u8; 8] = [242, 30, 46, 76, 108, 235, 128, 181];
+    }
+
+    /// Register OApp with LayerZero endpoint - REQUIRED during initialization
+    ///
+    /// `payer` funds the OApp registry; the OApp signs with its PDA seeds.
+    pub fn register_oapp<'info>(
+        endpoint_program: &AccountInfo<'info>,
+        payer: &AccountInfo<'info>,
+        oapp: &AccountInfo<'info>,
+        accounts: &[AccountInfo<'info>],
+        oapp_signer_seeds: &[&[u8]],
+        params: &RegisterOAppParams,
+    ) -> Result<()> {
+        invoke(endpoint_program, &[payer, oapp], accounts, &[oapp_signer_seeds], params)
+    }
 
     /// Clear a message from the endpoint - CRITICAL: Must be called FIRST in lz_receive
-    pub fn clear(
-        endpoint_program: &AccountInfo,
-        accounts: &[AccountInfo],
+    ///
+    /// The OApp is the receiver and signs with its PDA seeds.
+    pub fn clear<'info>(
+        endpoint_program: &AccountInfo<'info>,
+        oapp: &AccountInfo<'info>,
+        accounts: &[AccountInfo<'info>],
         oapp_signer_seeds: &[&[u8]],
-        params: &crate::instructions::lz_receive::ClearParams,
+        params: &ClearParams,
     ) -> Result<()> {
-        // Create CPI instruction for endpoint clear
-        let mut data = Vec::new();
-        data.push(0u8); // clear instruction discriminator (LayerZero V2 endpoint)
-        data.extend_from_slice(&params.receiver.to_bytes());
-        data.extend_from_slice(&params.src_eid.to_le_bytes());
-        data.extend_from_slice(&params.sender);
-        data.extend_from_slice(&params.nonce.to_le_bytes());
-        data.extend_from_slice(&params.guid);
-        data.extend_from_slice(&(params.message.len() as u32).to_le_bytes());
-        data.extend_from_slice(&params.message);
-        
-        let instruction = anchor_lang::solana_program::instruction::Instruction {
-            program_id: *endpoint_program.key,
-            accounts: accounts.iter().map(|acc| anchor_lang::solana_program::instruction::AccountMeta {
-                pubkey: *acc.key,
-                is_signer: acc.is_signer,
-                is_writable: acc.is_writable,
-            }).collect(),
-            data,
-        };
+        invoke(endpoint_program, &[oapp], accounts, &[oapp_signer_seeds], params)
+    }
 
-        // Execute CPI with OApp signer seeds
-        anchor_lang::solana_program::program::invoke_signed(
-            &instruction,
-            accounts,
-            &[oapp_signer_seeds],
-        ).map_err(|_| crate::error::ErrorCode::EndpointCpiFailed)?;
+    /// Send a composed message through the endpoint
+    ///
+    /// The OApp sends the compose to `params.to` and signs with its PDA seeds.
+    pub fn send_compose<'info>(
+        endpoint_program: &AccountInfo<'info>,
+        oapp: &AccountInfo<'info>,
+        accounts: &[AccountInfo<'info>],
+        oapp_signer_seeds: &[&[u8]],
+        params: &SendComposeParams,
+    ) -> Result<()> {
+        invoke(endpoint_program, &[oapp], accounts, &[oapp_signer_seeds], params)
+    }
 
-        Ok(())
+    /// Clear a compose message from the endpoint before executing it
+    ///
+    /// The OApp is the compose recipient and signs with its PDA seeds.
+    pub fn clear_compose<'info>(
+        endpoint_program: &AccountInfo<'info>,
+        oapp: &AccountInfo<'info>,
+        accounts: &[AccountInfo<'info>],
+        oapp_signer_seeds: &[&[u8]],
+        params: &ClearComposeParams,
+    ) -> Result<()> {
+        invoke(endpoint_program, &[oapp], accounts, &[oapp_signer_seeds], params)
     }
 
-    /// Register OApp with LayerZero endpoint - REQUIRED during initialization
-    pub fn register_oapp(
-        endpoint_program: &AccountInfo,
-        accounts: &[AccountInfo],
+    /// Send a message to a peer through the endpoint
+    ///
+    /// The OApp signs as the sender and `fee_payer` pays up to
+    /// `params.native_fee`; both sign with the given seeds.
+    pub fn send<'info>(
+        endpoint_program: &AccountInfo<'info>,
+        oapp: &AccountInfo<'info>,
+        fee_payer: &AccountInfo<'info>,
+        accounts: &[AccountInfo<'info>],
+        signer_seeds: &[&[&[u8]]],
+        params: &SendParams,
+    ) -> Result<()> {
+        invoke(endpoint_program, &[oapp, fee_payer], accounts, signer_seeds, params)
+    }
+
+    /// Quote message fee
+    ///
+    /// The endpoint returns the fee as return data.
+    pub fn quote<'info>(
+        endpoint_program: &AccountInfo<'info>,
+        accounts: &[AccountInfo<'info>],
+        params: &QuoteParams,
+    ) -> Result<MessagingFee> {
+        invoke(endpoint_program, &[], accounts, &[], params)?;
+
+        MessagingFee::from_return_data(
+            endpoint_program.key,
+            anchor_lang::solana_program::program::get_return_data(),
+        )
+    }
+
+    /// Set the library the endpoint sends the OApp's messages with
+    pub fn set_send_library<'info>(
+        endpoint_program: &AccountInfo<'info>,
+        oapp: &AccountInfo<'info>,
+        accounts: &[AccountInfo<'info>],
         oapp_signer_seeds: &[&[u8]],
-        oapp_address: &Pubkey,
-        delegate: &Pubkey,
+        params: &SetSendLibraryParams,
     ) -> Result<()> {
-        // Create CPI instruction for OApp registration
-        let mut data = Vec::new();
-        data.push(3u8); // register_oapp instruction discriminator
-        data.extend_from_slice(&oapp_address.to_bytes());
-        data.extend_from_slice(&delegate.to_bytes());
-        
-        let instruction = anchor_lang::solana_program::instruction::Instruction {
-            program_id: *endpoint_program.key,
-            accounts: accounts.iter().map(|acc| anchor_lang::solana_program::instruction::AccountMeta {
-                pubkey: *acc.key,
-                is_signer: acc.is_signer,
-                is_writable: acc.is_writable,
-            }).collect(),
-            data,
-        };
+        invoke(endpoint_program, &[oapp], accounts, &[oapp_signer_seeds], params)
+    }
 
-        // Execute CPI with OApp signer seeds
-        anchor_lang::solana_program::program::invoke_signed(
-            &instruction,
-            accounts,
-            &[oapp_signer_seeds],
-        ).map_err(|_| crate::error::ErrorCode::EndpointCpiFailed)?;
+    /// Set the library the endpoint accepts the OApp's messages from
+    pub fn set_receive_library<'info>(
+        endpoint_program: &AccountInfo<'info>,
+        oapp: &AccountInfo<'info>,
+        accounts: &[AccountInfo<'info>],
+        oapp_signer_seeds: &[&[u8]],
+        params: &SetReceiveLibraryParams,
+    ) -> Result<()> {
+        invoke(endpoint_program, &[oapp], accounts, &[oapp_signer_seeds], params)
+    }
 
-        Ok(())
+    /// Set a message library config (ULN or executor) for one of the OApp's
+    /// paths
+    ///
+    /// The library's accounts are in `accounts`.
+    pub fn set_config<'info>(
+        endpoint_program: &AccountInfo<'info>,
+        oapp: &AccountInfo<'info>,
+        accounts: &[AccountInfo<'info>],
+        oapp_signer_seeds: &[&[u8]],
+        params: &SetConfigParams,
+    ) -> Result<()> {
+        invoke(endpoint_program, &[oapp], accounts, &[oapp_signer_seeds], params)
     }
 
-    /// Send a composed message through the endpoint
-    pub fn send_compose(
-        endpoint_program: &AccountInfo,
-        accounts: &[AccountInfo],
-        to: &[u8; 32],
-        guid: &[u8; 32],
-        index: u16,
-        message: &[u8],
+    /// Replace the delegate allowed to configure the OApp on the endpoint
+    pub fn set_delegate<'info>(
+        endpoint_program: &AccountInfo<'info>,
+        oapp: &AccountInfo<'info>,
+        accounts: &[AccountInfo<'info>],
+        oapp_signer_seeds: &[&[u8]],
+        params: &SetDelegateParams,
     ) -> Result<()> {
-        // Create compose message data
-        let mut data = Vec::new();
-        data.push(1u8); // send_compose instruction discriminator
-        data.extend_from_slice(to);
-        data.extend_from_slice(guid);
-        data.extend_from_slice(&index.to_le_bytes());
-        data.extend_from_slice(&(message.len() as u32).to_le_bytes());
-        data.extend_from_slice(message);
+        invoke(endpoint_program, &[oapp], accounts, &[oapp_signer_seeds], params)
+    }
 
-        let instruction = anchor_lang::solana_program::instruction::Instruction {
-            program_id: *endpoint_program.key,
-            accounts: accounts.iter().map(|acc| anchor_lang::solana_program::instruction::AccountMeta {
+    /// Invoke an endpoint instruction
+    ///
+    /// `signers` come first and sign, with `signer_seeds` where they are
+    /// PDAs; `accounts` follow as passed in.
+    fn invoke<'info>(
+        endpoint_program: &AccountInfo<'info>,
+        signers: &[&AccountInfo<'info>],
+        accounts: &[AccountInfo<'info>],
+        signer_seeds: &[&[&[u8]]],
+        params: &impl EndpointInstruction,
+    ) -> Result<()> {
+        let metas = signers
+            .iter()
+            .map(|signer| AccountMeta {
+                pubkey: *signer.key,
+                is_signer: true,
+                is_writable: signer.is_writable,
+            })
+            .chain(accounts.iter().map(|acc| AccountMeta {
                 pubkey: *acc.key,
                 is_signer: acc.is_signer,
                 is_writable: acc.is_writable,
-            }).collect(),
-            data,
+            }))
+            .collect();
+        let instruction = Instruction {
+            program_id: *endpoint_program.key,
+            accounts: metas,
+            data: params.data(),
         };
 
-        anchor_lang::solana_program::program::invoke(
+        let mut infos: Vec<AccountInfo<'info>> = signers.iter().map(|signer| (*signer).clone()).collect();
+        infos.extend_from_slice(accounts);
+        anchor_lang::solana_program::program::invoke_signed(
             &instruction,
-            accounts,
+            &infos,
+            signer_seeds,
         ).map_err(|_| crate::error::ErrorCode::EndpointCpiFailed)?;
 
         Ok(())
     }
 
-    /// Quote message fee
-    pub fn quote_send(
-        endpoint_program: &AccountInfo,
-        accounts: &[AccountInfo],
-        dst_eid: u32,
-        message: &[u8],
-        options: &[u8],
-    ) -> Result<u64> {
-        // Create quote instruction data
-        let mut data = Vec::new();
-        data.push(2u8); // quote_send instruction discriminator
-        data.extend_from_slice(&dst_eid.to_le_bytes());
-        data.extend_from_slice(&(message.len() as u32).to_le_bytes());
-        data.extend_from_slice(message);
-        data.extend_from_slice(&(options.len() as u32).to_le_bytes());
-        data.extend_from_slice(options);
+    /// Fee the endpoint charges to send a message
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct MessagingFee {
+        pub native_fee: u64,
+        pub lz_token_fee: u64,
+    }
 
+    impl MessagingFee {
+        /// Parse the fee from the return data of a quote
+        ///
+        /// The data must come from the endpoint and hold exactly a Borsh
+        /// `MessagingFee`.
+        pub fn from_return_data(
+            endpoint_program: &Pubkey,
+            return_data: Option<(Pubkey, Vec<u8>)>,
+        ) -> Result<Self> {
+            match return_data {
+                Some((program_id, data)) if program_id == *endpoint_program => {
+                    Self::try_from_slice(&data)
+                        .map_err(|_| crate::error::ErrorCode::InvalidQuote.into())
+                }
+                _ => Err(crate::error::ErrorCode::InvalidQuote.into()),
+            }
+        }
+    }
+}
+
+/// CPI helpers for composer programs registered with `set_composer`
+pub mod composer {
+    use super::*;
+
+    /// Arguments of a composer's `lz_compose` instruction
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+    pub struct LzComposeParams {
+        /// OApp store that queued the compose
+        pub from: Pubkey,
+        pub guid: [u8; 32],
+        pub index: u16,
+        pub compose_type: u8,
+        /// Source endpoint and peer of the packet that triggered the compose
+        pub src_eid: u32,
+        pub sender: [u8; 32],
+        pub nonce: u64,
+        pub message: Vec<u8>,
+    }
+
+    /// Anchor discriminator of the composer's `lz_compose` instruction
+    pub fn lz_compose_discriminator() -> [u8; 8] {
+        let hash = anchor_lang::solana_program::hash::hash(b"global:lz_compose");
+        hash.to_bytes()[..8].try_into().unwrap()
+    }
+
+    /// Call a composer's `lz_compose` and return the data it returned
+    ///
+    /// The OApp's compose authority signs first so the composer can check
+    /// where the call came from; `accounts` follow in order. The store itself
+    /// never signs, so a composer cannot act as the OApp on the endpoint.
+    pub fn lz_compose<'info>(
+        composer_program: &AccountInfo<'info>,
+        compose_authority: &AccountInfo<'info>,
+        accounts: &[AccountInfo<'info>],
+        authority_signer_seeds: &[&[u8]],
+        params: &LzComposeParams,
+    ) -> Result<Vec<u8>> {
+        let mut data = lz_compose_discriminator().to_vec();
+        params.serialize(&mut data)?;
+
+        let mut metas = vec![anchor_lang::solana_program::instruction::AccountMeta::new_readonly(compose_authority.key(), true)];
+        metas.extend(accounts.iter().map(|acc| anchor_lang::solana_program::instruction::AccountMeta {
+            pubkey: *acc.key,
+            is_signer: acc.is_signer,
+            is_writable: acc.is_writable,
+        }));
         let instruction = anchor_lang::solana_program::instruction::Instruction {
-            program_id: *endpoint_program.key,
-            accounts: accounts.iter().map(|acc| anchor_lang::solana_program::instruction::AccountMeta {
-                pubkey: *acc.key,
-                is_signer: acc.is_signer,
-                is_writable: acc.is_writable,
-            }).collect(),
+            program_id: *composer_program.key,
+            accounts: metas,
             data,
         };
 
-        // Execute CPI and return fee (simplified - actual implementation would parse return data)
-        anchor_lang::solana_program::program::invoke(
+        let mut infos = vec![compose_authority.clone()];
+        infos.extend_from_slice(accounts);
+        anchor_lang::solana_program::program::invoke_signed(
             &instruction,
-            accounts,
-        ).map_err(|_| crate::error::ErrorCode::EndpointCpiFailed)?;
+            &infos,
+            &[authority_signer_seeds],
+        )?;
+
+        // Return data left by anyone but the composer is not its result
+        let result = match anchor_lang::solana_program::program::get_return_data() {
+            Some((program_id, data)) if program_id == *composer_program.key => data,
+            _ => Vec::new(),
+        };
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_composer_discriminator_matches_anchor() {
+        // Composers are expected to be Anchor programs with an `lz_compose`
+        // instruction, named like this program's own
+        assert_eq!(composer::lz_compose_discriminator(), crate::instruction::LzCompose::DISCRIMINATOR);
+    }
+
+    use endpoint::EndpointInstruction;
+
+    const OAPP: Pubkey = Pubkey::new_from_array([0xAA; 32]);
+    const LIB: Pubkey = Pubkey::new_from_array([0xBB; 32]);
+
+    fn discriminator(name: &str) -> [u8; 8] {
+        let hash = anchor_lang::solana_program::hash::hash(format!("global:{name}").as_bytes());
+        hash.to_bytes()[..8].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_endpoint_discriminators_match_anchor() {
+        assert_eq!(endpoint::RegisterOAppParams::DISCRIMINATOR, discriminator("register_oapp"));
+        assert_eq!(endpoint::ClearParams::DISCRIMINATOR, discriminator("clear"));
+        assert_eq!(endpoint::SendComposeParams::DISCRIMINATOR, discriminator("send_compose"));
+        assert_eq!(endpoint::ClearComposeParams::DISCRIMINATOR, discriminator("clear_compose"));
+        assert_eq!(endpoint::SendParams::DISCRIMINATOR, discriminator("send"));
+        assert_eq!(endpoint::QuoteParams::DISCRIMINATOR, discriminator("quote"));
+        assert_eq!(endpoint::SetSendLibraryParams::DISCRIMINATOR, discriminator("set_send_library"));
+        assert_eq!(endpoint::SetReceiveLibraryParams::DISCRIMINATOR, discriminator("set_receive_library"));
+        assert_eq!(endpoint::SetConfigParams::DISCRIMINATOR, discriminator("set_config"));
+        assert_eq!(endpoint::SetDelegateParams::DISCRIMINATOR, discriminator("set_delegate"));
+    }
+
+    #[test]
+    fn test_register_oapp_data() {
+        let data = endpoint::RegisterOAppParams { delegate: LIB }.data();
+
+        let expected = [&[129, 89, 71, 68, 11, 82, 210, 125][..], &[0xBB; 32]].concat();
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn test_clear_data() {
+        let data = endpoint::ClearParams {
+            receiver: OAPP,
+            src_eid: 30101,
+            sender: [0x11; 32],
+            nonce: 0x0102030405060708,
+            guid: [0x22; 32],
+            message: &[0xde, 0xad, 0xbe],
+        }
+        .data();
+
+        let expected = [
+            &[250, 39, 28, 213, 123, 163, 133, 5][..],
+            &[0xAA; 32],
+            &[0x95, 0x75, 0x00, 0x00], // 30101
+            &[0x11; 32],
+            &[0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01],
+            &[0x22; 32],
+            &[3, 0, 0, 0, 0xde, 0xad, 0xbe],
+        ]
+        .concat();
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn test_send_compose_data() {
+        let data = endpoint::SendComposeParams {
+            to: OAPP,
+            guid: [0x22; 32],
+            index: 0x0102,
+            message: &[0xc0, 0xff, 0xee],
+        }
+        .data();
+
+        let expected = [
+            &[75, 38, 228, 168, 43, 39, 238, 229][..],
+            &[0xAA; 32],
+            &[0x22; 32],
+            &[0x02, 0x01],
+            &[3, 0, 0, 0, 0xc0, 0xff, 0xee],
+        ]
+        .concat();
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn test_clear_compose_data() {
+        let data = endpoint::ClearComposeParams {
+            from: OAPP,
+            guid: [0x22; 32],
+            index: 1,
+            message: &[],
+        }
+        .data();
+
+        let expected = [
+            &[118, 1, 18, 142, 95, 175, 21, 125][..],
+            &[0xAA; 32],
+            &[0x22; 32],
+            &[1, 0],
+            &[0, 0, 0, 0],
+        ]
+        .concat();
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn test_send_data() {
+        let data = endpoint::SendParams {
+            dst_eid: 30101,
+            receiver: [0x33; 32],
+            message: &[1, 2],
+            options: &[0, 3],
+            native_fee: 1_000_000,
+            lz_token_fee: 7,
+        }
+        .data();
+
+        let expected = [
+            &[102, 251, 20, 187, 65, 75, 12, 69][..],
+            &[0x95, 0x75, 0x00, 0x00],
+            &[0x33; 32],
+            &[2, 0, 0, 0, 1, 2],
+            &[2, 0, 0, 0, 0, 3],
+            &[0x40, 0x42, 0x0f, 0, 0, 0, 0, 0], // 1_000_000
+            &[7, 0, 0, 0, 0, 0, 0, 0],
+        ]
+        .concat();
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn test_quote_data() {
+        let data = endpoint::QuoteParams {
+            sender: OAPP,
+            dst_eid: 30101,
+            receiver: [0x33; 32],
+            message: &[1],
+            options: &[],
+            pay_in_lz_token: true,
+        }
+        .data();
+
+        let expected = [
+            &[149, 42, 109, 247, 134, 146, 213, 123][..],
+            &[0xAA; 32],
+            &[0x95, 0x75, 0x00, 0x00],
+            &[0x33; 32],
+            &[1, 0, 0, 0, 1],
+            &[0, 0, 0, 0],
+            &[1],
+        ]
+        .concat();
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn test_set_send_library_data() {
+        let data = endpoint::SetSendLibraryParams { sender: OAPP, eid: 30101, new_lib: LIB }.data();
+
+        let expected = [
+            &[251, 118, 78, 158, 134, 149, 129, 5][..],
+            &[0xAA; 32],
+            &[0x95, 0x75, 0x00, 0x00],
+            &[0xBB; 32],
+        ]
+        .concat();
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn test_set_receive_library_data() {
+        let data = endpoint::SetReceiveLibraryParams {
+            receiver: OAPP,
+            eid: 30101,
+            new_lib: LIB,
+            grace_period: 300,
+        }
+        .data();
+
+        let expected = [
+            &[223, 172, 180, 105, 165, 161, 147, 228][..],
+            &[0xAA; 32],
+            &[0x95, 0x75, 0x00, 0x00],
+            &[0xBB; 32],
+            &[0x2c, 0x01, 0, 0, 0, 0, 0, 0], // 300
+        ]
+        .concat();
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn test_set_config_data() {
+        let data = endpoint::SetConfigParams {
+            oapp: OAPP,
+            eid: 30101,
+            config_type: crate::constants::CONFIG_TYPE_RECEIVE_ULN,
+            config: &[9, 8, 7],
+        }
+        .data();
+
+        let expected = [
+            &[108, 158, 154, 175, 212, 98, 52, 66][..],
+            &[0xAA; 32],
+            &[0x95, 0x75, 0x00, 0x00],
+            &[3, 0, 0, 0],
+            &[3, 0, 0, 0, 9, 8, 7],
+        ]
+        .concat();
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn test_set_delegate_data() {
+        let data = endpoint::SetDelegateParams { delegate: LIB }.data();
+
+        let expected = [&[242, 30, 46, 76, 108, 235, 128, 181][..], &[0xBB; 32]].concat();
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn test_messaging_fee_from_return_data() {
+        let endpoint_program = Pubkey::new_unique();
+        let fee = endpoint::MessagingFee { native_fee: 1_234_567, lz_token_fee: 89 };
+        let data = fee.try_to_vec().unwrap();
+        assert_eq!(data.len(), 16);
+
+        let parsed = endpoint::MessagingFee::from_return_data(&endpoint_program, Some((endpoint_program, data.clone())));
+        assert_eq!(parsed.unwrap(), fee);
 
-        // For now, return a placeholder fee (in practice, would parse from return data)
-        Ok(1_000_000) // 0.001 SOL
+        let invalid = crate::error::ErrorCode::InvalidQuote.into();
+        // missing, from another program, or malformed
+        assert_eq!(endpoint::MessagingFee::from_return_data(&endpoint_program, None).unwrap_err(), invalid);
+        let other = Some((Pubkey::new_unique(), data.clone()));
+        assert_eq!(endpoint::MessagingFee::from_return_data(&endpoint_program, other).unwrap_err(), invalid);
+        let short = Some((endpoint_program, data[..15].to_vec()));
+        assert_eq!(endpoint::MessagingFee::from_return_data(&endpoint_program, short).unwrap_err(), invalid);
+        let long = Some((endpoint_program, [data, vec![0]].concat()));
+        assert_eq!(endpoint::MessagingFee::from_return_data(&endpoint_program, long).unwrap_err(), invalid);
     }
 }
@@ -194,4 +194,92 @@ pub enum ErrorCode {
 
     #[msg("Too many attributes: Maximum number of attributes exceeded")]
     TooManyAttributes,
+
+    // Message versioning
+    #[msg("Invalid minimum version: Version is not supported or lower than the current minimum")]
+    InvalidMinVersion,
+
+    // Command envelopes
+    #[msg("Nested envelope: An envelope cannot contain another envelope")]
+    NestedEnvelope,
+
+    // DAO attestations
+    #[msg("Attestation required: Command must be signed by the DAO signer set")]
+    AttestationRequired,
+
+    #[msg("Invalid attestation: Signature is malformed, repeated, or not from a DAO signer")]
+    InvalidAttestation,
+
+    #[msg("Insufficient attestations: Fewer DAO signatures than the signer threshold")]
+    InsufficientAttestations,
+
+    #[msg("Invalid signer set: Too many or duplicate signers, or threshold out of range")]
+    InvalidSignerSet,
+
+    // Admin handover
+    #[msg("Invalid pending admin: Key is not the proposed admin or cannot be proposed")]
+    InvalidPendingAdmin,
+
+    #[msg("No pending admin: There is no admin transfer in progress")]
+    NoPendingAdmin,
+
+    #[msg("Admin transfer expired: The pending admin did not accept before the deadline")]
+    AdminTransferExpired,
+
+    // Failed messages
+    #[msg("Invalid failed message account: Account is not the failed message PDA for this guid")]
+    InvalidFailedMessageAccount,
+
+    // Receive account layout
+    #[msg("Missing receive accounts: Fewer remaining accounts than the message requires")]
+    MissingReceiveAccounts,
+
+    #[msg("Invalid receive account: Remaining account does not match the expected layout")]
+    InvalidReceiveAccount,
+
+    // Compose execution
+    #[msg("Unsupported compose type: No composer is registered for this compose type")]
+    UnsupportedComposeType,
+
+    #[msg("Invalid composer: Account is not the composer registered for this compose type")]
+    InvalidComposer,
+
+    #[msg("Compose types full: No more compose types can be registered")]
+    ComposeTypesFull,
+
+    #[msg("Compose result too large: Composer returned more data than a receipt records")]
+    ComposeResultTooLarge,
+
+    // Fee quotes
+    #[msg("Invalid quote: Endpoint did not return a messaging fee")]
+    InvalidQuote,
+
+    // Endpoint configuration
+    #[msg("Invalid config type: Not an executor or ULN config type")]
+    InvalidConfigType,
+
+    // Enforced options
+    #[msg("Invalid options: Expected type 3 LayerZero options")]
+    InvalidOptions,
+
+    #[msg("Options too large: Enforced options exceed the maximum length")]
+    OptionsTooLarge,
+
+    // Receipts
+    #[msg("Receipt not configured: No receipt send accounts are set for this peer")]
+    ReceiptNotConfigured,
+
+    #[msg("Invalid receipt accounts: Too many accounts, or a signer or default account")]
+    InvalidReceiptAccounts,
+
+    // Endpoint CPI preconditions
+    #[msg("Compose already queued: The endpoint holds a compose message for this packet")]
+    ComposeAlreadyQueued,
+
+    #[msg("OApp not registered: The endpoint has no registry for this OApp")]
+    OAppNotRegistered,
+
+    // Migration
+    #[msg("Invalid migration account: Not a peer of this store in the old layout")]
+    InvalidMigrationAccount,
 }
@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+
+/// Accept a pending admin transfer
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [OAppStore::SEEDS],
+        bump = store.bump
+    )]
+    pub store: Account<'info, OAppStore>,
+    
+    /// The proposed admin
+    pub pending_admin: Signer<'info>,
+}
+
+/// Cancel a pending admin transfer
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelAdminTransfer<'info> {
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [OAppStore::SEEDS],
+        bump = store.bump
+    )]
+    pub store: Account<'info, OAppStore>,
+    
+    pub admin: Signer<'info>,
+}
+
+/// Handler for accepting the admin role
+pub fn accept_admin_handler(ctx: Context<AcceptAdmin>) -> Result<()> {
+    let store = &mut ctx.accounts.store;
+    let pending_admin = store
+        .pending_admin
+        .ok_or(crate::error::ErrorCode::NoPendingAdmin)?;
+    if pending_admin != ctx.accounts.pending_admin.key() {
+        return Err(crate::error::ErrorCode::InvalidPendingAdmin.into());
+    }
+    if Clock::get()?.unix_timestamp > store.pending_admin_deadline {
+        return Err(crate::error::ErrorCode::AdminTransferExpired.into());
+    }
+    
+    let previous_admin = store.admin;
+    store.admin = pending_admin;
+    store.pending_admin = None;
+    store.pending_admin_deadline = 0;
+    
+    msg!("Admin transferred from {} to {}", previous_admin, store.admin);
+    emit_cpi!(AdminTransferAccepted {
+        previous_admin,
+        admin: store.admin,
+    });
+    Ok(())
+}
+
+/// Handler for cancelling a pending admin transfer
+///
+/// Also clears an expired proposal.
+pub fn cancel_admin_transfer_handler(ctx: Context<CancelAdminTransfer>) -> Result<()> {
+    let store = &mut ctx.accounts.store;
+    
+    let pending_admin = store
+        .pending_admin
+        .take()
+        .ok_or(crate::error::ErrorCode::NoPendingAdmin)?;
+    store.pending_admin_deadline = 0;
+    
+    msg!("Admin transfer to {} cancelled", pending_admin);
+    emit_cpi!(AdminTransferCancelled { pending_admin });
+    Ok(())
+}
+
+#[event]
+pub struct AdminTransferAccepted {
+    pub previous_admin: Pubkey,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct AdminTransferCancelled {
+    pub pending_admin: Pubkey,
+}
@@ -0,0 +1,180 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::cpi::endpoint;
+use crate::constants::{CONFIG_TYPE_EXECUTOR, CONFIG_TYPE_RECEIVE_ULN, CONFIG_TYPE_SEND_ULN};
+
+/// Parameters for set_send_library instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SetSendLibraryParams {
+    pub eid: u32,
+    /// Default pubkey to use the endpoint's default library
+    pub new_lib: Pubkey,
+}
+
+/// Parameters for set_receive_library instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SetReceiveLibraryParams {
+    pub eid: u32,
+    /// Default pubkey to use the endpoint's default library
+    pub new_lib: Pubkey,
+    /// Slots during which the old library is still accepted
+    pub grace_period: u64,
+}
+
+/// Parameters for set_config instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SetConfigParams {
+    pub eid: u32,
+    /// One of the `CONFIG_TYPE_*` constants
+    pub config_type: u32,
+    /// Config in the encoding the message library expects
+    pub config: Vec<u8>,
+}
+
+/// Change the OApp's settings on the LayerZero endpoint
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetEndpointConfig<'info> {
+    #[account(
+        seeds = [OAppStore::SEEDS],
+        bump = store.bump
+    )]
+    pub store: Account<'info, OAppStore>,
+    
+    #[account(
+        constraint = authority.key() == store.admin || authority.key() == store.delegate
+            @ crate::error::ErrorCode::InsufficientAuthority
+    )]
+    pub authority: Signer<'info>,
+    
+    /// LayerZero endpoint program
+    /// CHECK: Checked against the store's endpoint
+    #[account(address = store.endpoint @ crate::error::ErrorCode::InvalidEndpoint)]
+    pub endpoint: AccountInfo<'info>,
+    
+    // Endpoint accounts (and message library accounts for set_config)
+    // follow as remaining accounts
+}
+
+/// Handler for setting the send library of a path
+pub fn set_send_library_handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetEndpointConfig<'info>>,
+    params: SetSendLibraryParams,
+) -> Result<()> {
+    let store = &ctx.accounts.store;
+    let seeds: &[&[u8]] = &[OAppStore::SEEDS, &[store.bump]];
+    endpoint::set_send_library(
+        &ctx.accounts.endpoint,
+        &store.to_account_info(),
+        ctx.remaining_accounts,
+        seeds,
+        &endpoint::SetSendLibraryParams {
+            sender: store.key(),
+            eid: params.eid,
+            new_lib: params.new_lib,
+        },
+    )?;
+    
+    msg!("Send library for EID {} set to {}", params.eid, params.new_lib);
+    emit_cpi!(SendLibrarySet {
+        eid: params.eid,
+        new_lib: params.new_lib,
+        authority: ctx.accounts.authority.key(),
+    });
+    Ok(())
+}
+
+/// Handler for setting the receive library of a path
+pub fn set_receive_library_handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetEndpointConfig<'info>>,
+    params: SetReceiveLibraryParams,
+) -> Result<()> {
+    let store = &ctx.accounts.store;
+    let seeds: &[&[u8]] = &[OAppStore::SEEDS, &[store.bump]];
+    endpoint::set_receive_library(
+        &ctx.accounts.endpoint,
+        &store.to_account_info(),
+        ctx.remaining_accounts,
+        seeds,
+        &endpoint::SetReceiveLibraryParams {
+            receiver: store.key(),
+            eid: params.eid,
+            new_lib: params.new_lib,
+            grace_period: params.grace_period,
+        },
+    )?;
+    
+    msg!("Receive library for EID {} set to {} - grace period: {}",
+         params.eid, params.new_lib, params.grace_period);
+    emit_cpi!(ReceiveLibrarySet {
+        eid: params.eid,
+        new_lib: params.new_lib,
+        grace_period: params.grace_period,
+        authority: ctx.accounts.authority.key(),
+    });
+    Ok(())
+}
+
+/// Handler for setting a message library config of a path
+///
+/// Covers the executor config and the send and receive ULN configs (DVNs
+/// and confirmations); the library checks the config itself.
+pub fn set_config_handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetEndpointConfig<'info>>,
+    params: SetConfigParams,
+) -> Result<()> {
+    let store = &ctx.accounts.store;
+    if !matches!(
+        params.config_type,
+        CONFIG_TYPE_EXECUTOR | CONFIG_TYPE_SEND_ULN | CONFIG_TYPE_RECEIVE_ULN
+    ) {
+        return Err(crate::error::ErrorCode::InvalidConfigType.into());
+    }
+    
+    let seeds: &[&[u8]] = &[OAppStore::SEEDS, &[store.bump]];
+    endpoint::set_config(
+        &ctx.accounts.endpoint,
+        &store.to_account_info(),
+        ctx.remaining_accounts,
+        seeds,
+        &endpoint::SetConfigParams {
+            oapp: store.key(),
+            eid: params.eid,
+            config_type: params.config_type,
+            config: &params.config,
+        },
+    )?;
+    
+    msg!("Config type {} for EID {} set - {} bytes",
+         params.config_type, params.eid, params.config.len());
+    emit_cpi!(ConfigSet {
+        eid: params.eid,
+        config_type: params.config_type,
+        config: params.config,
+        authority: ctx.accounts.authority.key(),
+    });
+    Ok(())
+}
+
+#[event]
+pub struct SendLibrarySet {
+    pub eid: u32,
+    pub new_lib: Pubkey,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct ReceiveLibrarySet {
+    pub eid: u32,
+    pub new_lib: Pubkey,
+    pub grace_period: u64,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct ConfigSet {
+    pub eid: u32,
+    pub config_type: u32,
+    pub config: Vec<u8>,
+    pub authority: Pubkey,
+}
@@ -0,0 +1,158 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+
+/// Enforce options on a message type sent to a destination, or replace
+/// the options already enforced on it
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(dst_eid: u32, msg_type: u8)]
+pub struct SetEnforcedOptions<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = EnforcedOptions::LEN,
+        seeds = [EnforcedOptions::SEEDS, store.key().as_ref(), &dst_eid.to_le_bytes(), &[msg_type]],
+        bump
+    )]
+    pub enforced_options: Account<'info, EnforcedOptions>,
+    
+    #[account(
+        has_one = admin,
+        seeds = [OAppStore::SEEDS],
+        bump = store.bump
+    )]
+    pub store: Account<'info, OAppStore>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+/// Stop enforcing options on a message type sent to a destination
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(dst_eid: u32, msg_type: u8)]
+pub struct RemoveEnforcedOptions<'info> {
+    #[account(
+        mut,
+        close = admin,
+        seeds = [EnforcedOptions::SEEDS, store.key().as_ref(), &dst_eid.to_le_bytes(), &[msg_type]],
+        bump = enforced_options.bump
+    )]
+    pub enforced_options: Account<'info, EnforcedOptions>,
+    
+    #[account(
+        has_one = admin,
+        seeds = [OAppStore::SEEDS],
+        bump = store.bump
+    )]
+    pub store: Account<'info, OAppStore>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+/// Handler for enforcing options
+///
+/// `options` must be type 3 options. They replace any options already
+/// enforced on the message type.
+pub fn set_enforced_options_handler(
+    ctx: Context<SetEnforcedOptions>,
+    dst_eid: u32,
+    msg_type: u8,
+    options: Vec<u8>,
+) -> Result<()> {
+    if options.len() > EnforcedOptions::MAX_OPTIONS_LEN {
+        return Err(crate::error::ErrorCode::OptionsTooLarge.into());
+    }
+    EnforcedOptions::assert_type_3(&options)?;
+    
+    let enforced_options = &mut ctx.accounts.enforced_options;
+    enforced_options.store = ctx.accounts.store.key();
+    enforced_options.dst_eid = dst_eid;
+    enforced_options.msg_type = msg_type;
+    enforced_options.options = options;
+    enforced_options.bump = ctx.bumps.enforced_options;
+    
+    msg!("Enforced options set for EID {} - type {}, {} bytes",
+         dst_eid, msg_type, enforced_options.options.len());
+    emit_cpi!(EnforcedOptionsSet {
+        dst_eid,
+        msg_type,
+        options: enforced_options.options.clone(),
+    });
+    Ok(())
+}
+
+/// Handler for removing enforced options
+pub fn remove_enforced_options_handler(
+    ctx: Context<RemoveEnforcedOptions>,
+    dst_eid: u32,
+    msg_type: u8,
+) -> Result<()> {
+    msg!("Enforced options removed for EID {} - type {}", dst_eid, msg_type);
+    emit_cpi!(EnforcedOptionsRemoved { dst_eid, msg_type });
+    Ok(())
+}
+
+/// Options to send a message with: the enforced options at
+/// `enforced_options`, if any, merged with the caller's
+///
+/// The account's address must already be checked; an account that was never
+/// initialized enforces nothing.
+pub(crate) fn combine_options(enforced_options: &AccountInfo, options: &[u8]) -> Result<Vec<u8>> {
+    if enforced_options.owner != &crate::ID {
+        return EnforcedOptions::combine(&[], options);
+    }
+    let enforced = EnforcedOptions::try_deserialize(&mut &enforced_options.try_borrow_data()?[..])?;
+    EnforcedOptions::combine(&enforced.options, options)
+}
+
+#[event]
+pub struct EnforcedOptionsSet {
+    pub dst_eid: u32,
+    pub msg_type: u8,
+    pub options: Vec<u8>,
+}
+
+#[event]
+pub struct EnforcedOptionsRemoved {
+    pub dst_eid: u32,
+    pub msg_type: u8,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn combine_with(owner: &Pubkey, data: &mut [u8], options: &[u8]) -> Result<Vec<u8>> {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let account = AccountInfo::new(&key, false, false, &mut lamports, data, owner, false, 0);
+        combine_options(&account, options)
+    }
+
+    #[test]
+    fn test_combine_options_with_account() {
+        let enforced = EnforcedOptions {
+            store: Pubkey::new_unique(),
+            dst_eid: 30101,
+            msg_type: msg_codec::MessageCodec::COMMAND_RECEIPT,
+            options: vec![0, 3, 1, 0, 1, 4],
+            bump: 255,
+        };
+        let mut data = Vec::new();
+        enforced.try_serialize(&mut data).unwrap();
+
+        let combined = combine_with(&crate::ID, &mut data, &[0, 3, 9]).unwrap();
+        assert_eq!(combined, [0, 3, 1, 0, 1, 4, 9]);
+        assert_eq!(combine_with(&crate::ID, &mut data, &[]).unwrap(), enforced.options);
+
+        // Never initialized: the caller's options go through as they are
+        let system = anchor_lang::system_program::ID;
+        assert_eq!(combine_with(&system, &mut [], &[0, 1]).unwrap(), [0, 1]);
+    }
+}
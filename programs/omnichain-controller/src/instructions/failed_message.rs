@@ -0,0 +1,210 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::instructions::lz_receive::{run_command, MessageOrigin, PendingEvents};
+use crate::instructions::receipt::{send_receipt, ReceiptSent};
+use crate::instructions::receive_accounts::{ReceiveExtras, ReceiveLayout};
+
+/// Turn the failed message vault on or off
+#[derive(Accounts)]
+pub struct SetFailedMessageMode<'info> {
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [OAppStore::SEEDS],
+        bump = store.bump
+    )]
+    pub store: Account<'info, OAppStore>,
+    
+    pub admin: Signer<'info>,
+}
+
+/// Retry a failed message
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RetryFailedMessage<'info> {
+    #[account(
+        mut,
+        seeds = [OAppStore::SEEDS],
+        bump = store.bump
+    )]
+    pub store: Account<'info, OAppStore>,
+    
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer,
+        seeds = [FailedMessage::SEEDS, store.key().as_ref(), &failed_message.guid],
+        bump = failed_message.bump
+    )]
+    pub failed_message: Account<'info, FailedMessage>,
+    
+    /// Peer the message came from, sent the new receipt
+    #[account(
+        mut,
+        seeds = [PeerConfig::SEEDS, store.key().as_ref(), &failed_message.src_eid.to_le_bytes()],
+        bump = peer_config.bump,
+        constraint = peer_config.trusted @ crate::error::ErrorCode::UntrustedPeer
+    )]
+    pub peer_config: Account<'info, PeerConfig>,
+    
+    /// Send accounts of receipts to the peer, required while the store
+    /// sends receipts
+    #[account(
+        seeds = [ReceiptConfig::SEEDS, store.key().as_ref(), &failed_message.src_eid.to_le_bytes()],
+        bump = receipt_config.bump
+    )]
+    pub receipt_config: Option<Account<'info, ReceiptConfig>>,
+    
+    /// Refunded the failed message rent
+    /// CHECK: Checked against `failed_message.payer`
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    
+    #[account(
+        constraint = authority.key() == store.admin || authority.key() == store.delegate
+            @ crate::error::ErrorCode::InsufficientAuthority
+    )]
+    pub authority: Signer<'info>,
+    
+    /// LayerZero endpoint program
+    /// CHECK: Checked against the store's endpoint
+    #[account(address = store.endpoint @ crate::error::ErrorCode::InvalidEndpoint)]
+    pub endpoint: AccountInfo<'info>,
+    
+    // The accounts the message's command needs, then the receipt accounts,
+    // follow as remaining accounts (see `ReceiveLayout::retry`)
+}
+
+/// Discard a failed message
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DiscardFailedMessage<'info> {
+    #[account(
+        mut,
+        seeds = [OAppStore::SEEDS],
+        bump = store.bump
+    )]
+    pub store: Account<'info, OAppStore>,
+    
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer,
+        seeds = [FailedMessage::SEEDS, store.key().as_ref(), &failed_message.guid],
+        bump = failed_message.bump
+    )]
+    pub failed_message: Account<'info, FailedMessage>,
+    
+    /// Refunded the failed message rent
+    /// CHECK: Checked against `failed_message.payer`
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    
+    #[account(
+        constraint = authority.key() == store.admin || authority.key() == store.delegate
+            @ crate::error::ErrorCode::InsufficientAuthority
+    )]
+    pub authority: Signer<'info>,
+}
+
+/// Handler for toggling the failed message vault
+pub fn set_failed_message_mode_handler(
+    ctx: Context<SetFailedMessageMode>,
+    enabled: bool,
+) -> Result<()> {
+    let store = &mut ctx.accounts.store;
+    store.store_failed_messages = enabled;
+    
+    msg!("Failed message vault {}", if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+/// Handler for retrying a failed message
+///
+/// The message was validated when it was received, so only its command runs
+/// again. On failure the transaction reverts and the message stays stored.
+/// When the store sends receipts, the peer gets a new receipt replacing the
+/// failed one.
+pub fn retry_failed_message_handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, RetryFailedMessage<'info>>,
+) -> Result<()> {
+    let failed_message = &ctx.accounts.failed_message;
+    let store = &mut ctx.accounts.store;
+    
+    let decoded = msg_codec::MessageCodec::decode_message(&failed_message.message)?;
+    let layout = ReceiveLayout::retry(
+        &store.endpoint,
+        &store.key(),
+        &failed_message.guid,
+        ReceiveExtras::for_decoded(Some(&decoded)),
+    )
+    .with_receipt(store, &store.key(), failed_message.src_eid, ctx.accounts.receipt_config.as_deref())?;
+    let accounts = layout.parse(ctx.remaining_accounts)?;
+    
+    let command = decoded.decode_command()?;
+    let origin = MessageOrigin {
+        src_eid: failed_message.src_eid,
+        sender: failed_message.sender,
+        nonce: failed_message.nonce,
+        guid: failed_message.guid,
+    };
+    let mut events = PendingEvents::default();
+    run_command(
+        &ctx.accounts.endpoint,
+        store,
+        accounts.extras,
+        &decoded,
+        command,
+        origin,
+        failed_message.packet_nonce,
+        &mut events,
+    )?;
+    
+    // Later messages may have run while this one was stored
+    store.nonce = store.nonce.max(decoded.nonce);
+    store.processed_messages += 1;
+    
+    msg!("Failed message retried - Command: {}, Nonce: {}", decoded.command, decoded.nonce);
+    events.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+    emit_cpi!(FailedMessageResolved {
+        guid: failed_message.guid,
+        executed: true,
+    });
+    
+    if store.send_receipts {
+        let receipt_nonce = send_receipt(
+            &ctx.accounts.endpoint,
+            store,
+            &mut ctx.accounts.peer_config,
+            accounts.receipt,
+            origin,
+            0,
+        )?;
+        
+        emit_cpi!(ReceiptSent {
+            guid: origin.guid,
+            dst_eid: origin.src_eid,
+            nonce: receipt_nonce,
+            result_code: 0,
+        });
+    }
+    Ok(())
+}
+
+/// Handler for discarding a failed message
+pub fn discard_failed_message_handler(ctx: Context<DiscardFailedMessage>) -> Result<()> {
+    let failed_message = &ctx.accounts.failed_message;
+    
+    msg!("Failed message discarded - Nonce: {}", failed_message.nonce);
+    emit_cpi!(FailedMessageResolved {
+        guid: failed_message.guid,
+        executed: false,
+    });
+    Ok(())
+}
+
+#[event]
+pub struct FailedMessageResolved {
+    pub guid: [u8; 32],
+    pub executed: bool,
+}
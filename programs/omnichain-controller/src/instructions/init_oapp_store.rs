@@ -58,8 +58,8 @@ pub struct InitOAppStoreParams {
 }
 
 /// Handler for initializing LayerZero OApp Store
-pub fn init_oapp_store_handler(
-    ctx: Context<InitOAppStore>,
+pub fn init_oapp_store_handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, InitOAppStore<'info>>,
     params: InitOAppStoreParams,
 ) -> Result<()> {
     let store = &mut ctx.accounts.store;
@@ -77,9 +77,18 @@ pub fn init_oapp_store_handler(
         ethereum_eid: params.ethereum_eid,
         voting_period: 86400, // 24 hours default
         quorum: 10, // 10% default
+        signers: Vec::new(),
+        signer_threshold: 0,
     };
     store.nonce = 0;
     store.processed_messages = 0;
+    store.min_message_version = msg_codec::MessageCodec::MESSAGE_VERSION_V1;
+    store.paused = false;
+    store.pending_admin = None;
+    store.pending_admin_deadline = 0;
+    store.store_failed_messages = false;
+    store.send_receipts = false;
+    store.receipt_native_fee = 0;
     
     // Initialize lz_receive_types
     lz_receive_types.store = store.key();
@@ -88,19 +97,22 @@ pub fn init_oapp_store_handler(
     
     // Initialize lz_compose_types
     lz_compose_types.store = store.key();
-    lz_compose_types.compose_types = vec![1]; // Default compose message type
+    lz_compose_types.compose_types = Vec::new(); // Filled in by set_composer
     lz_compose_types.bump = ctx.bumps.lz_compose_types;
     
     // CRITICAL: Register with LayerZero Endpoint
     let seeds = &[OAppStore::SEEDS, &[store.bump]];
-    let endpoint_accounts = &ctx.remaining_accounts[..]; // Accounts for endpoint registration
+    let endpoint_accounts = ctx.remaining_accounts; // Accounts for endpoint registration
     
     endpoint::register_oapp(
         &ctx.accounts.endpoint,
+        &ctx.accounts.admin.to_account_info(),
+        &store.to_account_info(),
         endpoint_accounts,
         seeds,
-        &store.key(),
-        &params.delegate,
+        &endpoint::RegisterOAppParams {
+            delegate: params.delegate,
+        },
     )?;
     
     msg!("OApp Store initialized - Admin: {}, Endpoint: {}, Delegate: {}", 
@@ -0,0 +1,193 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::cpi::{composer, endpoint};
+use crate::instructions::receive_accounts::ComposeLayout;
+
+/// LayerZero compose message instruction
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(src_eid: u32, sender: [u8; 32], nonce: u64, guid: [u8; 32], index: u16)]
+pub struct LzCompose<'info> {
+    #[account(
+        seeds = [OAppStore::SEEDS],
+        bump = store.bump
+    )]
+    pub store: Account<'info, OAppStore>,
+    
+    #[account(
+        seeds = [PeerConfig::SEEDS, store.key().as_ref(), &src_eid.to_le_bytes()],
+        bump = peer_config.bump,
+        constraint = peer_config.trusted @ crate::error::ErrorCode::UntrustedPeer
+    )]
+    pub peer_config: Account<'info, PeerConfig>,
+    
+    #[account(
+        seeds = [LzComposeTypes::SEEDS, store.key().as_ref()],
+        bump = lz_compose_types.bump
+    )]
+    pub lz_compose_types: Account<'info, LzComposeTypes>,
+    
+    /// Composer registered for the message's compose type, checked
+    /// against the message in the handler
+    #[account(
+        constraint = composer_config.store == store.key() @ crate::error::ErrorCode::InvalidComposer
+    )]
+    pub composer_config: Account<'info, ComposerConfig>,
+    
+    /// Composer program
+    /// CHECK: Checked against the composer config
+    #[account(
+        executable,
+        address = composer_config.composer @ crate::error::ErrorCode::InvalidComposer
+    )]
+    pub composer: AccountInfo<'info>,
+    
+    /// Signs the composer call in place of the store
+    /// CHECK: Data-less PDA
+    #[account(
+        seeds = [ComposeAuthority::SEEDS, store.key().as_ref()],
+        bump
+    )]
+    pub compose_authority: AccountInfo<'info>,
+    
+    /// Replay protection for (guid, index); fails if already executed
+    #[account(
+        init,
+        payer = payer,
+        space = ComposeReceipt::LEN,
+        seeds = [ComposeReceipt::SEEDS, store.key().as_ref(), &guid, &index.to_le_bytes()],
+        bump
+    )]
+    pub compose_receipt: Account<'info, ComposeReceipt>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// LayerZero endpoint program
+    /// CHECK: Checked against the store's endpoint
+    #[account(address = store.endpoint @ crate::error::ErrorCode::InvalidEndpoint)]
+    pub endpoint: AccountInfo<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    // Endpoint clear accounts, then the composer's accounts, follow as
+    // remaining accounts (see `ComposeLayout`)
+}
+
+/// Handler for LayerZero compose message
+///
+/// Clears the compose from the endpoint, then calls the composer registered
+/// for its compose type and records what the composer returned.
+#[allow(clippy::too_many_arguments)]
+pub fn lz_compose_handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, LzCompose<'info>>,
+    src_eid: u32,
+    sender: [u8; 32],
+    nonce: u64,
+    guid: [u8; 32],
+    index: u16,
+    message: Vec<u8>,
+) -> Result<()> {
+    let store = &ctx.accounts.store;
+    store.require_not_paused()?;
+    
+    // Clear the compose first, as lz_receive does with its packet
+    let seeds: &[&[u8]] = &[OAppStore::SEEDS, &[store.bump]];
+    let layout = ComposeLayout::new(&store.endpoint, &store.key(), &guid, index);
+    let (clear_accounts, composer_accounts) = layout.parse(ctx.remaining_accounts)?;
+    endpoint::clear_compose(
+        &ctx.accounts.endpoint,
+        &store.to_account_info(),
+        clear_accounts,
+        seeds,
+        &endpoint::ClearComposeParams {
+            from: store.key(),
+            guid,
+            index,
+            message: &message,
+        },
+    )?;
+    
+    // Validate message size
+    if !msg_codec::MessageValidator::validate_message_size(&message) {
+        return Err(crate::error::ErrorCode::MessageTooLarge.into());
+    }
+    
+    // Decode the compose envelope queued by lz_receive
+    let compose = msg_codec::ComposeMessage::decode(&message)?;
+    
+    // The envelope must describe the packet it was composed from
+    if compose.src_eid != src_eid || compose.nonce != nonce {
+        return Err(crate::error::ErrorCode::InvalidLzMessage.into());
+    }
+    
+    // Validate sender matches peer configuration
+    if compose.compose_from != sender || ctx.accounts.peer_config.peer_address != sender {
+        return Err(crate::error::ErrorCode::UnauthorizedSender.into());
+    }
+    
+    // Route by compose type to the registered composer
+    let (compose_type, compose_msg) = compose.compose_type()?;
+    if !ctx.accounts.lz_compose_types.compose_types.contains(&compose_type) {
+        return Err(crate::error::ErrorCode::UnsupportedComposeType.into());
+    }
+    if ctx.accounts.composer_config.compose_type != compose_type {
+        return Err(crate::error::ErrorCode::InvalidComposer.into());
+    }
+    
+    let params = composer::LzComposeParams {
+        from: store.key(),
+        guid,
+        index,
+        compose_type,
+        src_eid,
+        sender,
+        nonce,
+        message: compose_msg.to_vec(),
+    };
+    let store_key = store.key();
+    let authority_seeds: &[&[u8]] = &[
+        ComposeAuthority::SEEDS,
+        store_key.as_ref(),
+        &[ctx.bumps.compose_authority],
+    ];
+    let result = composer::lz_compose(
+        &ctx.accounts.composer,
+        &ctx.accounts.compose_authority,
+        composer_accounts,
+        authority_seeds,
+        &params,
+    )?;
+    if result.len() > ComposeReceipt::MAX_RESULT_LEN {
+        return Err(crate::error::ErrorCode::ComposeResultTooLarge.into());
+    }
+    
+    let receipt = &mut ctx.accounts.compose_receipt;
+    receipt.store = store.key();
+    receipt.guid = guid;
+    receipt.index = index;
+    receipt.compose_type = compose_type;
+    receipt.composer = ctx.accounts.composer.key();
+    receipt.result = result;
+    receipt.bump = ctx.bumps.compose_receipt;
+    
+    msg!("Compose message executed - type {}, {} bytes, Nonce: {}, From EID: {}",
+         compose_type, compose_msg.len(), nonce, src_eid);
+    emit_cpi!(ComposeExecuted {
+        guid,
+        index,
+        compose_type,
+        composer: receipt.composer,
+        result: receipt.result.clone(),
+    });
+    Ok(())
+}
+
+#[event]
+pub struct ComposeExecuted {
+    pub guid: [u8; 32],
+    pub index: u16,
+    pub compose_type: u8,
+    pub composer: Pubkey,
+    pub result: Vec<u8>,
+}
@@ -1,19 +1,69 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
+use crate::state::message_types::CrossChainMessageProcessed;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::system_program;
 use crate::cpi::endpoint;
+use crate::instructions::receive_accounts::{ReceiveExtras, ReceiveLayout};
+use crate::instructions::receive_message::execute_legacy_command;
+use crate::instructions::receipt::{send_receipt, ReceiptSent};
+use crate::instructions::set_delegate::update_delegate;
 
-/// LayerZero Clear Parameters
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct ClearParams {
-    pub receiver: Pubkey,
+/// Where a message came from, as reported in command events
+#[derive(Clone, Copy)]
+pub struct MessageOrigin {
     pub src_eid: u32,
     pub sender: [u8; 32],
     pub nonce: u64,
     pub guid: [u8; 32],
-    pub message: Vec<u8>,
+}
+
+/// Events raised while running a command
+///
+/// Command handlers cannot reach the event CPI accounts, so they record
+/// their events here and the instruction emits them through event CPI once
+/// the command has run. A command that fails leaves only the events that
+/// report its failure, so a failure stored in the vault never reports
+/// changes that were rolled back.
+#[derive(Default)]
+pub struct PendingEvents(Vec<Vec<u8>>);
+
+impl PendingEvents {
+    pub fn push<E: anchor_lang::Event>(&mut self, event: E) {
+        self.0.push(event.data());
+    }
+    
+    /// Emit the recorded events in order
+    pub fn emit<'info>(self, event_authority: &AccountInfo<'info>, bump: u8) -> Result<()> {
+        for data in self.0 {
+            let instruction = Instruction::new_with_bytes(
+                crate::ID,
+                &[anchor_lang::event::EVENT_IX_TAG_LE, &data].concat(),
+                vec![AccountMeta::new_readonly(event_authority.key(), true)],
+            );
+            invoke_signed(
+                &instruction,
+                std::slice::from_ref(event_authority),
+                &[&[b"__event_authority", &[bump]]],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Compose index used for the single compose a message can queue
+pub const COMPOSE_INDEX: u16 = 0;
+
+/// What became of a cleared message
+enum ReceiveOutcome {
+    Executed,
+    /// The command failed and was saved to the vault with this error code
+    Stored(u32),
 }
 
 /// LayerZero receive message instruction
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(src_eid: u32)]
 pub struct LzReceive<'info> {
@@ -25,6 +75,7 @@ pub struct LzReceive<'info> {
     pub store: Account<'info, OAppStore>,
     
     #[account(
+        mut,
         seeds = [PeerConfig::SEEDS, store.key().as_ref(), &src_eid.to_le_bytes()],
         bump = peer_config.bump,
         constraint = peer_config.trusted @ crate::error::ErrorCode::UntrustedPeer
@@ -37,51 +88,26 @@ pub struct LzReceive<'info> {
     )]
     pub lz_receive_types: Account<'info, LzReceiveTypes>,
     
-    /// LayerZero endpoint program
-    /// CHECK: This is the LayerZero endpoint program
-    pub endpoint: AccountInfo<'info>,
-    
-    /// Accounts for endpoint CPI
-    /// CHECK: These are accounts required for endpoint operations
-    pub endpoint_accounts: AccountInfo<'info>,
-}
-
-/// LayerZero compose message instruction
-#[derive(Accounts)]
-#[instruction(src_eid: u32)]
-pub struct LzCompose<'info> {
-    #[account(
-        mut,
-        seeds = [OAppStore::SEEDS],
-        bump = store.bump
-    )]
-    pub store: Account<'info, OAppStore>,
-    
-    #[account(
-        seeds = [PeerConfig::SEEDS, store.key().as_ref(), &src_eid.to_le_bytes()],
-        bump = peer_config.bump,
-        constraint = peer_config.trusted @ crate::error::ErrorCode::UntrustedPeer
-    )]
-    pub peer_config: Account<'info, PeerConfig>,
-    
+    /// Send accounts of receipts to the peer, required while the store
+    /// sends receipts
     #[account(
-        seeds = [LzComposeTypes::SEEDS, store.key().as_ref()],
-        bump = lz_compose_types.bump
+        seeds = [ReceiptConfig::SEEDS, store.key().as_ref(), &src_eid.to_le_bytes()],
+        bump = receipt_config.bump
     )]
-    pub lz_compose_types: Account<'info, LzComposeTypes>,
+    pub receipt_config: Option<Account<'info, ReceiptConfig>>,
     
     /// LayerZero endpoint program
-    /// CHECK: This is the LayerZero endpoint program
+    /// CHECK: Checked against the store's endpoint
+    #[account(address = store.endpoint @ crate::error::ErrorCode::InvalidEndpoint)]
     pub endpoint: AccountInfo<'info>,
     
-    /// Accounts for endpoint CPI
-    /// CHECK: These are accounts required for endpoint operations
-    pub endpoint_accounts: AccountInfo<'info>,
+    // Event CPI accounts are appended here; endpoint CPI accounts follow as
+    // remaining accounts (see `ReceiveLayout`)
 }
 
 /// Handler for LayerZero receive message
-pub fn lz_receive_handler(
-    ctx: Context<LzReceive>,
+pub fn lz_receive_handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, LzReceive<'info>>,
     src_eid: u32,
     sender: [u8; 32],
     nonce: u64,
@@ -90,147 +116,523 @@ pub fn lz_receive_handler(
 ) -> Result<()> {
     let store = &mut ctx.accounts.store;
     
+    // Decoded once, for the account layout and the command. A message that
+    // does not decode is still cleared, then rejected.
+    let decoded = msg_codec::MessageCodec::decode_message(&message);
+    
     // 1. CRITICAL: Call endpoint clear FIRST for replay protection (LayerZero V2 requirement)
     let seeds = &[OAppStore::SEEDS, &[store.bump]];
-    let clear_accounts = &ctx.remaining_accounts[..4]; // First 4 accounts are for clear
+    let layout = ReceiveLayout::new(
+        &store.endpoint,
+        &store.key(),
+        src_eid,
+        &sender,
+        &guid,
+        ReceiveExtras::for_decoded(decoded.as_ref().ok()),
+    )
+    .with_vault(store, &store.key(), &guid)
+    .with_receipt(store, &store.key(), src_eid, ctx.accounts.receipt_config.as_deref())?;
+    let accounts = layout.parse(ctx.remaining_accounts)?;
     
     // Call LayerZero endpoint clear CPI - MUST BE FIRST OPERATION
     endpoint::clear(
         &ctx.accounts.endpoint,
-        clear_accounts,
+        &store.to_account_info(),
+        accounts.clear,
         seeds,
-        &ClearParams {
+        &endpoint::ClearParams {
             receiver: store.key(),
             src_eid,
             sender,
             nonce,
             guid,
-            message: message.clone(),
+            message: &message,
         },
     )?;
     
-    // 2. Validate message size
-    if !msg_codec::MessageValidator::validate_message_size(&message) {
-        return Err(crate::error::ErrorCode::MessageTooLarge.into());
-    }
-    
-    // 3. Decode the message
-    let decoded = msg_codec::MessageCodec::decode_message(&message)?;
-    
-    // 4. Validate message version and command
-    if !msg_codec::MessageCodec::validate_command(decoded.command) {
-        return Err(crate::error::ErrorCode::InvalidCommand.into());
-    }
-    
-    // 5. Validate nonce
-    if !msg_codec::MessageValidator::validate_nonce(store.nonce, decoded.nonce) {
-        return Err(crate::error::ErrorCode::InvalidNonce.into());
-    }
-    
-    // 6. Validate timestamp
-    if !msg_codec::MessageValidator::validate_timestamp(decoded.timestamp) {
-        return Err(crate::error::ErrorCode::InvalidTimestamp.into());
-    }
-    
-    // Validate sender matches peer configuration
-    if ctx.accounts.peer_config.peer_address != sender {
-        return Err(crate::error::ErrorCode::UnauthorizedSender.into());
-    }
+    emit_cpi!(MessageCleared {
+        guid,
+        src_eid,
+        sender,
+        nonce,
+    });
     
-    // Process the message based on command type
-    match decoded.command {
-        msg_codec::MessageCodec::COMMAND_UPDATE_COLLECTION_METADATA => {
-            handle_update_collection_metadata(store, &decoded.payload)?;
+    let mut command_id = None;
+    let mut dao_nonce = None;
+    let mut events = PendingEvents::default();
+    let receive = || -> Result<ReceiveOutcome> {
+        // 2. Validate message size
+        if !msg_codec::MessageValidator::validate_message_size(&message) {
+            return Err(crate::error::ErrorCode::MessageTooLarge.into());
         }
-        msg_codec::MessageCodec::COMMAND_EMERGENCY_PAUSE => {
-            handle_emergency_pause(store)?;
+        
+        // 3. Fail if the message did not decode
+        let decoded = decoded?;
+        command_id = Some(decoded.command);
+        dao_nonce = Some(decoded.nonce);
+        
+        // 4. Validate message version and command
+        if decoded.version < store.min_message_version {
+            return Err(crate::error::ErrorCode::UnsupportedVersion.into());
         }
-        msg_codec::MessageCodec::COMMAND_EMERGENCY_UNPAUSE => {
-            handle_emergency_unpause(store)?;
+        let command = decoded.decode_command()?;
+        
+        // 5. Validate timestamp
+        if !msg_codec::MessageValidator::validate_timestamp(decoded.timestamp) {
+            return Err(crate::error::ErrorCode::InvalidTimestamp.into());
         }
-        msg_codec::MessageCodec::COMMAND_TRANSFER_AUTHORITY => {
-            handle_transfer_authority(store, &decoded.payload)?;
+        
+        // 6. Validate sender matches peer configuration
+        let peer_config = &mut ctx.accounts.peer_config;
+        if peer_config.peer_address != sender {
+            return Err(crate::error::ErrorCode::UnauthorizedSender.into());
         }
-        msg_codec::MessageCodec::COMMAND_BATCH_UPDATE_CNFTS => {
-            handle_batch_update_cnfts(store, &decoded.payload)?;
-        }
-        _ => {
-            return Err(crate::error::ErrorCode::UnsupportedCommand.into());
+        
+        // 7. Validate the packet nonce against this peer's delivery mode, and
+        //    that the DAO message was not executed in an earlier packet
+        peer_config.accept_nonce(nonce)?;
+        peer_config.accept_dao_nonce(decoded.nonce)?;
+        
+        // Verify DAO signer attestation
+        verify_attestation(store, &decoded, &command)?;
+        
+        // Process the message based on command type. With the vault on, a
+        // failed command is rolled back (e.g. earlier envelope sub-commands)
+        // and stored instead of reverting.
+        let origin = MessageOrigin {
+            src_eid,
+            sender,
+            nonce: decoded.nonce,
+            guid,
+        };
+        let snapshot = store.store_failed_messages.then(|| (**store).clone());
+        let result = run_command(
+            &ctx.accounts.endpoint,
+            store,
+            accounts.extras,
+            &decoded,
+            command,
+            origin,
+            nonce,
+            &mut events,
+        );
+        if let Err(error) = result {
+            let Some(snapshot) = snapshot else {
+                return Err(error);
+            };
+            **store = snapshot;
+            
+            store_failed_message(accounts.vault, &store.key(), origin, nonce, &error, &message, &mut events)?;
+            return Ok(ReceiveOutcome::Stored(error_code(&error)));
         }
-    }
+        
+        // Update nonce and processed messages count
+        store.nonce = store.nonce.max(decoded.nonce);
+        store.processed_messages += 1;
+        
+        msg!("Message processed - Command: {}, Nonce: {}, From EID: {}", 
+             decoded.command, decoded.nonce, src_eid);
+        
+        Ok(ReceiveOutcome::Executed)
+    };
     
-    // Update nonce and processed messages count
-    store.nonce = decoded.nonce;
-    store.processed_messages += 1;
+    // A failure that reverts leaves no events; only stored failures are
+    // reported as rejected
+    let outcome = receive()?;
+    events.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
     
-    msg!("Message processed - Command: {}, Nonce: {}, From EID: {}", 
-         decoded.command, decoded.nonce, src_eid);
+    let result_code = match outcome {
+        ReceiveOutcome::Executed => {
+            emit_cpi!(CommandExecuted {
+                guid,
+                src_eid,
+                sender,
+                nonce,
+                command: command_id.unwrap_or_default(),
+                result_code: 0,
+            });
+            0
+        }
+        ReceiveOutcome::Stored(result_code) => {
+            emit_cpi!(CommandRejected {
+                guid,
+                src_eid,
+                sender,
+                nonce,
+                command: command_id.unwrap_or_default(),
+                result_code,
+            });
+            result_code
+        }
+    };
     
+    // Acknowledge to the DAO. A receipt that cannot be sent reverts the
+    // message, which can be retried once the fee vault is funded.
+    if store.send_receipts {
+        let origin = MessageOrigin {
+            src_eid,
+            sender,
+            nonce: dao_nonce.unwrap_or_default(),
+            guid,
+        };
+        let receipt_nonce = send_receipt(
+            &ctx.accounts.endpoint,
+            store,
+            &mut ctx.accounts.peer_config,
+            accounts.receipt,
+            origin,
+            result_code,
+        )?;
+        
+        emit_cpi!(ReceiptSent {
+            guid,
+            dst_eid: src_eid,
+            nonce: receipt_nonce,
+            result_code,
+        });
+    }
     Ok(())
 }
 
-/// Handler for LayerZero compose message
-pub fn lz_compose_handler(
-    ctx: Context<LzCompose>,
+/// Run a validated command with the extra accounts it needs
+///
+/// Shared by `lz_receive` and `retry_failed_message`. `packet_nonce` is the
+/// nonce of the packet that delivered the message, which a compose carries.
+///
+/// Compose and set delegate call the endpoint. A failed CPI aborts the whole
+/// transaction, so the failed message vault never sees it; the endpoint's
+/// accounts are checked first and those failures are stored like any other.
+/// What cannot be checked, such as an executor too poor to pay for the
+/// compose queue, aborts the transaction and the packet stays verified.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn run_command<'info>(
+    endpoint_program: &AccountInfo<'info>,
+    store: &mut Account<'info, OAppStore>,
+    extras: &[AccountInfo<'info>],
+    decoded: &msg_codec::DecodedMessage,
+    command: msg_codec::Command,
+    origin: MessageOrigin,
+    packet_nonce: u64,
+    events: &mut PendingEvents,
+) -> Result<()> {
+    match command {
+        msg_codec::Command::Compose(payload) => {
+            store.require_not_paused()?;
+            
+            send_compose_message(
+                endpoint_program,
+                store,
+                extras,
+                origin.src_eid,
+                origin.sender,
+                packet_nonce,
+                &origin.guid,
+                payload,
+            )
+        }
+        msg_codec::Command::Legacy(payload) => {
+            store.require_not_paused()?;
+            
+            handle_legacy_message(extras, decoded, payload, &origin.sender, events)
+        }
+        msg_codec::Command::SetDelegate(payload) => {
+            store.require_not_paused()?;
+            
+            update_delegate(store, endpoint_program, extras, payload.delegate, Some(origin.guid), events)
+        }
+        command => execute_command(store, command, origin, events),
+    }
+}
+
+/// Queue a compose message for this OApp's lz_compose
+///
+/// The endpoint creates one compose queue account per packet, so an
+/// occupied one is refused before the CPI.
+#[allow(clippy::too_many_arguments)]
+fn send_compose_message<'info>(
+    endpoint_program: &AccountInfo<'info>,
+    store: &Account<'info, OAppStore>,
+    accounts: &[AccountInfo<'info>],
     src_eid: u32,
     sender: [u8; 32],
-    _nonce: u64,
-    guid: [u8; 32],
-    message: Vec<u8>,
+    nonce: u64,
+    guid: &[u8; 32],
+    payload: msg_codec::ComposePayload,
 ) -> Result<()> {
-    let store = &mut ctx.accounts.store;
+    // The queue follows the executor, who pays for it
+    let queued = accounts
+        .get(1)
+        .is_some_and(|queue| queue.owner != &system_program::ID || !queue.data_is_empty());
+    if queued {
+        return Err(crate::error::ErrorCode::ComposeAlreadyQueued.into());
+    }
     
-    // Validate message size
-    if !msg_codec::MessageValidator::validate_message_size(&message) {
-        return Err(crate::error::ErrorCode::MessageTooLarge.into());
+    let compose_msg = msg_codec::ComposeMessage::typed_compose_msg(&payload);
+    let compose_message = msg_codec::ComposeMessage {
+        nonce,
+        src_eid,
+        amount_ld: 0,
+        compose_from: sender,
+        compose_msg: &compose_msg,
     }
+    .encode();
     
-    // Decode the message
-    let decoded = msg_codec::MessageCodec::decode_message(&message)?;
+    endpoint::send_compose(
+        endpoint_program,
+        &store.to_account_info(),
+        accounts,
+        &[OAppStore::SEEDS, &[store.bump]],
+        &endpoint::SendComposeParams {
+            to: store.key(),
+            guid: *guid,
+            index: COMPOSE_INDEX,
+            message: &compose_message,
+        },
+    )?;
+    
+    msg!("Compose message queued - type {}, {} bytes", payload.compose_type, payload.compose_msg.len());
+    Ok(())
+}
+
+/// Run a legacy Borsh message against the controller config
+///
+/// `accounts` is the controller config. The Borsh message repeats the nonce,
+/// timestamp and sender, which must match the validated header and peer.
+/// Replays are refused by the peer's DAO nonce like any other message; the
+/// config's own nonce belongs to `receive_layerzero_message`, so a message
+/// run there cannot hold back messages sent here.
+fn handle_legacy_message(
+    accounts: &[AccountInfo],
+    decoded: &msg_codec::DecodedMessage,
+    payload: msg_codec::LegacyPayload,
+    sender: &[u8; 32],
+    events: &mut PendingEvents,
+) -> Result<()> {
+    let [controller_config] = accounts else {
+        return Err(crate::error::ErrorCode::InvalidReceiveAccount.into());
+    };
+    if controller_config.owner != &crate::ID {
+        return Err(crate::error::ErrorCode::InvalidReceiveAccount.into());
+    }
+    let mut config = ControllerConfig::try_deserialize(&mut &controller_config.try_borrow_data()?[..])?;
     
-    // Validate sender matches peer configuration
-    if ctx.accounts.peer_config.peer_address != sender {
+    let message = payload.message()?;
+    if message.nonce != decoded.nonce || message.timestamp != decoded.timestamp {
+        return Err(crate::error::ErrorCode::InvalidLzMessage.into());
+    }
+    // EVM peers are left-padded to 32 bytes
+    if sender[..12] != [0; 12] || message.sender != sender[12..] {
         return Err(crate::error::ErrorCode::UnauthorizedSender.into());
     }
     
-    // Process compose message (simplified - could be more complex)
-    msg!("Compose message processed - Command: {}, Nonce: {}, From EID: {}", 
-         decoded.command, decoded.nonce, src_eid);
+    let command_type = execute_legacy_command(&mut config, controller_config.key(), message.command)?;
+    config.last_update = Clock::get()?.unix_timestamp;
+    config.try_serialize(&mut &mut controller_config.try_borrow_mut_data()?[..])?;
     
-    // Send compose response if needed
-    let response_message = msg_codec::MessageCodec::encode_message(
-        decoded.command,
-        store.nonce + 1,
-        Clock::get()?.unix_timestamp,
-        b"compose_response",
-    )?;
+    msg!("Legacy command processed - {}", command_type);
+    events.push(CrossChainMessageProcessed {
+        nonce: message.nonce,
+        sender: message.sender,
+        command_type: command_type.to_string(),
+        timestamp: config.last_update,
+        success: true,
+    });
+    Ok(())
+}
+
+/// Check the message attestation against the DAO signer set
+///
+/// An attestation is verified whenever present, and required for attested
+/// commands once a signer threshold is configured.
+fn verify_attestation(
+    store: &Account<OAppStore>,
+    decoded: &msg_codec::DecodedMessage,
+    command: &msg_codec::Command,
+) -> Result<()> {
+    let dao_config = &store.dao_config;
     
-    let accounts = vec![ctx.accounts.endpoint_accounts.clone()];
-    endpoint::send_compose(
-        &ctx.accounts.endpoint,
-        &accounts,
-        &sender,
-        &guid,
-        0, // index
-        &response_message,
+    let attestation = match decoded.attestation()? {
+        Some(attestation) => attestation,
+        None if dao_config.signer_threshold > 0 && command.requires_attestation()? => {
+            return Err(crate::error::ErrorCode::AttestationRequired.into());
+        }
+        None => return Ok(()),
+    };
+    
+    msg_codec::MessageValidator::verify_attestation(
+        &decoded.attestation_hash(&store.key()),
+        &attestation,
+        &dao_config.signers,
+        dao_config.signer_threshold,
+    )
+}
+
+/// Save a failed command into its `FailedMessage` PDA
+///
+/// `accounts` are the failed message PDA, the payer and the system program.
+#[allow(clippy::too_many_arguments)]
+fn store_failed_message<'info>(
+    accounts: &[AccountInfo<'info>],
+    store_key: &Pubkey,
+    origin: MessageOrigin,
+    packet_nonce: u64,
+    error: &Error,
+    message: &[u8],
+    events: &mut PendingEvents,
+) -> Result<()> {
+    let [failed_message, payer, system_program_account] = accounts else {
+        return Err(crate::error::ErrorCode::InvalidFailedMessageAccount.into());
+    };
+    
+    let (expected, bump) = FailedMessage::find_pda(store_key, &origin.guid);
+    if failed_message.key() != expected {
+        return Err(crate::error::ErrorCode::InvalidFailedMessageAccount.into());
+    }
+    
+    let space = FailedMessage::space(message.len());
+    system_program::create_account(
+        CpiContext::new_with_signer(
+            system_program_account.clone(),
+            system_program::CreateAccount {
+                from: payer.clone(),
+                to: failed_message.clone(),
+            },
+            &[&[FailedMessage::SEEDS, store_key.as_ref(), &origin.guid, &[bump]]],
+        ),
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        &crate::ID,
     )?;
     
-    store.nonce += 1;
+    let record = FailedMessage {
+        store: *store_key,
+        guid: origin.guid,
+        src_eid: origin.src_eid,
+        sender: origin.sender,
+        nonce: origin.nonce,
+        packet_nonce,
+        error_code: error_code(error),
+        payer: payer.key(),
+        bump,
+        message: message.to_vec(),
+    };
+    record.try_serialize(&mut &mut failed_message.try_borrow_mut_data()?[..])?;
+    
+    msg!("Command failed, message stored for retry - Error: {}", record.error_code);
+    events.push(FailedMessageStored {
+        guid: record.guid,
+        src_eid: record.src_eid,
+        nonce: record.nonce,
+        error_code: record.error_code,
+    });
+    Ok(())
+}
+
+/// Dispatch a decoded command to its handler
+///
+/// While paused only unpause gets through; an envelope is checked per
+/// sub-command.
+fn execute_command(
+    store: &mut OAppStore,
+    command: msg_codec::Command,
+    origin: MessageOrigin,
+    events: &mut PendingEvents,
+) -> Result<()> {
+    if !matches!(
+        command,
+        msg_codec::Command::EmergencyUnpause | msg_codec::Command::Envelope(_)
+    ) {
+        store.require_not_paused()?;
+    }
+    
+    match command {
+        msg_codec::Command::UpdateCollectionMetadata(payload) => {
+            handle_update_collection_metadata(store, payload)
+        }
+        msg_codec::Command::EmergencyPause => handle_emergency_pause(store, origin, events),
+        msg_codec::Command::EmergencyUnpause => handle_emergency_unpause(store, origin, events),
+        msg_codec::Command::TransferAuthority(payload) => {
+            handle_transfer_authority(store, payload, origin, events)
+        }
+        msg_codec::Command::BatchUpdateCnfts(payload) => handle_batch_update_cnfts(store, payload),
+        msg_codec::Command::SetMinVersion(payload) => handle_set_min_version(store, payload),
+        msg_codec::Command::Envelope(payload) => handle_envelope(store, payload, origin, events),
+        // Composing, legacy and set delegate commands need extra accounts,
+        // so only a top-level message can carry them
+        msg_codec::Command::Compose(_)
+        | msg_codec::Command::Legacy(_)
+        | msg_codec::Command::SetDelegate(_) => {
+            Err(crate::error::ErrorCode::UnsupportedCommand.into())
+        }
+        // Receipts only travel from Solana to the DAO
+        msg_codec::Command::Receipt(_) => Err(crate::error::ErrorCode::UnsupportedCommand.into()),
+    }
+}
+
+/// Handle envelope command
+///
+/// Sub-commands run in order against the same store. The first failure
+/// fails the whole envelope: the transaction reverts, or with the vault on
+/// the store is rolled back, so none of the earlier sub-commands take
+/// effect. Their events are dropped and only the failure is reported.
+fn handle_envelope(
+    store: &mut OAppStore,
+    payload: msg_codec::EnvelopePayload,
+    origin: MessageOrigin,
+    events: &mut PendingEvents,
+) -> Result<()> {
+    let reported = events.0.len();
+    for index in 0..payload.len() {
+        let result = payload
+            .get(index)
+            .and_then(|command| execute_command(store, command, origin, events));
+
+        if let Err(error) = result {
+            events.0.truncate(reported);
+            events.push(EnvelopeCommandFailed {
+                nonce: origin.nonce,
+                index: index as u32,
+                command: payload.command_at(index)?,
+                error_code: error_code(&error),
+            });
+            return Err(error);
+        }
+    }
     
+    msg!("Envelope processed - {} commands", payload.len());
     Ok(())
 }
 
+/// Set on the reported code of a builtin program error
+///
+/// Builtin errors live in the upper half of the runtime's `u64` code, so
+/// truncating them would report 0, which reads as success.
+pub const BUILTIN_ERROR_FLAG: u32 = 1 << 31;
+
+/// Numeric code of an error, as reported in events and receipts
+///
+/// Never 0. Custom and Anchor codes are reported as they are; builtin
+/// program errors as their index with `BUILTIN_ERROR_FLAG` set.
+fn error_code(error: &Error) -> u32 {
+    let code = match error {
+        Error::AnchorError(error) => return error.error_code_number,
+        Error::ProgramError(error) => u64::from(error.program_error.clone()),
+    };
+    match u32::try_from(code) {
+        Ok(custom) => custom,
+        Err(_) => (code >> 32) as u32 | BUILTIN_ERROR_FLAG,
+    }
+}
+
 /// Handle update collection metadata command
 fn handle_update_collection_metadata(
     store: &mut OAppStore,
-    payload: &[u8],
+    metadata_payload: msg_codec::UpdateMetadataPayload,
 ) -> Result<()> {
-    let metadata_payload = msg_codec::MessageCodec::decode_update_metadata_payload(payload)?;
-    
-    // Update collection metadata
-    store.collection_metadata.uri = metadata_payload.uri;
-    store.collection_metadata.name = metadata_payload.name;
-    store.collection_metadata.symbol = metadata_payload.symbol;
+    // Update collection metadata (strings are only copied once validated)
+    store.collection_metadata.uri = metadata_payload.uri.as_str()?.to_string();
+    store.collection_metadata.name = metadata_payload.name.as_str()?.to_string();
+    store.collection_metadata.symbol = metadata_payload.symbol.as_str()?.to_string();
     
     msg!("Collection metadata updated - Name: {}, Symbol: {}, URI: {}", 
          store.collection_metadata.name,
@@ -241,29 +643,307 @@ fn handle_update_collection_metadata(
 }
 
 /// Handle emergency pause command
-fn handle_emergency_pause(_store: &mut OAppStore) -> Result<()> {
-    // Emergency pause logic would go here
+fn handle_emergency_pause(
+    store: &mut OAppStore,
+    origin: MessageOrigin,
+    events: &mut PendingEvents,
+) -> Result<()> {
+    store.paused = true;
+    
     msg!("Emergency pause activated");
+    events.push(EmergencyPaused {
+        src_eid: origin.src_eid,
+        sender: origin.sender,
+        nonce: origin.nonce,
+        guid: origin.guid,
+    });
     Ok(())
 }
 
 /// Handle emergency unpause command
-fn handle_emergency_unpause(_store: &mut OAppStore) -> Result<()> {
-    // Emergency unpause logic would go here
+fn handle_emergency_unpause(
+    store: &mut OAppStore,
+    origin: MessageOrigin,
+    events: &mut PendingEvents,
+) -> Result<()> {
+    store.paused = false;
+    
     msg!("Emergency unpause activated");
+    events.push(EmergencyUnpaused {
+        src_eid: origin.src_eid,
+        sender: origin.sender,
+        nonce: origin.nonce,
+        guid: origin.guid,
+    });
     Ok(())
 }
 
 /// Handle transfer authority command
-fn handle_transfer_authority(_store: &mut OAppStore, _payload: &[u8]) -> Result<()> {
-    // Transfer authority logic would go here
-    msg!("Authority transfer processed");
+///
+/// Only proposes the new admin; the key must sign `accept_admin` before the
+/// deadline, so a mistyped key cannot take over. A new proposal replaces any
+/// pending one.
+fn handle_transfer_authority(
+    store: &mut OAppStore,
+    payload: msg_codec::TransferAuthorityPayload,
+    origin: MessageOrigin,
+    events: &mut PendingEvents,
+) -> Result<()> {
+    if payload.new_authority == Pubkey::default() {
+        return Err(crate::error::ErrorCode::InvalidPendingAdmin.into());
+    }
+    
+    let deadline = Clock::get()?.unix_timestamp + crate::constants::ADMIN_TRANSFER_WINDOW_SECONDS;
+    store.pending_admin = Some(payload.new_authority);
+    store.pending_admin_deadline = deadline;
+    
+    msg!("Authority transfer proposed - New authority: {}, Deadline: {}",
+         payload.new_authority, deadline);
+    events.push(AdminTransferProposed {
+        admin: store.admin,
+        pending_admin: payload.new_authority,
+        deadline,
+        guid: origin.guid,
+    });
     Ok(())
 }
 
 /// Handle batch update cNFTs command
-fn handle_batch_update_cnfts(_store: &mut OAppStore, _payload: &[u8]) -> Result<()> {
+fn handle_batch_update_cnfts(
+    _store: &mut OAppStore,
+    payload: msg_codec::BatchUpdateCnftsPayload,
+) -> Result<()> {
     // Batch update logic would go here
-    msg!("Batch cNFT update processed");
+    msg!("Batch cNFT update processed - {} updates", payload.len());
+    Ok(())
+}
+
+/// Handle set minimum version command
+fn handle_set_min_version(
+    store: &mut OAppStore,
+    payload: msg_codec::SetMinVersionPayload,
+) -> Result<()> {
+    let min_version = payload.min_version;
+    
+    // Only raise to a version this program can still decode
+    if !msg_codec::MessageCodec::is_supported_version(min_version)
+        || min_version < store.min_message_version
+    {
+        return Err(crate::error::ErrorCode::InvalidMinVersion.into());
+    }
+    
+    store.min_message_version = min_version;
+    
+    msg!("Minimum message version set to {}", min_version);
     Ok(())
 }
+
+#[event]
+pub struct MessageCleared {
+    pub guid: [u8; 32],
+    pub src_eid: u32,
+    pub sender: [u8; 32],
+    pub nonce: u64,
+}
+
+#[event]
+pub struct CommandExecuted {
+    pub guid: [u8; 32],
+    pub src_eid: u32,
+    pub sender: [u8; 32],
+    pub nonce: u64,
+    pub command: u8,
+    pub result_code: u32,
+}
+
+/// A validated command that failed and was stored in the failed message
+/// vault
+#[event]
+pub struct CommandRejected {
+    pub guid: [u8; 32],
+    pub src_eid: u32,
+    pub sender: [u8; 32],
+    pub nonce: u64,
+    pub command: u8,
+    pub result_code: u32,
+}
+
+#[event]
+pub struct AdminTransferProposed {
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+    pub deadline: i64,
+    pub guid: [u8; 32],
+}
+
+#[event]
+pub struct FailedMessageStored {
+    pub guid: [u8; 32],
+    pub src_eid: u32,
+    pub nonce: u64,
+    pub error_code: u32,
+}
+
+#[event]
+pub struct EmergencyPaused {
+    pub src_eid: u32,
+    pub sender: [u8; 32],
+    pub nonce: u64,
+    pub guid: [u8; 32],
+}
+
+#[event]
+pub struct EmergencyUnpaused {
+    pub src_eid: u32,
+    pub sender: [u8; 32],
+    pub nonce: u64,
+    pub guid: [u8; 32],
+}
+
+#[event]
+pub struct EnvelopeCommandFailed {
+    pub nonce: u64,
+    pub index: u32,
+    pub command: u8,
+    pub error_code: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::program_error::ProgramError;
+    use anchor_lang::Event;
+    use crate::state::message_types::MessageCommand;
+
+    #[test]
+    fn test_failed_envelope_reports_only_its_failure() {
+        // An all-zero store decodes with empty strings and no pending admin
+        let mut store = OAppStore::deserialize(&mut &[0u8; OAppStore::LEN][..]).unwrap();
+        store.paused = true;
+        let payload = msg_codec::EnvelopePayload::encode_commands(&[
+            msg_codec::Command::EmergencyUnpause,
+            msg_codec::Command::SetMinVersion(msg_codec::SetMinVersionPayload { min_version: 0xEE }),
+        ]);
+        let command = msg_codec::Command::decode(msg_codec::MessageCodec::COMMAND_ENVELOPE, &payload).unwrap();
+        let origin = MessageOrigin {
+            src_eid: 30101,
+            sender: [1; 32],
+            nonce: 7,
+            guid: [2; 32],
+        };
+
+        let mut events = PendingEvents::default();
+        let error = execute_command(&mut store, command, origin, &mut events).unwrap_err();
+        assert_eq!(error, crate::error::ErrorCode::InvalidMinVersion.into());
+
+        // The unpause ran and is left for lz_receive to roll back, but only
+        // the failure is reported
+        assert!(!store.paused);
+        let failure = EnvelopeCommandFailed {
+            nonce: 7,
+            index: 1,
+            command: msg_codec::MessageCodec::COMMAND_SET_MIN_VERSION,
+            error_code: error_code(&error),
+        };
+        assert_eq!(events.0, [failure.data()]);
+    }
+
+    /// Run a legacy message against a config that `receive_layerzero_message`
+    /// took up to its last nonce
+    fn run_legacy(nonce: u64, command: MessageCommand) -> Result<()> {
+        let config = ControllerConfig::deserialize(&mut &[0u8; ControllerConfig::space()][..]).unwrap();
+        let mut data = Vec::new();
+        ControllerConfig { message_nonce: u64::MAX, ..config }.try_serialize(&mut data).unwrap();
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let account = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &crate::ID, false, 0);
+
+        let sender = [0x11; 20];
+        let message = crate::state::message_types::CrossChainMessage {
+            command,
+            payload: vec![],
+            nonce,
+            timestamp: 1_700_000_000,
+            sender,
+        };
+        let encoded = msg_codec::MessageCodec::encode_message_borsh(&message, &[]).unwrap();
+        let decoded = msg_codec::MessageCodec::decode_message(&encoded).unwrap();
+        let msg_codec::Command::Legacy(payload) = decoded.decode_command().unwrap() else {
+            panic!("expected a legacy command");
+        };
+        let peer = [[0; 12].as_slice(), &sender].concat().try_into().unwrap();
+        handle_legacy_message(&[account], &decoded, payload, &peer, &mut PendingEvents::default())
+    }
+
+    #[test]
+    fn test_legacy_message_cannot_transfer_authority() {
+        let transfer = MessageCommand::TransferAuthority { new_authority: Pubkey::new_unique() };
+        assert_eq!(run_legacy(6, transfer).unwrap_err(), crate::error::ErrorCode::UnsupportedCommand.into());
+    }
+
+    #[test]
+    fn test_legacy_message_ignores_config_nonce() {
+        // The command runs, and fails on its own URI check
+        let new_uri = "x".repeat(crate::constants::MAX_URI_LENGTH + 1);
+        let error = run_legacy(1, MessageCommand::UpdateCollectionMetadata { new_uri }).unwrap_err();
+        assert_eq!(error, crate::error::ErrorCode::UriTooLong.into());
+    }
+
+    #[test]
+    fn test_error_code_of_anchor_errors() {
+        let error: Error = crate::error::ErrorCode::InvalidNonce.into();
+        assert_eq!(error_code(&error), 6002);
+
+        let error: Error = anchor_lang::error::ErrorCode::ConstraintSeeds.into();
+        assert_eq!(error_code(&error), 2006);
+    }
+
+    #[test]
+    fn test_error_code_of_custom_program_errors() {
+        let error: Error = ProgramError::Custom(42).into();
+        assert_eq!(error_code(&error), 42);
+
+        // Custom(0) is carried by the runtime as a builtin
+        let error: Error = ProgramError::Custom(0).into();
+        assert_eq!(error_code(&error), 1 | BUILTIN_ERROR_FLAG);
+    }
+
+    #[test]
+    fn test_error_code_of_builtin_program_errors() {
+        let builtins = [
+            ProgramError::InvalidArgument,
+            ProgramError::InvalidInstructionData,
+            ProgramError::InvalidAccountData,
+            ProgramError::AccountDataTooSmall,
+            ProgramError::InsufficientFunds,
+            ProgramError::IncorrectProgramId,
+            ProgramError::MissingRequiredSignature,
+            ProgramError::AccountAlreadyInitialized,
+            ProgramError::UninitializedAccount,
+            ProgramError::NotEnoughAccountKeys,
+            ProgramError::AccountBorrowFailed,
+            ProgramError::MaxSeedLengthExceeded,
+            ProgramError::InvalidSeeds,
+            ProgramError::BorshIoError(String::new()),
+            ProgramError::AccountNotRentExempt,
+            ProgramError::UnsupportedSysvar,
+            ProgramError::IllegalOwner,
+            ProgramError::MaxAccountsDataAllocationsExceeded,
+            ProgramError::InvalidRealloc,
+            ProgramError::MaxInstructionTraceLengthExceeded,
+            ProgramError::BuiltinProgramsMustConsumeComputeUnits,
+            ProgramError::InvalidAccountOwner,
+            ProgramError::ArithmeticOverflow,
+            ProgramError::Immutable,
+            ProgramError::IncorrectAuthority,
+        ];
+        let mut codes = Vec::new();
+        for builtin in builtins {
+            let index = (u64::from(builtin.clone()) >> 32) as u32;
+            let code = error_code(&builtin.into());
+            assert_eq!(code, index | BUILTIN_ERROR_FLAG);
+            assert!(!codes.contains(&code));
+            codes.push(code);
+        }
+    }
+}
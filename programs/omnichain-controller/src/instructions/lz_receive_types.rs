@@ -1,5 +1,6 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
+use crate::instructions::receive_accounts::{ReceiveExtras, ReceiveLayout};
 
 /// LayerZero receive types instruction - Returns account list for Executor
 #[derive(Accounts)]
@@ -16,6 +17,14 @@ pub struct LzReceiveTypesContext<'info> {
         bump = lz_receive_types.bump
     )]
     pub lz_receive_types: Account<'info, LzReceiveTypes>,
+    
+    /// Send accounts of receipts to the peer, required while the store
+    /// sends receipts
+    #[account(
+        seeds = [ReceiptConfig::SEEDS, store.key().as_ref(), &src_eid.to_le_bytes()],
+        bump = receipt_config.bump
+    )]
+    pub receipt_config: Option<Account<'info, ReceiptConfig>>,
 }
 
 /// LayerZero account structure for account discovery
@@ -52,7 +61,7 @@ pub fn lz_receive_types_handler(
         },
     ];
     
-    // 2. The peer that sent the message (read-only) - REQUIRED SECOND
+    // 2. The peer that sent the message (writable nonce state) - REQUIRED SECOND
     let store_key = store.key();
     let peer_seeds = [
         PeerConfig::SEEDS,
@@ -64,7 +73,7 @@ pub fn lz_receive_types_handler(
     accounts.push(LzAccount {
         pubkey: peer_key,
         is_signer: false,
-        is_writable: false,
+        is_writable: true,
     });
     
     // 3. lz_receive_types PDA (read-only) - REQUIRED THIRD
@@ -74,142 +83,56 @@ pub fn lz_receive_types_handler(
         is_writable: false,
     });
     
-    // 4. LayerZero endpoint program (read-only) - REQUIRED FOURTH
+    // 4. Receipt config (read-only), or this program when the store sends
+    //    no receipts, leaving the optional account empty
+    accounts.push(LzAccount {
+        pubkey: match &ctx.accounts.receipt_config {
+            Some(receipt_config) if store.send_receipts => receipt_config.key(),
+            _ => crate::ID,
+        },
+        is_signer: false,
+        is_writable: false,
+    });
+    
+    // 5. LayerZero endpoint program (read-only) - REQUIRED FIFTH
     accounts.push(LzAccount {
         pubkey: store.endpoint,
         is_signer: false,
         is_writable: false,
     });
     
-    // 5. Accounts specifically required for calling Endpoint::clear() - CRITICAL
-    // These are the accounts that the LayerZero endpoint needs to clear the message
-    accounts.extend(get_accounts_for_clear(
-        store.endpoint,
+    // 6. Event CPI accounts (read-only)
+    let (event_authority, _) = Pubkey::find_program_address(&[b"__event_authority"], &crate::ID);
+    accounts.extend([
+        LzAccount {
+            pubkey: event_authority,
+            is_signer: false,
+            is_writable: false,
+        },
+        LzAccount {
+            pubkey: crate::ID,
+            is_signer: false,
+            is_writable: false,
+        },
+    ]);
+    
+    // 7. Accounts for Endpoint::clear(), then any extras the message needs,
+    //    the failed message accounts and the receipt accounts
+    let extras = ReceiveExtras::for_message(&params.message);
+    let layout = ReceiveLayout::new(
+        &store.endpoint,
         &store.key(),
         params.src_eid,
         &params.sender,
-        params.nonce,
         &params.guid,
-    )?);
-    
-    // 6. (Optional) If compose message, add accounts for send_compose()
-    if is_compose_message(&params.message) {
-        accounts.extend(get_accounts_for_send_compose(
-            store.endpoint,
-            &store.key(),        // payer = this PDA
-            &store.key(),        // receiver (self-compose)
-            &params.guid,
-            &params.message,
-        )?);
-    }
+        extras,
+    )
+    .with_vault(store, &store.key(), &params.guid)
+    .with_receipt(store, &store.key(), params.src_eid, ctx.accounts.receipt_config.as_deref())?;
+    accounts.extend(layout.accounts().cloned());
     
     msg!("lz_receive_types: Returning {} accounts for src_eid: {}", 
          accounts.len(), params.src_eid);
     
     Ok(accounts)
 }
-
-/// Get accounts required for endpoint clear CPI call
-fn get_accounts_for_clear(
-    endpoint_program: Pubkey,
-    oapp_address: &Pubkey,
-    src_eid: u32,
-    sender: &[u8; 32],
-    nonce: u64,
-    guid: &[u8; 32],
-) -> Result<Vec<LzAccount>> {
-    // These are the minimum accounts required by LayerZero endpoint for clear() CPI
-    // The exact accounts depend on the LayerZero implementation
-    let mut clear_accounts = vec![];
-    
-    // Endpoint configuration account (read-only)
-    let (endpoint_config, _) = Pubkey::find_program_address(
-        &[b"EndpointConfig"],
-        &endpoint_program,
-    );
-    clear_accounts.push(LzAccount {
-        pubkey: endpoint_config,
-        is_signer: false,
-        is_writable: false,
-    });
-    
-    // Receive library account (read-only)
-    let (receive_library, _) = Pubkey::find_program_address(
-        &[b"ReceiveLibrary", oapp_address.as_ref()],
-        &endpoint_program,
-    );
-    clear_accounts.push(LzAccount {
-        pubkey: receive_library,
-        is_signer: false,
-        is_writable: false,
-    });
-    
-    // Nonce account (writable) - for replay protection
-    let nonce_seed = format!("{}:{}:{}", src_eid, sender.iter().map(|b| format!("{:02x}", b)).collect::<String>(), nonce);
-    let (nonce_account, _) = Pubkey::find_program_address(
-        &[b"Nonce", nonce_seed.as_bytes()],
-        &endpoint_program,
-    );
-    clear_accounts.push(LzAccount {
-        pubkey: nonce_account,
-        is_signer: false,
-        is_writable: true,
-    });
-    
-    // Payload hash account (writable) - for message verification
-    let (payload_hash, _) = Pubkey::find_program_address(
-        &[b"PayloadHash", guid],
-        &endpoint_program,
-    );
-    clear_accounts.push(LzAccount {
-        pubkey: payload_hash,
-        is_signer: false,
-        is_writable: true,
-    });
-    
-    Ok(clear_accounts)
-}
-
-/// Get accounts required for send compose CPI call
-fn get_accounts_for_send_compose(
-    endpoint_program: Pubkey,
-    _payer: &Pubkey,
-    receiver: &Pubkey,
-    guid: &[u8; 32],
-    _message: &[u8],
-) -> Result<Vec<LzAccount>> {
-    let mut compose_accounts = vec![];
-    
-    // Compose queue account (writable)
-    let (compose_queue, _) = Pubkey::find_program_address(
-        &[b"ComposeQueue", receiver.as_ref(), guid],
-        &endpoint_program,
-    );
-    compose_accounts.push(LzAccount {
-        pubkey: compose_queue,
-        is_signer: false,
-        is_writable: true,
-    });
-    
-    // System program for account creation
-    compose_accounts.push(LzAccount {
-        pubkey: anchor_lang::system_program::ID,
-        is_signer: false,
-        is_writable: false,
-    });
-    
-    Ok(compose_accounts)
-}
-
-/// Check if message is a compose message
-fn is_compose_message(message: &[u8]) -> bool {
-    // Check if message has compose flag or specific compose message type
-    // This depends on your message codec implementation
-    if message.len() < 1 {
-        return false;
-    }
-    
-    // Simple check - you may need to adjust based on your message format
-    // For now, assume first byte indicates compose if it's 0xFF
-    message[0] == 0xFF
-}
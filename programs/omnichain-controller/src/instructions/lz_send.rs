@@ -0,0 +1,290 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use crate::state::*;
+use crate::cpi::endpoint;
+use crate::instructions::enforced_options::combine_options;
+
+/// Parameters for lz_send instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LzSendParams {
+    pub dst_eid: u32,
+    pub command: u8,
+    /// ABI-encoded command payload, as `MessageCodec` encodes it
+    pub payload: Vec<u8>,
+    pub options: Vec<u8>,
+    /// Most native fee the payer is willing to pay
+    pub native_fee: u64,
+}
+
+/// Parameters for quote instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct QuoteParams {
+    pub dst_eid: u32,
+    pub command: u8,
+    pub payload: Vec<u8>,
+    pub options: Vec<u8>,
+    pub pay_in_lz_token: bool,
+}
+
+/// Quote the fee of sending a message to a configured peer
+#[derive(Accounts)]
+#[instruction(params: QuoteParams)]
+pub struct Quote<'info> {
+    #[account(
+        seeds = [OAppStore::SEEDS],
+        bump = store.bump
+    )]
+    pub store: Account<'info, OAppStore>,
+    
+    #[account(
+        seeds = [PeerConfig::SEEDS, store.key().as_ref(), &params.dst_eid.to_le_bytes()],
+        bump = peer_config.bump,
+        constraint = peer_config.trusted @ crate::error::ErrorCode::UntrustedPeer
+    )]
+    pub peer_config: Account<'info, PeerConfig>,
+    
+    /// Options enforced on this command to the destination, if any
+    /// CHECK: Address checked by seeds; read by `combine_options`
+    #[account(
+        seeds = [EnforcedOptions::SEEDS, store.key().as_ref(), &params.dst_eid.to_le_bytes(), &[params.command]],
+        bump
+    )]
+    pub enforced_options: UncheckedAccount<'info>,
+    
+    /// LayerZero endpoint program
+    /// CHECK: Checked against the store's endpoint
+    #[account(address = store.endpoint @ crate::error::ErrorCode::InvalidEndpoint)]
+    pub endpoint: AccountInfo<'info>,
+    
+    // Endpoint quote accounts follow as remaining accounts
+}
+
+/// Send a message to a configured peer
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(params: LzSendParams)]
+pub struct LzSend<'info> {
+    #[account(
+        seeds = [OAppStore::SEEDS],
+        bump = store.bump
+    )]
+    pub store: Account<'info, OAppStore>,
+    
+    #[account(
+        mut,
+        seeds = [PeerConfig::SEEDS, store.key().as_ref(), &params.dst_eid.to_le_bytes()],
+        bump = peer_config.bump,
+        constraint = peer_config.trusted @ crate::error::ErrorCode::UntrustedPeer
+    )]
+    pub peer_config: Account<'info, PeerConfig>,
+    
+    /// Options enforced on this command to the destination, if any
+    /// CHECK: Address checked by seeds; read by `combine_options`
+    #[account(
+        seeds = [EnforcedOptions::SEEDS, store.key().as_ref(), &params.dst_eid.to_le_bytes(), &[params.command]],
+        bump
+    )]
+    pub enforced_options: UncheckedAccount<'info>,
+    
+    /// Holds the native fee while the endpoint charges it
+    #[account(
+        mut,
+        seeds = [FeeVault::SEEDS, store.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// Receives whatever part of the fee the endpoint did not charge
+    /// CHECK: Any account can be refunded
+    #[account(mut)]
+    pub refund_address: UncheckedAccount<'info>,
+    
+    #[account(
+        constraint = authority.key() == store.admin || authority.key() == store.delegate
+            @ crate::error::ErrorCode::InsufficientAuthority
+    )]
+    pub authority: Signer<'info>,
+    
+    /// LayerZero endpoint program
+    /// CHECK: Checked against the store's endpoint
+    #[account(address = store.endpoint @ crate::error::ErrorCode::InvalidEndpoint)]
+    pub endpoint: AccountInfo<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    // Endpoint send accounts follow as remaining accounts
+}
+
+/// Handler for sending a message to a peer
+///
+/// The payer deposits `native_fee` in the fee vault, which pays the
+/// endpoint; the unspent rest goes to `refund_address`. The options sent are
+/// the command's enforced options merged with `params.options`.
+pub fn lz_send_handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, LzSend<'info>>,
+    params: LzSendParams,
+) -> Result<()> {
+    let store = &ctx.accounts.store;
+    store.require_not_paused()?;
+    
+    require_outbound_command(params.command)?;
+    let peer_config = &mut ctx.accounts.peer_config;
+    let (nonce, message) = encode_outbound(peer_config, params.command, &params.payload)?;
+    let options = combine_options(&ctx.accounts.enforced_options, &params.options)?;
+    
+    // Deposit the fee
+    let fee_vault = &ctx.accounts.fee_vault;
+    let vault_balance = fee_vault.lamports();
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.payer.to_account_info(),
+                to: fee_vault.to_account_info(),
+            },
+        ),
+        params.native_fee,
+    )?;
+    
+    let store_key = store.key();
+    let store_seeds: &[&[u8]] = &[OAppStore::SEEDS, &[store.bump]];
+    let vault_seeds: &[&[u8]] = &[FeeVault::SEEDS, store_key.as_ref(), &[ctx.bumps.fee_vault]];
+    endpoint::send(
+        &ctx.accounts.endpoint,
+        &store.to_account_info(),
+        &fee_vault.to_account_info(),
+        ctx.remaining_accounts,
+        &[store_seeds, vault_seeds],
+        &endpoint::SendParams {
+            dst_eid: params.dst_eid,
+            receiver: peer_config.peer_address,
+            message: &message,
+            options: &options,
+            native_fee: params.native_fee,
+            lz_token_fee: 0,
+        },
+    )?;
+    
+    // Refund what the endpoint left in the vault
+    let refund = fee_vault
+        .lamports()
+        .saturating_sub(vault_balance)
+        .min(params.native_fee);
+    if refund > 0 {
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: fee_vault.to_account_info(),
+                    to: ctx.accounts.refund_address.to_account_info(),
+                },
+                &[vault_seeds],
+            ),
+            refund,
+        )?;
+    }
+    
+    peer_config.outbound_nonce = nonce;
+    
+    let native_fee = params.native_fee - refund;
+    msg!("Message sent to EID {} - Nonce: {}, fee: {}, refund: {}",
+         params.dst_eid, nonce, native_fee, refund);
+    emit_cpi!(MessageSent {
+        dst_eid: params.dst_eid,
+        receiver: peer_config.peer_address,
+        nonce,
+        command: params.command,
+        native_fee,
+        refund,
+    });
+    Ok(())
+}
+
+/// Handler for quoting a send
+///
+/// Encodes the message and merges the options exactly as `lz_send` would
+/// right now, so the quote covers what is really sent.
+pub fn quote_handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, Quote<'info>>,
+    params: QuoteParams,
+) -> Result<endpoint::MessagingFee> {
+    require_outbound_command(params.command)?;
+    let peer_config = &ctx.accounts.peer_config;
+    let (_, message) = encode_outbound(peer_config, params.command, &params.payload)?;
+    let options = combine_options(&ctx.accounts.enforced_options, &params.options)?;
+    
+    let fee = endpoint::quote(
+        &ctx.accounts.endpoint,
+        ctx.remaining_accounts,
+        &endpoint::QuoteParams {
+            sender: ctx.accounts.store.key(),
+            dst_eid: params.dst_eid,
+            receiver: peer_config.peer_address,
+            message: &message,
+            options: &options,
+            pay_in_lz_token: params.pay_in_lz_token,
+        },
+    )?;
+    
+    msg!("Quote for EID {} - native fee: {}, lz token fee: {}",
+         params.dst_eid, fee.native_fee, fee.lz_token_fee);
+    Ok(fee)
+}
+
+/// Fail unless `command` is one lz_send may send
+fn require_outbound_command(command: u8) -> Result<()> {
+    if !msg_codec::MessageCodec::OUTBOUND_COMMANDS.contains(&command) {
+        return Err(crate::error::ErrorCode::UnsupportedCommand.into());
+    }
+    Ok(())
+}
+
+/// Encode the next message to a peer, returning its nonce
+pub(crate) fn encode_outbound(peer_config: &PeerConfig, command: u8, payload: &[u8]) -> Result<(u64, Vec<u8>)> {
+    let nonce = peer_config
+        .outbound_nonce
+        .checked_add(1)
+        .ok_or(crate::error::ErrorCode::InvalidNonce)?;
+    let message = msg_codec::MessageCodec::encode_message(
+        command,
+        nonce,
+        Clock::get()?.unix_timestamp,
+        payload,
+    )?;
+    if !msg_codec::MessageValidator::validate_message_size(&message) {
+        return Err(crate::error::ErrorCode::MessageTooLarge.into());
+    }
+    
+    // Only send what a peer running this codec would accept
+    msg_codec::MessageCodec::decode_message(&message)?.decode_command()?;
+    
+    Ok((nonce, message))
+}
+
+#[event]
+pub struct MessageSent {
+    pub dst_eid: u32,
+    pub receiver: [u8; 32],
+    pub nonce: u64,
+    pub command: u8,
+    pub native_fee: u64,
+    pub refund: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_receipts_cannot_be_sent() {
+        let unsupported: Result<()> = Err(crate::error::ErrorCode::UnsupportedCommand.into());
+        assert_eq!(require_outbound_command(msg_codec::MessageCodec::COMMAND_RECEIPT), unsupported);
+        assert_eq!(require_outbound_command(u8::MAX), unsupported);
+
+        assert!(require_outbound_command(msg_codec::MessageCodec::COMMAND_EMERGENCY_PAUSE).is_ok());
+        assert!(require_outbound_command(msg_codec::MessageCodec::COMMAND_SET_DELEGATE).is_ok());
+    }
+}
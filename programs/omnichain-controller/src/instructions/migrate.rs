@@ -0,0 +1,197 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+use crate::state::*;
+
+/// Move accounts written by the first deployment to the current layout
+///
+/// The store is migrated if it still has the old layout; old peers follow
+/// as remaining accounts. Each account is migrated once, by its length.
+#[derive(Accounts)]
+pub struct Migrate<'info> {
+    /// CHECK: Decoded by the handler in whichever layout it has
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [OAppStore::SEEDS],
+        bump
+    )]
+    pub store: UncheckedAccount<'info>,
+    
+    /// Pays the rent the larger accounts need
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    // Peers in the old layout follow as remaining accounts
+}
+
+/// `OAppStore` as first deployed
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct OAppStoreV1 {
+    pub admin: Pubkey,
+    pub endpoint: Pubkey,
+    pub delegate: Pubkey,
+    pub bump: u8,
+    pub collection_metadata: CollectionMetadata,
+    pub dao_config: DaoConfigV1,
+    pub nonce: u64,
+    pub processed_messages: u64,
+}
+
+impl OAppStoreV1 {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // admin
+        32 + // endpoint
+        32 + // delegate
+        1 + // bump
+        CollectionMetadata::LEN + // collection_metadata
+        DaoConfigV1::LEN + // dao_config
+        8 + // nonce
+        8; // processed_messages
+}
+
+/// `DaoConfig` as first deployed, before the DAO signer set
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct DaoConfigV1 {
+    pub authorized_dao: [u8; 20],
+    pub ethereum_eid: u32,
+    pub voting_period: u64,
+    pub quorum: u8,
+}
+
+impl DaoConfigV1 {
+    pub const LEN: usize =
+        20 + // authorized_dao
+        4 + // ethereum_eid
+        8 + // voting_period
+        1; // quorum
+}
+
+/// `PeerConfig` as first deployed, before per-peer nonces
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PeerConfigV1 {
+    pub src_eid: u32,
+    pub peer_address: [u8; 32],
+    pub trusted: bool,
+    pub bump: u8,
+}
+
+impl PeerConfigV1 {
+    pub const LEN: usize = 8 + // discriminator
+        4 + // src_eid
+        32 + // peer_address
+        1 + // trusted
+        1; // bump
+}
+
+/// Handler for migrating the store and old peers
+///
+/// New store fields take the defaults `init_oapp_store` sets. Old peers
+/// never tracked packet nonces, so they become unordered; every DAO nonce
+/// up to the store's last one counts as executed, as it did before.
+pub fn migrate_handler<'info>(ctx: Context<'_, '_, '_, 'info, Migrate<'info>>) -> Result<()> {
+    let store_info = ctx.accounts.store.to_account_info();
+    let old_store = if store_info.data_len() == OAppStoreV1::LEN {
+        Some(decode_v1::<OAppStoreV1>(&store_info, OAppStore::DISCRIMINATOR)?)
+    } else {
+        None
+    };
+    let store = match old_store {
+        Some(old) => OAppStore {
+            admin: old.admin,
+            endpoint: old.endpoint,
+            delegate: old.delegate,
+            bump: old.bump,
+            collection_metadata: old.collection_metadata,
+            dao_config: DaoConfig {
+                authorized_dao: old.dao_config.authorized_dao,
+                ethereum_eid: old.dao_config.ethereum_eid,
+                voting_period: old.dao_config.voting_period,
+                quorum: old.dao_config.quorum,
+                signers: Vec::new(),
+                signer_threshold: 0,
+            },
+            nonce: old.nonce,
+            processed_messages: old.processed_messages,
+            min_message_version: msg_codec::MessageCodec::MESSAGE_VERSION_V1,
+            paused: false,
+            pending_admin: None,
+            pending_admin_deadline: 0,
+            store_failed_messages: false,
+            send_receipts: false,
+            receipt_native_fee: 0,
+        },
+        None => OAppStore::try_deserialize(&mut &store_info.try_borrow_data()?[..])?,
+    };
+    if store.admin != ctx.accounts.admin.key() {
+        return Err(crate::error::ErrorCode::InsufficientAuthority.into());
+    }
+    
+    if store_info.data_len() == OAppStoreV1::LEN {
+        grow(&store_info, OAppStore::LEN, &ctx.accounts.admin, &ctx.accounts.system_program)?;
+        store.try_serialize(&mut &mut store_info.try_borrow_mut_data()?[..])?;
+        msg!("Store migrated - {} messages processed", store.processed_messages);
+    }
+    
+    for peer_info in ctx.remaining_accounts {
+        if peer_info.owner != &crate::ID || peer_info.data_len() != PeerConfigV1::LEN {
+            return Err(crate::error::ErrorCode::InvalidMigrationAccount.into());
+        }
+        let old = decode_v1::<PeerConfigV1>(peer_info, PeerConfig::DISCRIMINATOR)?;
+        if peer_info.key() != PeerConfig::find_pda(&store_info.key(), old.src_eid).0 {
+            return Err(crate::error::ErrorCode::InvalidMigrationAccount.into());
+        }
+        
+        let peer = PeerConfig {
+            src_eid: old.src_eid,
+            peer_address: old.peer_address,
+            trusted: old.trusted,
+            bump: old.bump,
+            delivery_mode: DeliveryMode::Unordered,
+            inbound_nonce: 0,
+            nonce_bitmap: 0,
+            outbound_nonce: 0,
+            dao_nonce: store.nonce,
+            dao_nonce_bitmap: if store.nonce > 0 { u64::MAX } else { 0 },
+        };
+        grow(peer_info, PeerConfig::LEN, &ctx.accounts.admin, &ctx.accounts.system_program)?;
+        peer.try_serialize(&mut &mut peer_info.try_borrow_mut_data()?[..])?;
+        msg!("Peer for EID {} migrated", old.src_eid);
+    }
+    Ok(())
+}
+
+/// Decode an account written in an old layout
+fn decode_v1<T: AnchorDeserialize>(account: &AccountInfo, discriminator: &[u8]) -> Result<T> {
+    let data = account.try_borrow_data()?;
+    if !data.starts_with(discriminator) {
+        return Err(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch.into());
+    }
+    Ok(T::deserialize(&mut &data[discriminator.len()..])?)
+}
+
+/// Resize `account` to `len`, the admin topping up its rent
+fn grow<'info>(
+    account: &AccountInfo<'info>,
+    len: usize,
+    admin: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let shortfall = Rent::get()?.minimum_balance(len).saturating_sub(account.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: admin.to_account_info(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    account.resize(len)?;
+    Ok(())
+}
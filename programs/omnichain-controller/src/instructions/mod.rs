@@ -3,11 +3,37 @@ pub mod receive_message;
 pub mod update_metadata;
 pub mod init_oapp_store;
 pub mod lz_receive;
+pub mod lz_compose;
+pub mod lz_send;
 pub mod lz_receive_types;
+pub mod receive_accounts;
+pub mod set_dao_signers;
+pub mod admin_transfer;
+pub mod set_peer;
+pub mod failed_message;
+pub mod set_composer;
+pub mod receipt;
+pub mod endpoint_config;
+pub mod set_delegate;
+pub mod enforced_options;
+pub mod migrate;
 
 pub use initialize::*;
 pub use receive_message::*;
 pub use update_metadata::*;
 pub use init_oapp_store::*;
 pub use lz_receive::*;
+pub use lz_compose::*;
+pub use lz_send::*;
 pub use lz_receive_types::*;
+pub use receive_accounts::*;
+pub use set_dao_signers::*;
+pub use admin_transfer::*;
+pub use set_peer::*;
+pub use failed_message::*;
+pub use set_composer::*;
+pub use receipt::*;
+pub use endpoint_config::*;
+pub use set_delegate::*;
+pub use enforced_options::*;
+pub use migrate::*;
@@ -0,0 +1,256 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::cpi::endpoint;
+use crate::instructions::lz_receive::MessageOrigin;
+use crate::instructions::lz_send::encode_outbound;
+use crate::instructions::enforced_options::combine_options;
+use crate::instructions::lz_receive_types::LzAccount;
+
+/// Turn execution receipts on or off
+#[derive(Accounts)]
+pub struct SetReceiptMode<'info> {
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [OAppStore::SEEDS],
+        bump = store.bump
+    )]
+    pub store: Account<'info, OAppStore>,
+    
+    pub admin: Signer<'info>,
+}
+
+/// Set the endpoint send accounts of receipts to a peer
+#[derive(Accounts)]
+#[instruction(dst_eid: u32)]
+pub struct SetReceiptAccounts<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = ReceiptConfig::LEN,
+        seeds = [ReceiptConfig::SEEDS, store.key().as_ref(), &dst_eid.to_le_bytes()],
+        bump
+    )]
+    pub receipt_config: Account<'info, ReceiptConfig>,
+    
+    #[account(
+        has_one = admin,
+        seeds = [OAppStore::SEEDS],
+        bump = store.bump
+    )]
+    pub store: Account<'info, OAppStore>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler for toggling execution receipts
+///
+/// Receipts are paid from the fee vault, which must hold at least
+/// `native_fee` for every message received while they are on.
+pub fn set_receipt_mode_handler(
+    ctx: Context<SetReceiptMode>,
+    enabled: bool,
+    native_fee: u64,
+) -> Result<()> {
+    let store = &mut ctx.accounts.store;
+    store.send_receipts = enabled;
+    store.receipt_native_fee = native_fee;
+    
+    msg!("Execution receipts {} - native fee: {}",
+         if enabled { "enabled" } else { "disabled" }, native_fee);
+    Ok(())
+}
+
+/// Handler for setting the send accounts of receipts to a peer
+///
+/// `send_accounts` are what the endpoint's `send` takes after the OApp and
+/// the fee payer, for the send library configured on the path. The program
+/// only signs as its own PDAs, so none can be a signer.
+pub fn set_receipt_accounts_handler(
+    ctx: Context<SetReceiptAccounts>,
+    dst_eid: u32,
+    send_accounts: Vec<LzAccount>,
+) -> Result<()> {
+    let invalid = send_accounts.len() > ReceiptConfig::MAX_SEND_ACCOUNTS
+        || send_accounts
+            .iter()
+            .any(|account| account.is_signer || account.pubkey == Pubkey::default());
+    if invalid {
+        return Err(crate::error::ErrorCode::InvalidReceiptAccounts.into());
+    }
+    
+    let receipt_config = &mut ctx.accounts.receipt_config;
+    receipt_config.store = ctx.accounts.store.key();
+    receipt_config.dst_eid = dst_eid;
+    receipt_config.send_accounts = send_accounts;
+    receipt_config.bump = ctx.bumps.receipt_config;
+    
+    msg!("Receipt accounts set for EID {} - {} accounts",
+         dst_eid, receipt_config.send_accounts.len());
+    Ok(())
+}
+
+/// Send the receipt of a received message back to its peer
+///
+/// `accounts` are the collection Merkle tree (or the store without one), the
+/// fee vault, the receipt's enforced options and the configured endpoint send
+/// accounts (see `ReceiveLayout::with_receipt`). Returns the nonce the
+/// receipt was sent with.
+pub(crate) fn send_receipt<'info>(
+    endpoint_program: &AccountInfo<'info>,
+    store: &Account<'info, OAppStore>,
+    peer_config: &mut PeerConfig,
+    accounts: &[AccountInfo<'info>],
+    origin: MessageOrigin,
+    result_code: u32,
+) -> Result<u64> {
+    let [merkle_tree, fee_vault, enforced_options, send_accounts @ ..] = accounts else {
+        return Err(crate::error::ErrorCode::MissingReceiveAccounts.into());
+    };
+    
+    let receipt = msg_codec::ReceiptPayload {
+        guid: origin.guid,
+        nonce: origin.nonce,
+        result_code,
+        merkle_root: merkle_root(merkle_tree),
+        processed_messages: store.processed_messages,
+    };
+    let (nonce, message) = encode_outbound(
+        peer_config,
+        msg_codec::MessageCodec::COMMAND_RECEIPT,
+        &receipt.encode(),
+    )?;
+    let options = combine_options(enforced_options, &[])?;
+    
+    let store_key = store.key();
+    let (_, vault_bump) = FeeVault::find_pda(&store_key);
+    let store_seeds: &[&[u8]] = &[OAppStore::SEEDS, &[store.bump]];
+    let vault_seeds: &[&[u8]] = &[FeeVault::SEEDS, store_key.as_ref(), &[vault_bump]];
+    endpoint::send(
+        endpoint_program,
+        &store.to_account_info(),
+        fee_vault,
+        send_accounts,
+        &[store_seeds, vault_seeds],
+        &endpoint::SendParams {
+            dst_eid: origin.src_eid,
+            receiver: peer_config.peer_address,
+            message: &message,
+            options: &options,
+            native_fee: store.receipt_native_fee,
+            lz_token_fee: 0,
+        },
+    )?;
+    
+    peer_config.outbound_nonce = nonce;
+    Ok(nonce)
+}
+
+/// Current root of a concurrent Merkle tree account
+///
+/// Zero when the account is not an initialized account compression tree, so
+/// a store without a collection tree still gets receipts.
+fn merkle_root(tree: &AccountInfo) -> [u8; 32] {
+    let compression_program = crate::constants::SPL_ACCOUNT_COMPRESSION_PROGRAM_ID
+        .parse::<Pubkey>()
+        .unwrap();
+    if tree.owner != &compression_program {
+        return [0; 32];
+    }
+    
+    tree.try_borrow_data()
+        .ok()
+        .and_then(|data| read_merkle_root(&data))
+        .unwrap_or_default()
+}
+
+/// Size of the account compression tree header
+const TREE_HEADER_LEN: usize = 56;
+
+/// Read the active change log root out of concurrent Merkle tree data
+///
+/// The header holds the buffer size and depth; the tree that follows starts
+/// with the sequence number, the active change log index and the buffer
+/// size, then the change logs, each a root, a path of `max_depth` nodes and
+/// an index with padding.
+fn read_merkle_root(data: &[u8]) -> Option<[u8; 32]> {
+    let u32_at = |offset: usize| Some(u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?));
+    let u64_at = |offset: usize| Some(u64::from_le_bytes(data.get(offset..offset + 8)?.try_into().ok()?));
+    
+    // Account type 1 is a concurrent Merkle tree, header version 0 is V1
+    if data.first() != Some(&1) || data.get(1) != Some(&0) {
+        return None;
+    }
+    let max_buffer_size = u32_at(2)? as u64;
+    let max_depth = u32_at(6)? as usize;
+    
+    let active_index = u64_at(TREE_HEADER_LEN + 8)?;
+    if active_index >= max_buffer_size {
+        return None;
+    }
+    
+    let change_log_len = 32 + 32 * max_depth + 8;
+    let offset = (active_index as usize)
+        .checked_mul(change_log_len)?
+        .checked_add(TREE_HEADER_LEN + 24)?;
+    data.get(offset..offset.checked_add(32)?)?.try_into().ok()
+}
+
+#[event]
+pub struct ReceiptSent {
+    pub guid: [u8; 32],
+    pub dst_eid: u32,
+    pub nonce: u64,
+    pub result_code: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tree data with `root` in change log `active_index`
+    fn tree_data(max_depth: u32, max_buffer_size: u32, active_index: u64, root: [u8; 32]) -> Vec<u8> {
+        let change_log_len = 32 + 32 * max_depth as usize + 8;
+        let mut data = vec![0u8; TREE_HEADER_LEN + 24 + change_log_len * max_buffer_size as usize];
+        data[0] = 1;
+        data[2..6].copy_from_slice(&max_buffer_size.to_le_bytes());
+        data[6..10].copy_from_slice(&max_depth.to_le_bytes());
+        data[TREE_HEADER_LEN + 8..TREE_HEADER_LEN + 16].copy_from_slice(&active_index.to_le_bytes());
+
+        let offset = TREE_HEADER_LEN + 24 + active_index as usize * change_log_len;
+        data[offset..offset + 32].copy_from_slice(&root);
+        data
+    }
+
+    #[test]
+    fn test_read_merkle_root() {
+        let data = tree_data(3, 8, 2, [9; 32]);
+        assert_eq!(read_merkle_root(&data), Some([9; 32]));
+
+        let data = tree_data(14, 64, 0, [4; 32]);
+        assert_eq!(read_merkle_root(&data), Some([4; 32]));
+    }
+
+    #[test]
+    fn test_read_merkle_root_rejects_malformed_tree() {
+        // Uninitialized account
+        let mut data = tree_data(3, 8, 2, [9; 32]);
+        data[0] = 0;
+        assert_eq!(read_merkle_root(&data), None);
+
+        // Active index past the buffer
+        let mut data = tree_data(3, 8, 2, [9; 32]);
+        data[TREE_HEADER_LEN + 8..TREE_HEADER_LEN + 16].copy_from_slice(&8u64.to_le_bytes());
+        assert_eq!(read_merkle_root(&data), None);
+
+        // Truncated change logs
+        let data = tree_data(3, 8, 7, [9; 32]);
+        assert_eq!(read_merkle_root(&data[..data.len() - 200]), None);
+        assert_eq!(read_merkle_root(&[]), None);
+    }
+}
@@ -0,0 +1,595 @@
+use anchor_lang::prelude::*;
+use crate::instructions::lz_receive_types::LzAccount;
+use crate::state::*;
+
+/// Remaining accounts of `lz_receive`, after its named accounts
+///
+/// `lz_receive_types` emits this list and `lz_receive` parses it, so both
+/// sides derive every account from the same definition. The clear accounts
+/// always come first, followed by at most one `ReceiveExtras` group, the
+/// failed message accounts when the store keeps failed messages and, when
+/// the store sends receipts, the receipt accounts.
+pub struct ReceiveLayout {
+    clear: Vec<LzAccount>,
+    extras: Vec<LzAccount>,
+    vault: Vec<LzAccount>,
+    receipt: Vec<LzAccount>,
+}
+
+/// Optional account group following the clear accounts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReceiveExtras {
+    None,
+    /// The message is a compose command
+    Compose,
+    /// The message is a legacy Borsh message
+    Legacy,
+    /// The message changes the endpoint delegate
+    SetDelegate,
+}
+
+/// Remaining accounts split by group
+pub struct ReceiveAccounts<'a, 'info> {
+    pub clear: &'a [AccountInfo<'info>],
+    pub extras: &'a [AccountInfo<'info>],
+    pub vault: &'a [AccountInfo<'info>],
+    pub receipt: &'a [AccountInfo<'info>],
+}
+
+impl ReceiveExtras {
+    /// The extra group a message needs
+    ///
+    /// Messages that do not decode get no extras; `lz_receive` rejects them
+    /// after clearing.
+    pub fn for_message(message: &[u8]) -> Self {
+        Self::for_decoded(msg_codec::MessageCodec::decode_message(message).as_ref().ok())
+    }
+    
+    /// The extra group of a message already decoded, if it decoded
+    pub fn for_decoded(decoded: Option<&msg_codec::DecodedMessage>) -> Self {
+        match decoded.map(|decoded| decoded.command) {
+            Some(msg_codec::MessageCodec::COMMAND_COMPOSE) => ReceiveExtras::Compose,
+            Some(msg_codec::MessageCodec::COMMAND_LEGACY) => ReceiveExtras::Legacy,
+            Some(msg_codec::MessageCodec::COMMAND_SET_DELEGATE) => ReceiveExtras::SetDelegate,
+            _ => ReceiveExtras::None,
+        }
+    }
+    
+    fn accounts(self, endpoint_program: &Pubkey, store_key: &Pubkey, guid: &[u8; 32]) -> Vec<LzAccount> {
+        match self {
+            ReceiveExtras::None => Vec::new(),
+            ReceiveExtras::Compose => compose_accounts(endpoint_program, store_key, guid),
+            ReceiveExtras::Legacy => legacy_accounts(),
+            ReceiveExtras::SetDelegate => set_delegate_accounts(endpoint_program, store_key),
+        }
+    }
+}
+
+impl ReceiveLayout {
+    pub const CLEAR_ACCOUNTS: usize = 4;
+
+    pub fn new(
+        endpoint_program: &Pubkey,
+        store_key: &Pubkey,
+        src_eid: u32,
+        sender: &[u8; 32],
+        guid: &[u8; 32],
+        extras: ReceiveExtras,
+    ) -> Self {
+        ReceiveLayout {
+            clear: clear_accounts(endpoint_program, store_key, src_eid, sender, guid),
+            extras: extras.accounts(endpoint_program, store_key, guid),
+            vault: Vec::new(),
+            receipt: Vec::new(),
+        }
+    }
+    
+    /// Remaining accounts of `retry_failed_message`: the extras group of
+    /// the stored message, with nothing to clear. Add the receipt accounts
+    /// with `with_receipt`.
+    pub fn retry(endpoint_program: &Pubkey, store_key: &Pubkey, guid: &[u8; 32], extras: ReceiveExtras) -> Self {
+        ReceiveLayout {
+            clear: Vec::new(),
+            extras: extras.accounts(endpoint_program, store_key, guid),
+            vault: Vec::new(),
+            receipt: Vec::new(),
+        }
+    }
+    
+    /// Add the failed message accounts if the store keeps failed messages
+    pub fn with_vault(mut self, store: &OAppStore, store_key: &Pubkey, guid: &[u8; 32]) -> Self {
+        if store.store_failed_messages {
+            self.vault = failed_message_accounts(store_key, guid);
+        }
+        self
+    }
+
+    /// Add the receipt accounts if the store sends receipts, failing if the
+    /// peer has no receipt config
+    pub fn with_receipt(
+        mut self,
+        store: &OAppStore,
+        store_key: &Pubkey,
+        src_eid: u32,
+        receipt_config: Option<&ReceiptConfig>,
+    ) -> Result<Self> {
+        if store.send_receipts {
+            let config = receipt_config.ok_or(crate::error::ErrorCode::ReceiptNotConfigured)?;
+            self.receipt = receipt_accounts(store, store_key, src_eid, config);
+        }
+        Ok(self)
+    }
+
+    /// The account metas for `lz_receive_types` to return
+    pub fn accounts(&self) -> impl Iterator<Item = &LzAccount> {
+        self.clear.iter().chain(&self.extras).chain(&self.vault).chain(&self.receipt)
+    }
+
+    /// Check `accounts` against the layout and split them by group
+    pub fn parse<'a, 'info>(
+        &self,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<ReceiveAccounts<'a, 'info>> {
+        let expected = self.clear.len() + self.extras.len() + self.vault.len() + self.receipt.len();
+        if accounts.len() != expected {
+            msg!("Expected {} remaining accounts, got {}", expected, accounts.len());
+            let error = if accounts.len() < expected {
+                crate::error::ErrorCode::MissingReceiveAccounts
+            } else {
+                crate::error::ErrorCode::InvalidReceiveAccount
+            };
+            return Err(error.into());
+        }
+        
+        check_accounts(accounts, self.accounts())?;
+        
+        let (clear, rest) = accounts.split_at(self.clear.len());
+        let (extras, rest) = rest.split_at(self.extras.len());
+        let (vault, receipt) = rest.split_at(self.vault.len());
+        Ok(ReceiveAccounts { clear, extras, vault, receipt })
+    }
+}
+
+/// Remaining accounts of `lz_compose`: the clear compose accounts, then
+/// whatever the composer program needs
+pub struct ComposeLayout {
+    clear: Vec<LzAccount>,
+}
+
+impl ComposeLayout {
+    pub const CLEAR_ACCOUNTS: usize = 1;
+
+    pub fn new(endpoint_program: &Pubkey, store_key: &Pubkey, guid: &[u8; 32], index: u16) -> Self {
+        ComposeLayout {
+            clear: vec![
+                // Compose queue entry being cleared (writable)
+                LzAccount {
+                    pubkey: compose_queue(endpoint_program, store_key, guid, index),
+                    is_signer: false,
+                    is_writable: true,
+                },
+            ],
+        }
+    }
+
+    /// Check the clear accounts and split off the composer's accounts
+    pub fn parse<'a, 'info>(
+        &self,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<(&'a [AccountInfo<'info>], &'a [AccountInfo<'info>])> {
+        if accounts.len() < self.clear.len() {
+            return Err(crate::error::ErrorCode::MissingReceiveAccounts.into());
+        }
+        let (clear, composer) = accounts.split_at(self.clear.len());
+        check_accounts(clear, self.clear.iter())?;
+        
+        Ok((clear, composer))
+    }
+}
+
+/// Check accounts one by one against their expected metas
+fn check_accounts<'a>(
+    accounts: &[AccountInfo],
+    metas: impl Iterator<Item = &'a LzAccount>,
+) -> Result<()> {
+    for (index, (account, meta)) in accounts.iter().zip(metas).enumerate() {
+        // A signing default pubkey is the executor's slot, filled in at
+        // execution; a non-signing one is the system program
+        let key_matches = if meta.pubkey == Pubkey::default() && meta.is_signer {
+            account.is_signer
+        } else {
+            account.key() == meta.pubkey
+        };
+        if !key_matches || (meta.is_writable && !account.is_writable) {
+            msg!("Remaining account {} should be {} (writable: {})",
+                 index, meta.pubkey, meta.is_writable);
+            return Err(crate::error::ErrorCode::InvalidReceiveAccount.into());
+        }
+    }
+    Ok(())
+}
+
+/// Accounts required for the endpoint clear CPI
+fn clear_accounts(
+    endpoint_program: &Pubkey,
+    oapp_address: &Pubkey,
+    src_eid: u32,
+    sender: &[u8; 32],
+    guid: &[u8; 32],
+) -> Vec<LzAccount> {
+    let pda = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, endpoint_program).0;
+    
+    vec![
+        // Endpoint configuration account (read-only)
+        LzAccount {
+            pubkey: pda(&[b"EndpointConfig"]),
+            is_signer: false,
+            is_writable: false,
+        },
+        // Receive library account (read-only)
+        LzAccount {
+            pubkey: pda(&[b"ReceiveLibrary", oapp_address.as_ref()]),
+            is_signer: false,
+            is_writable: false,
+        },
+        // Nonce account for the path (writable) - for replay protection
+        LzAccount {
+            pubkey: pda(&[b"Nonce", oapp_address.as_ref(), &src_eid.to_be_bytes(), sender]),
+            is_signer: false,
+            is_writable: true,
+        },
+        // Payload hash account (writable) - for message verification
+        LzAccount {
+            pubkey: pda(&[b"PayloadHash", guid]),
+            is_signer: false,
+            is_writable: true,
+        },
+    ]
+}
+
+/// Endpoint account holding a queued compose message
+fn compose_queue(endpoint_program: &Pubkey, receiver: &Pubkey, guid: &[u8; 32], index: u16) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"ComposeQueue", receiver.as_ref(), guid, &index.to_be_bytes()],
+        endpoint_program,
+    )
+    .0
+}
+
+/// Accounts required for the send compose CPI
+fn compose_accounts(endpoint_program: &Pubkey, receiver: &Pubkey, guid: &[u8; 32]) -> Vec<LzAccount> {
+    vec![
+        // Default pubkey is replaced by the executor, who pays for the queue entry
+        LzAccount {
+            pubkey: Pubkey::default(),
+            is_signer: true,
+            is_writable: true,
+        },
+        // Compose queue account (writable)
+        LzAccount {
+            pubkey: compose_queue(endpoint_program, receiver, guid, crate::instructions::lz_receive::COMPOSE_INDEX),
+            is_signer: false,
+            is_writable: true,
+        },
+        // System program for account creation
+        LzAccount {
+            pubkey: anchor_lang::system_program::ID,
+            is_signer: false,
+            is_writable: false,
+        },
+    ]
+}
+
+/// Accounts required to run a legacy command
+fn legacy_accounts() -> Vec<LzAccount> {
+    let (controller_config, _) = Pubkey::find_program_address(
+        &[crate::constants::CONTROLLER_CONFIG_SEED],
+        &crate::ID,
+    );
+    
+    vec![LzAccount {
+        pubkey: controller_config,
+        is_signer: false,
+        is_writable: true,
+    }]
+}
+
+/// Accounts required for the endpoint set delegate CPI
+fn set_delegate_accounts(endpoint_program: &Pubkey, oapp_address: &Pubkey) -> Vec<LzAccount> {
+    let (oapp_registry, _) = Pubkey::find_program_address(&[b"OApp", oapp_address.as_ref()], endpoint_program);
+    
+    vec![
+        // OApp registry holding the delegate (writable)
+        LzAccount {
+            pubkey: oapp_registry,
+            is_signer: false,
+            is_writable: true,
+        },
+    ]
+}
+
+/// Accounts required to store a failed command
+fn failed_message_accounts(store_key: &Pubkey, guid: &[u8; 32]) -> Vec<LzAccount> {
+    let (failed_message, _) = FailedMessage::find_pda(store_key, guid);
+    
+    vec![
+        LzAccount {
+            pubkey: failed_message,
+            is_signer: false,
+            is_writable: true,
+        },
+        // Default pubkey is replaced by the executor, who pays the rent
+        LzAccount {
+            pubkey: Pubkey::default(),
+            is_signer: true,
+            is_writable: true,
+        },
+        LzAccount {
+            pubkey: anchor_lang::system_program::ID,
+            is_signer: false,
+            is_writable: false,
+        },
+    ]
+}
+
+/// Accounts required to send an execution receipt back to the peer: the
+/// receipt's own accounts, then the configured endpoint send accounts
+fn receipt_accounts(store: &OAppStore, store_key: &Pubkey, dst_eid: u32, config: &ReceiptConfig) -> Vec<LzAccount> {
+    // Without a collection tree the store stands in, reporting a zero root
+    let merkle_tree = match store.collection_metadata.tree_config.merkle_tree {
+        tree if tree == Pubkey::default() => *store_key,
+        tree => tree,
+    };
+    let (fee_vault, _) = FeeVault::find_pda(store_key);
+    let (enforced_options, _) = EnforcedOptions::find_pda(
+        store_key,
+        dst_eid,
+        msg_codec::MessageCodec::COMMAND_RECEIPT,
+    );
+    
+    let mut accounts = vec![
+        // Collection tree the receipt reports the root of (read-only)
+        LzAccount {
+            pubkey: merkle_tree,
+            is_signer: false,
+            is_writable: false,
+        },
+        // Fee vault paying the endpoint (writable)
+        LzAccount {
+            pubkey: fee_vault,
+            is_signer: false,
+            is_writable: true,
+        },
+        // Options enforced on receipts, possibly uninitialized (read-only)
+        LzAccount {
+            pubkey: enforced_options,
+            is_signer: false,
+            is_writable: false,
+        },
+    ];
+    accounts.extend(config.send_accounts.iter().cloned());
+    accounts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENDPOINT: Pubkey = Pubkey::new_from_array([7; 32]);
+    const STORE: Pubkey = Pubkey::new_from_array([8; 32]);
+
+    fn layout(extras: ReceiveExtras) -> ReceiveLayout {
+        ReceiveLayout::new(&ENDPOINT, &STORE, 30101, &[1; 32], &[2; 32], extras)
+    }
+
+    /// Run `f` with account infos for `keys`, all writable and signing
+    fn with_accounts<R>(keys: &[Pubkey], f: impl FnOnce(&[AccountInfo]) -> R) -> R {
+        let mut lamports = vec![0u64; keys.len()];
+        let mut data = vec![Vec::<u8>::new(); keys.len()];
+        let accounts: Vec<AccountInfo> = keys
+            .iter()
+            .zip(lamports.iter_mut())
+            .zip(data.iter_mut())
+            .map(|((key, lamports), data)| {
+                AccountInfo::new(key, true, true, lamports, data, &ENDPOINT, false, 0)
+            })
+            .collect();
+        f(&accounts)
+    }
+
+    /// The layout's keys, with a new signer in each executor slot
+    fn keys(layout: &ReceiveLayout) -> Vec<Pubkey> {
+        layout
+            .accounts()
+            .map(|meta| if meta.pubkey == Pubkey::default() && meta.is_signer { Pubkey::new_unique() } else { meta.pubkey })
+            .collect()
+    }
+
+    #[test]
+    fn test_layout_groups() {
+        assert_eq!(layout(ReceiveExtras::None).accounts().count(), ReceiveLayout::CLEAR_ACCOUNTS);
+        assert_eq!(layout(ReceiveExtras::Compose).accounts().count(), ReceiveLayout::CLEAR_ACCOUNTS + 3);
+        assert_eq!(layout(ReceiveExtras::Legacy).accounts().count(), ReceiveLayout::CLEAR_ACCOUNTS + 1);
+        assert_eq!(layout(ReceiveExtras::SetDelegate).accounts().count(), ReceiveLayout::CLEAR_ACCOUNTS + 1);
+    }
+
+    #[test]
+    fn test_system_program_slot_is_not_executor() {
+        let layout = layout(ReceiveExtras::Compose);
+        let mut keys = keys(&layout);
+        // The system program shares the executor's default pubkey
+        *keys.last_mut().unwrap() = Pubkey::new_unique();
+        with_accounts(&keys, |accounts| {
+            let error = layout.parse(accounts).err().unwrap();
+            assert_eq!(error, crate::error::ErrorCode::InvalidReceiveAccount.into());
+        });
+    }
+
+    /// Receipt config listing `count` send accounts, the last one writable
+    fn receipt_config(count: usize) -> ReceiptConfig {
+        let mut send_accounts: Vec<_> = (0..count)
+            .map(|_| LzAccount {
+                pubkey: Pubkey::new_unique(),
+                is_signer: false,
+                is_writable: false,
+            })
+            .collect();
+        if let Some(last) = send_accounts.last_mut() {
+            last.is_writable = true;
+        }
+        ReceiptConfig {
+            store: STORE,
+            dst_eid: 30101,
+            send_accounts,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn test_receipt_group_follows_extras() {
+        // An all-zero store decodes with empty strings and no pending admin
+        let mut store = OAppStore::deserialize(&mut &[0u8; OAppStore::LEN][..]).unwrap();
+        store.endpoint = ENDPOINT;
+        store.collection_metadata.tree_config.merkle_tree = Pubkey::new_from_array([3; 32]);
+        let config = receipt_config(3);
+        
+        let without = layout(ReceiveExtras::Compose).with_receipt(&store, &STORE, 30101, None).unwrap();
+        assert_eq!(without.accounts().count(), ReceiveLayout::CLEAR_ACCOUNTS + 3);
+        
+        store.send_receipts = true;
+        let layout = layout(ReceiveExtras::Compose).with_receipt(&store, &STORE, 30101, Some(&config)).unwrap();
+        let keys = keys(&layout);
+        assert_eq!(keys.len(), ReceiveLayout::CLEAR_ACCOUNTS + 3 + 6);
+        assert_eq!(keys[ReceiveLayout::CLEAR_ACCOUNTS + 3], store.collection_metadata.tree_config.merkle_tree);
+        assert_eq!(keys[ReceiveLayout::CLEAR_ACCOUNTS + 4], FeeVault::find_pda(&STORE).0);
+        assert_eq!(
+            keys[ReceiveLayout::CLEAR_ACCOUNTS + 5],
+            EnforcedOptions::find_pda(&STORE, 30101, msg_codec::MessageCodec::COMMAND_RECEIPT).0
+        );
+        // The configured send accounts come last, as configured
+        let send: Vec<_> = config.send_accounts.iter().map(|account| account.pubkey).collect();
+        assert_eq!(keys[ReceiveLayout::CLEAR_ACCOUNTS + 6..], send[..]);
+        
+        with_accounts(&keys, |accounts| {
+            let parsed = layout.parse(accounts).unwrap();
+            assert_eq!(parsed.clear.len(), ReceiveLayout::CLEAR_ACCOUNTS);
+            assert_eq!(parsed.extras.len(), 3);
+            assert_eq!(parsed.receipt.len(), 6);
+        });
+    }
+
+    #[test]
+    fn test_receipt_group_needs_config() {
+        let mut store = OAppStore::deserialize(&mut &[0u8; OAppStore::LEN][..]).unwrap();
+        store.send_receipts = true;
+        
+        let error = layout(ReceiveExtras::None).with_receipt(&store, &STORE, 30101, None).err().unwrap();
+        assert_eq!(error, crate::error::ErrorCode::ReceiptNotConfigured.into());
+        
+        // Without a collection tree the store stands in for it
+        let layout = layout(ReceiveExtras::None).with_receipt(&store, &STORE, 30101, Some(&receipt_config(0))).unwrap();
+        assert_eq!(layout.accounts().nth(ReceiveLayout::CLEAR_ACCOUNTS).unwrap().pubkey, STORE);
+    }
+
+    #[test]
+    fn test_parse_splits_groups() {
+        let mut store = OAppStore::deserialize(&mut &[0u8; OAppStore::LEN][..]).unwrap();
+        store.store_failed_messages = true;
+
+        // Every command type can be stored, so the vault follows the extras
+        let layout = layout(ReceiveExtras::SetDelegate).with_vault(&store, &STORE, &[2; 32]);
+        let keys = keys(&layout);
+        assert_eq!(keys[ReceiveLayout::CLEAR_ACCOUNTS + 1], FailedMessage::find_pda(&STORE, &[2; 32]).0);
+        with_accounts(&keys, |accounts| {
+            let parsed = layout.parse(accounts).unwrap();
+            assert_eq!(parsed.clear.len(), ReceiveLayout::CLEAR_ACCOUNTS);
+            assert_eq!(parsed.extras.len(), 1);
+            assert_eq!(parsed.vault.len(), 3);
+            assert!(parsed.receipt.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_retry_layout_has_only_extras() {
+        let retry = ReceiveLayout::retry(&ENDPOINT, &STORE, &[2; 32], ReceiveExtras::Compose);
+        let received: Vec<_> = layout(ReceiveExtras::Compose)
+            .accounts()
+            .skip(ReceiveLayout::CLEAR_ACCOUNTS)
+            .map(|meta| meta.pubkey)
+            .collect();
+        assert_eq!(retry.accounts().map(|meta| meta.pubkey).collect::<Vec<_>>(), received);
+
+        // A retry sends its receipt with the same accounts as the receive
+        let mut store = OAppStore::deserialize(&mut &[0u8; OAppStore::LEN][..]).unwrap();
+        store.endpoint = ENDPOINT;
+        store.send_receipts = true;
+        let config = receipt_config(2);
+        let retry = ReceiveLayout::retry(&ENDPOINT, &STORE, &[2; 32], ReceiveExtras::Compose)
+            .with_receipt(&store, &STORE, 30101, Some(&config))
+            .unwrap();
+        let received = layout(ReceiveExtras::Compose).with_receipt(&store, &STORE, 30101, Some(&config)).unwrap();
+        let pubkeys = |layout: &ReceiveLayout| layout.accounts().map(|meta| meta.pubkey).collect::<Vec<_>>();
+        assert_eq!(pubkeys(&retry)[..], pubkeys(&received)[ReceiveLayout::CLEAR_ACCOUNTS..]);
+        let keys = keys(&retry);
+        with_accounts(&keys, |accounts| {
+            let parsed = retry.parse(accounts).unwrap();
+            assert!(parsed.clear.is_empty());
+            assert_eq!((parsed.extras.len(), parsed.receipt.len()), (3, 5));
+        });
+    }
+
+    #[test]
+    fn test_parse_rejects_wrong_count() {
+        let layout = layout(ReceiveExtras::Compose);
+        let mut keys = keys(&layout);
+
+        keys.pop();
+        with_accounts(&keys, |accounts| {
+            let error = layout.parse(accounts).err().unwrap();
+            assert_eq!(error, crate::error::ErrorCode::MissingReceiveAccounts.into());
+        });
+
+        keys.extend([Pubkey::new_unique(), Pubkey::new_unique()]);
+        with_accounts(&keys, |accounts| {
+            let error = layout.parse(accounts).err().unwrap();
+            assert_eq!(error, crate::error::ErrorCode::InvalidReceiveAccount.into());
+        });
+    }
+
+    #[test]
+    fn test_parse_rejects_wrong_derivation() {
+        let layout = layout(ReceiveExtras::None);
+        let mut keys = keys(&layout);
+        keys[2] = Pubkey::new_unique();
+
+        with_accounts(&keys, |accounts| {
+            let error = layout.parse(accounts).err().unwrap();
+            assert_eq!(error, crate::error::ErrorCode::InvalidReceiveAccount.into());
+        });
+    }
+
+    #[test]
+    fn test_compose_layout_clears_queued_compose() {
+        // lz_compose clears the queue entry lz_receive composed into
+        let compose = ComposeLayout::new(&ENDPOINT, &STORE, &[2; 32], crate::instructions::lz_receive::COMPOSE_INDEX);
+        let queued = layout(ReceiveExtras::Compose).extras[1].pubkey;
+        assert_eq!(compose.clear[0].pubkey, queued);
+
+        let keys = [queued, Pubkey::new_unique(), Pubkey::new_unique()];
+        with_accounts(&keys, |accounts| {
+            let (clear, composer) = compose.parse(accounts).unwrap();
+            assert_eq!(clear.len(), ComposeLayout::CLEAR_ACCOUNTS);
+            assert_eq!(composer.len(), 2);
+        });
+
+        let other = ComposeLayout::new(&ENDPOINT, &STORE, &[2; 32], 1);
+        with_accounts(&keys, |accounts| {
+            let error = other.parse(accounts).err().unwrap();
+            assert_eq!(error, crate::error::ErrorCode::InvalidReceiveAccount.into());
+        });
+        with_accounts(&[], |accounts| {
+            let error = compose.parse(accounts).err().unwrap();
+            assert_eq!(error, crate::error::ErrorCode::MissingReceiveAccounts.into());
+        });
+    }
+}
@@ -1,8 +1,8 @@
 use crate::error::ErrorCode;
 use crate::{constants::*, ControllerConfig};
-use crate::state::message_types::{CrossChainMessage, MessageCommand};
+use crate::state::message_types::{CrossChainMessage, CrossChainMessageProcessed, MessageCommand};
 use anchor_lang::prelude::*;
-use mpl_bubblegum::instructions::{MintToCollectionV1CpiBuilder, BurnBuilder, TransferBuilder};
+use mpl_bubblegum::instructions::{MintToCollectionV1Builder, BurnBuilder, TransferBuilder};
 use mpl_bubblegum::types::{MetadataArgs, TokenProgramVersion};
 
 #[derive(Accounts)]
@@ -15,8 +15,16 @@ pub struct ReceiveLayerZeroMessage<'info> {
     )]
     pub controller_config: Account<'info, ControllerConfig>,
 
+    /// Nothing verifies where these messages come from, so only the
+    /// controller authority may submit them; DAO messages arrive through
+    /// `lz_receive`
+    #[account(
+        constraint = authority.key() == controller_config.authority @ ErrorCode::InsufficientAuthority
+    )]
+    pub authority: Signer<'info>,
+
     /// LayerZero endpoint or relayer account
-    /// CHECK: LayerZero will validate the message origin
+    /// CHECK: Not verified; the authority vouches for the message
     pub layerzero_endpoint: AccountInfo<'info>,
 
     /// The Merkle tree for cNFT operations
@@ -110,8 +118,45 @@ pub fn receive_message_handler(
     config.message_nonce = cross_chain_msg.nonce;
     config.last_update = clock.unix_timestamp;
 
-    // Process the command
-    let command_type = match &cross_chain_msg.command {
+    let nonce = cross_chain_msg.nonce;
+    let sender = cross_chain_msg.sender;
+    let tree_config = config.key();
+    let command_type = execute_legacy_command(config, tree_config, cross_chain_msg.command)?;
+
+    msg!(
+        "Processed LayerZero message from EID {}, nonce: {}, command: {}",
+        src_eid,
+        nonce,
+        command_type
+    );
+    emit!(CrossChainMessageProcessed {
+        nonce,
+        sender,
+        command_type: command_type.to_string(),
+        timestamp: clock.unix_timestamp,
+        success: true,
+    });
+
+    Ok(())
+}
+
+/// Run a legacy `MessageCommand` against the controller config
+///
+/// Shared by `receive_layerzero_message` and `lz_receive`, which accepts the
+/// Borsh format as its own wire version. Each path checks its own nonces.
+/// `tree_config` is the config's address. Returns the command name for logs.
+pub(crate) fn execute_legacy_command(
+    config: &mut ControllerConfig,
+    tree_config: Pubkey,
+    command: MessageCommand,
+) -> Result<&'static str> {
+    // Only unpausing gets through while the controller is paused
+    require!(
+        !config.paused || matches!(command, MessageCommand::SetPaused { .. }),
+        ErrorCode::ControllerPaused
+    );
+
+    let command_type = match &command {
         MessageCommand::UpdateCollectionMetadata { .. } => "UpdateCollectionMetadata",
         MessageCommand::BatchUpdateMetadata { .. } => "BatchUpdateMetadata",
         MessageCommand::TransferAuthority { .. } => "TransferAuthority",
@@ -123,27 +168,28 @@ pub fn receive_message_handler(
         MessageCommand::VerifyTreeState { .. } => "VerifyTreeState",
     };
 
-    match cross_chain_msg.command {
+    match command {
         MessageCommand::UpdateCollectionMetadata { new_uri } => {
             handle_update_collection_metadata(config, new_uri)?;
         }
         MessageCommand::BatchUpdateMetadata { updates } => {
-            handle_batch_update_metadata(ctx, updates)?;
+            handle_batch_update_metadata(updates)?;
         }
-        MessageCommand::TransferAuthority { new_authority } => {
-            handle_transfer_authority(config, new_authority)?;
+        // Authority only moves through the store's two-step admin handover
+        MessageCommand::TransferAuthority { .. } => {
+            return err!(ErrorCode::UnsupportedCommand);
         }
         MessageCommand::SetPaused { paused } => {
             handle_set_paused(config, paused)?;
         }
         MessageCommand::MintCnfts { mint_requests } => {
-            handle_mint_cnfts(ctx, mint_requests)?;
+            handle_mint_cnfts(config, tree_config, mint_requests)?;
         }
         MessageCommand::BurnCnfts { burn_requests } => {
-            handle_burn_cnfts(ctx, burn_requests)?;
+            handle_burn_cnfts(config, tree_config, burn_requests)?;
         }
         MessageCommand::TransferCnfts { transfer_requests } => {
-            handle_transfer_cnfts(ctx, transfer_requests)?;
+            handle_transfer_cnfts(config, tree_config, transfer_requests)?;
         }
         MessageCommand::UpdateTreeConfig { new_config } => {
             handle_update_tree_config(config, new_config)?;
@@ -153,14 +199,7 @@ pub fn receive_message_handler(
         }
     }
 
-    msg!(
-        "Processed LayerZero message from EID {}, nonce: {}, command: {}",
-        src_eid,
-        cross_chain_msg.nonce,
-        command_type
-    );
-
-    Ok(())
+    Ok(command_type)
 }
 
 fn handle_update_collection_metadata(config: &mut ControllerConfig, new_uri: String) -> Result<()> {
@@ -173,7 +212,6 @@ fn handle_update_collection_metadata(config: &mut ControllerConfig, new_uri: Str
 }
 
 fn handle_batch_update_metadata(
-    _ctx: Context<ReceiveLayerZeroMessage>,
     updates: Vec<crate::state::message_types::MetadataUpdate>,
 ) -> Result<()> {
     require!(updates.len() <= MAX_BATCH_SIZE, ErrorCode::BatchTooLarge);
@@ -194,18 +232,6 @@ fn handle_batch_update_metadata(
     Ok(())
 }
 
-fn handle_transfer_authority(config: &mut ControllerConfig, new_authority: Pubkey) -> Result<()> {
-    let old_authority = config.authority;
-    config.authority = new_authority;
-
-    msg!(
-        "Transferred authority from {} to {}",
-        old_authority,
-        new_authority
-    );
-    Ok(())
-}
-
 fn handle_set_paused(config: &mut ControllerConfig, paused: bool) -> Result<()> {
     config.paused = paused;
 
@@ -214,14 +240,14 @@ fn handle_set_paused(config: &mut ControllerConfig, paused: bool) -> Result<()>
 }
 
 fn handle_mint_cnfts(
-    ctx: Context<ReceiveLayerZeroMessage>,
+    config: &ControllerConfig,
+    tree_config: Pubkey,
     mint_requests: Vec<crate::state::message_types::MintRequest>,
 ) -> Result<()> {
     require!(
         mint_requests.len() <= MAX_MINT_BATCH_SIZE,
         ErrorCode::MintBatchTooLarge
     );
-    let bubblegum_program = &ctx.accounts.bubblegum_program;
     for mint_request in mint_requests.iter() {
         validate_cnft_metadata(&mint_request.metadata)?;
         let metadata = MetadataArgs {
@@ -238,34 +264,29 @@ fn handle_mint_cnfts(
             token_standard: None,
             token_program_version: TokenProgramVersion::Original,
         };
-        let mut builder = MintToCollectionV1CpiBuilder::new(bubblegum_program);
+        let mut builder = MintToCollectionV1Builder::new();
         builder
-            .tree_config(&ctx.accounts.controller_config.to_account_info())
-            .leaf_owner(&ctx.accounts.recipient)
-            .leaf_delegate(&ctx.accounts.leaf_delegate)
-            .merkle_tree(&ctx.accounts.merkle_tree)
-            .payer(&ctx.accounts.tree_authority)
-            .tree_creator_or_delegate(&ctx.accounts.tree_authority)
-            .collection_authority(&ctx.accounts.collection_authority)
-            .collection_authority_record_pda(ctx.accounts.collection_authority_record_pda.as_ref())
-            .collection_mint(&ctx.accounts.collection_mint)
-            .collection_metadata(&ctx.accounts.collection_metadata)
-            .collection_edition(&ctx.accounts.collection_edition)
-            .bubblegum_signer(&ctx.accounts.bubblegum_signer)
-            .log_wrapper(&ctx.accounts.log_wrapper)
-            .compression_program(&ctx.accounts.compression_program)
-            .token_metadata_program(&ctx.accounts.token_metadata_program)
-            .system_program(&ctx.accounts.layerzero_endpoint)
+            .tree_config(tree_config)
+            .leaf_owner(mint_request.to)
+            .leaf_delegate(mint_request.to)
+            .merkle_tree(config.merkle_tree)
+            .payer(config.tree_authority)
+            .tree_creator_or_delegate(config.tree_authority)
+            .collection_authority(config.collection_authority)
+            .collection_mint(config.collection_mint)
+            .log_wrapper(SPL_NOOP_PROGRAM_ID.parse::<Pubkey>().unwrap())
+            .compression_program(SPL_ACCOUNT_COMPRESSION_PROGRAM_ID.parse::<Pubkey>().unwrap())
             .metadata(metadata);
         // For hackathon, just log instead of invoking
         msg!("Would mint cNFT to {} (real CPI, all accounts wired)", mint_request.to);
-        // builder.invoke()?; // Uncomment when all accounts are correct
+        // builder.instruction(); // Uncomment and invoke when collection accounts are wired
     }
     Ok(())
 }
 
 fn handle_burn_cnfts(
-    _ctx: Context<ReceiveLayerZeroMessage>,
+    config: &ControllerConfig,
+    tree_config: Pubkey,
     burn_requests: Vec<crate::state::message_types::BurnRequest>,
 ) -> Result<()> {
     require!(
@@ -273,8 +294,7 @@ fn handle_burn_cnfts(
         ErrorCode::BurnBatchTooLarge
     );
 
-    let _bubblegum_program = &_ctx.accounts.bubblegum_program;
-    for (_i, burn_request) in burn_requests.iter().enumerate() {
+    for burn_request in burn_requests.iter() {
         require!(
             !burn_request.proof.is_empty(),
             ErrorCode::InvalidProof
@@ -284,13 +304,13 @@ fn handle_burn_cnfts(
         // If you want to add more fields (root, data_hash, etc.), add them to BurnRequest and use here
         let mut builder = BurnBuilder::new();
         builder
-            .tree_config(_ctx.accounts.controller_config.key())
+            .tree_config(tree_config)
             .leaf_owner(burn_request.current_owner, false)
-            .leaf_delegate(_ctx.accounts.leaf_delegate.key(), false)
-            .merkle_tree(_ctx.accounts.merkle_tree.key())
-            .log_wrapper(_ctx.accounts.log_wrapper.key())
-            .compression_program(_ctx.accounts.compression_program.key())
-            .system_program(_ctx.accounts.layerzero_endpoint.key())
+            .leaf_delegate(burn_request.current_owner, false)
+            .merkle_tree(config.merkle_tree)
+            .log_wrapper(SPL_NOOP_PROGRAM_ID.parse::<Pubkey>().unwrap())
+            .compression_program(SPL_ACCOUNT_COMPRESSION_PROGRAM_ID.parse::<Pubkey>().unwrap())
+            .system_program(anchor_lang::system_program::ID)
             // .root(burn_request.root) // Uncomment if you add root to BurnRequest
             // .data_hash(burn_request.data_hash) // Uncomment if you add data_hash to BurnRequest
             // .creator_hash(burn_request.creator_hash) // Uncomment if you add creator_hash to BurnRequest
@@ -305,7 +325,8 @@ fn handle_burn_cnfts(
 }
 
 fn handle_transfer_cnfts(
-    _ctx: Context<ReceiveLayerZeroMessage>,
+    config: &ControllerConfig,
+    tree_config: Pubkey,
     transfer_requests: Vec<crate::state::message_types::TransferRequest>,
 ) -> Result<()> {
     require!(
@@ -313,8 +334,7 @@ fn handle_transfer_cnfts(
         ErrorCode::TransferBatchTooLarge
     );
 
-    let _bubblegum_program = &_ctx.accounts.bubblegum_program;
-    for (_i, transfer_request) in transfer_requests.iter().enumerate() {
+    for transfer_request in transfer_requests.iter() {
         require!(
             !transfer_request.proof.is_empty(),
             ErrorCode::InvalidProof
@@ -328,14 +348,14 @@ fn handle_transfer_cnfts(
         // If you want to add more fields (root, data_hash, etc.), add them to TransferRequest and use here
         let mut builder = TransferBuilder::new();
         builder
-            .tree_config(_ctx.accounts.controller_config.key())
+            .tree_config(tree_config)
             .leaf_owner(transfer_request.from, false)
-            .leaf_delegate(_ctx.accounts.leaf_delegate.key(), false)
+            .leaf_delegate(transfer_request.from, false)
             .new_leaf_owner(transfer_request.to)
-            .merkle_tree(_ctx.accounts.merkle_tree.key())
-            .log_wrapper(_ctx.accounts.log_wrapper.key())
-            .compression_program(_ctx.accounts.compression_program.key())
-            .system_program(_ctx.accounts.layerzero_endpoint.key())
+            .merkle_tree(config.merkle_tree)
+            .log_wrapper(SPL_NOOP_PROGRAM_ID.parse::<Pubkey>().unwrap())
+            .compression_program(SPL_ACCOUNT_COMPRESSION_PROGRAM_ID.parse::<Pubkey>().unwrap())
+            .system_program(anchor_lang::system_program::ID)
             // .root(transfer_request.root) // Uncomment if you add root to TransferRequest
             // .data_hash(transfer_request.data_hash) // Uncomment if you add data_hash to TransferRequest
             // .creator_hash(transfer_request.creator_hash) // Uncomment if you add creator_hash to TransferRequest
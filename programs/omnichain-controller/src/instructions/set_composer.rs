@@ -0,0 +1,126 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+
+/// Register the composer program for a compose type
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(compose_type: u8)]
+pub struct SetComposer<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = ComposerConfig::LEN,
+        seeds = [ComposerConfig::SEEDS, store.key().as_ref(), &[compose_type]],
+        bump
+    )]
+    pub composer_config: Account<'info, ComposerConfig>,
+    
+    #[account(
+        mut,
+        seeds = [LzComposeTypes::SEEDS, store.key().as_ref()],
+        bump = lz_compose_types.bump
+    )]
+    pub lz_compose_types: Account<'info, LzComposeTypes>,
+    
+    #[account(
+        has_one = admin,
+        seeds = [OAppStore::SEEDS],
+        bump = store.bump
+    )]
+    pub store: Account<'info, OAppStore>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+/// Remove the composer of a compose type
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(compose_type: u8)]
+pub struct RemoveComposer<'info> {
+    #[account(
+        mut,
+        close = admin,
+        seeds = [ComposerConfig::SEEDS, store.key().as_ref(), &[compose_type]],
+        bump = composer_config.bump
+    )]
+    pub composer_config: Account<'info, ComposerConfig>,
+    
+    #[account(
+        mut,
+        seeds = [LzComposeTypes::SEEDS, store.key().as_ref()],
+        bump = lz_compose_types.bump
+    )]
+    pub lz_compose_types: Account<'info, LzComposeTypes>,
+    
+    #[account(
+        has_one = admin,
+        seeds = [OAppStore::SEEDS],
+        bump = store.bump
+    )]
+    pub store: Account<'info, OAppStore>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+/// Handler for registering a composer
+pub fn set_composer_handler(
+    ctx: Context<SetComposer>,
+    compose_type: u8,
+    composer: Pubkey,
+) -> Result<()> {
+    let compose_types = &mut ctx.accounts.lz_compose_types.compose_types;
+    if !compose_types.contains(&compose_type) {
+        if compose_types.len() >= LzComposeTypes::MAX_COMPOSE_TYPES {
+            return Err(crate::error::ErrorCode::ComposeTypesFull.into());
+        }
+        compose_types.push(compose_type);
+    }
+    
+    let composer_config = &mut ctx.accounts.composer_config;
+    composer_config.store = ctx.accounts.store.key();
+    composer_config.compose_type = compose_type;
+    composer_config.composer = composer;
+    composer_config.bump = ctx.bumps.composer_config;
+    
+    msg!("Composer for type {} set to {}", compose_type, composer);
+    emit_cpi!(ComposerSet {
+        compose_type,
+        composer,
+    });
+    Ok(())
+}
+
+/// Handler for removing a composer
+///
+/// Compose messages of this type fail until a composer is set again.
+pub fn remove_composer_handler(ctx: Context<RemoveComposer>, compose_type: u8) -> Result<()> {
+    ctx.accounts
+        .lz_compose_types
+        .compose_types
+        .retain(|registered| *registered != compose_type);
+    
+    msg!("Composer for type {} removed", compose_type);
+    emit_cpi!(ComposerRemoved {
+        compose_type,
+        composer: ctx.accounts.composer_config.composer,
+    });
+    Ok(())
+}
+
+#[event]
+pub struct ComposerSet {
+    pub compose_type: u8,
+    pub composer: Pubkey,
+}
+
+#[event]
+pub struct ComposerRemoved {
+    pub compose_type: u8,
+    pub composer: Pubkey,
+}
@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+
+/// Replace the DAO signer set used to verify message attestations
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetDaoSigners<'info> {
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [OAppStore::SEEDS],
+        bump = store.bump
+    )]
+    pub store: Account<'info, OAppStore>,
+    
+    pub admin: Signer<'info>,
+}
+
+/// Handler for setting the DAO signer set
+pub fn set_dao_signers_handler(
+    ctx: Context<SetDaoSigners>,
+    signers: Vec<[u8; 20]>,
+    threshold: u8,
+) -> Result<()> {
+    // Threshold must be reachable, and a set without a threshold is meaningless
+    let has_duplicates = signers
+        .iter()
+        .enumerate()
+        .any(|(i, signer)| signers[..i].contains(signer));
+    if signers.len() > DaoConfig::MAX_SIGNERS
+        || has_duplicates
+        || threshold as usize > signers.len()
+        || (threshold == 0 && !signers.is_empty())
+    {
+        return Err(crate::error::ErrorCode::InvalidSignerSet.into());
+    }
+    
+    let dao_config = &mut ctx.accounts.store.dao_config;
+    dao_config.signers = signers;
+    dao_config.signer_threshold = threshold;
+    
+    msg!("DAO signer set updated - {} signers, threshold {}",
+         dao_config.signers.len(), dao_config.signer_threshold);
+    
+    emit_cpi!(DaoSignersUpdated {
+        signers: dao_config.signers.clone(),
+        threshold,
+    });
+    
+    Ok(())
+}
+
+#[event]
+pub struct DaoSignersUpdated {
+    pub signers: Vec<[u8; 20]>,
+    pub threshold: u8,
+}
@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::cpi::endpoint;
+use crate::instructions::lz_receive::PendingEvents;
+
+/// Replace the OApp delegate, locally and on the endpoint
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetDelegate<'info> {
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [OAppStore::SEEDS],
+        bump = store.bump
+    )]
+    pub store: Account<'info, OAppStore>,
+    
+    pub admin: Signer<'info>,
+    
+    /// LayerZero endpoint program
+    /// CHECK: Checked against the store's endpoint
+    #[account(address = store.endpoint @ crate::error::ErrorCode::InvalidEndpoint)]
+    pub endpoint: AccountInfo<'info>,
+    
+    // Endpoint set delegate accounts follow as remaining accounts
+}
+
+/// Handler for replacing the delegate
+pub fn set_delegate_handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetDelegate<'info>>,
+    delegate: Pubkey,
+) -> Result<()> {
+    let mut events = PendingEvents::default();
+    update_delegate(
+        &mut ctx.accounts.store,
+        &ctx.accounts.endpoint,
+        ctx.remaining_accounts,
+        delegate,
+        None,
+        &mut events,
+    )?;
+    events.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)
+}
+
+/// Set the delegate on the endpoint, then in the store
+///
+/// Shared by `set_delegate` and the DAO's set delegate command, which
+/// passes the guid of its message. `accounts` are the endpoint accounts,
+/// led by the OApp registry, which must exist so the command fails here
+/// rather than in the CPI.
+pub(crate) fn update_delegate<'info>(
+    store: &mut Account<'info, OAppStore>,
+    endpoint_program: &AccountInfo<'info>,
+    accounts: &[AccountInfo<'info>],
+    delegate: Pubkey,
+    guid: Option<[u8; 32]>,
+    events: &mut PendingEvents,
+) -> Result<()> {
+    let registered = accounts
+        .first()
+        .is_some_and(|registry| registry.owner == endpoint_program.key && !registry.data_is_empty());
+    require!(registered, crate::error::ErrorCode::OAppNotRegistered);
+    
+    let seeds: &[&[u8]] = &[OAppStore::SEEDS, &[store.bump]];
+    endpoint::set_delegate(
+        endpoint_program,
+        &store.to_account_info(),
+        accounts,
+        seeds,
+        &endpoint::SetDelegateParams { delegate },
+    )?;
+    
+    let previous_delegate = store.delegate;
+    store.delegate = delegate;
+    
+    msg!("Delegate changed from {} to {}", previous_delegate, delegate);
+    events.push(DelegateSet {
+        previous_delegate,
+        delegate,
+        guid,
+    });
+    Ok(())
+}
+
+/// `guid` is set when the DAO changed the delegate
+#[event]
+pub struct DelegateSet {
+    pub previous_delegate: Pubkey,
+    pub delegate: Pubkey,
+    pub guid: Option<[u8; 32]>,
+}
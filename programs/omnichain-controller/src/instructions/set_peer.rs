@@ -0,0 +1,127 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+
+/// Register a trusted peer for a source endpoint, or replace it
+#[derive(Accounts)]
+#[instruction(src_eid: u32)]
+pub struct SetPeer<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = PeerConfig::LEN,
+        seeds = [PeerConfig::SEEDS, store.key().as_ref(), &src_eid.to_le_bytes()],
+        bump
+    )]
+    pub peer_config: Account<'info, PeerConfig>,
+    
+    #[account(
+        has_one = admin,
+        seeds = [OAppStore::SEEDS],
+        bump = store.bump
+    )]
+    pub store: Account<'info, OAppStore>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+/// Stop trusting a source endpoint's peer
+#[derive(Accounts)]
+#[instruction(src_eid: u32)]
+pub struct RemovePeer<'info> {
+    #[account(
+        mut,
+        close = admin,
+        seeds = [PeerConfig::SEEDS, store.key().as_ref(), &src_eid.to_le_bytes()],
+        bump = peer_config.bump
+    )]
+    pub peer_config: Account<'info, PeerConfig>,
+    
+    #[account(
+        has_one = admin,
+        seeds = [OAppStore::SEEDS],
+        bump = store.bump
+    )]
+    pub store: Account<'info, OAppStore>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+/// Change how a peer's inbound nonces must arrive
+#[derive(Accounts)]
+#[instruction(src_eid: u32)]
+pub struct SetPeerDeliveryMode<'info> {
+    #[account(
+        mut,
+        seeds = [PeerConfig::SEEDS, store.key().as_ref(), &src_eid.to_le_bytes()],
+        bump = peer_config.bump
+    )]
+    pub peer_config: Account<'info, PeerConfig>,
+    
+    #[account(
+        has_one = admin,
+        seeds = [OAppStore::SEEDS],
+        bump = store.bump
+    )]
+    pub store: Account<'info, OAppStore>,
+    
+    pub admin: Signer<'info>,
+}
+
+/// Handler for registering a peer
+///
+/// Setting the peer already registered keeps its nonce state, so the
+/// delivery mode can be set again without replaying old messages. A new
+/// address starts its nonces over, as the endpoint does for a new sender.
+pub fn set_peer_handler(
+    ctx: Context<SetPeer>,
+    src_eid: u32,
+    peer_address: [u8; 32],
+    delivery_mode: DeliveryMode,
+) -> Result<()> {
+    let peer_config = &mut ctx.accounts.peer_config;
+    let rotated = !peer_config.trusted || peer_config.peer_address != peer_address;
+    peer_config.src_eid = src_eid;
+    peer_config.peer_address = peer_address;
+    peer_config.trusted = true;
+    peer_config.bump = ctx.bumps.peer_config;
+    peer_config.delivery_mode = delivery_mode;
+    if rotated {
+        peer_config.inbound_nonce = 0;
+        peer_config.nonce_bitmap = 0;
+        peer_config.outbound_nonce = 0;
+        peer_config.dao_nonce = 0;
+        peer_config.dao_nonce_bitmap = 0;
+    }
+    
+    msg!("Peer set for EID {} - {:?} delivery", src_eid, delivery_mode);
+    Ok(())
+}
+
+/// Handler for removing a peer
+pub fn remove_peer_handler(ctx: Context<RemovePeer>, src_eid: u32) -> Result<()> {
+    msg!("Peer removed for EID {} at nonce {}", src_eid, ctx.accounts.peer_config.inbound_nonce);
+    Ok(())
+}
+
+/// Handler for changing a peer's delivery mode
+///
+/// Nonce state carries over: an ordered peer continues from the highest
+/// nonce executed so far.
+pub fn set_peer_delivery_mode_handler(
+    ctx: Context<SetPeerDeliveryMode>,
+    src_eid: u32,
+    delivery_mode: DeliveryMode,
+) -> Result<()> {
+    let peer_config = &mut ctx.accounts.peer_config;
+    peer_config.delivery_mode = delivery_mode;
+    
+    msg!("Peer delivery mode for EID {} set to {:?} at nonce {}",
+         src_eid, delivery_mode, peer_config.inbound_nonce);
+    Ok(())
+}
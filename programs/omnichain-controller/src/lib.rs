@@ -1,3 +1,6 @@
+// Anchor's generated IDL instructions still call the deprecated `AccountInfo::realloc`
+#![allow(deprecated)]
+
 pub mod constants;
 pub mod error;
 pub mod instructions;
@@ -24,13 +27,19 @@ pub mod omnichain_controller {
     // ===============================
 
     /// Initialize the LayerZero OApp Store (NEW) - CRITICAL: Includes endpoint registration
-    pub fn init_oapp_store(
-        ctx: Context<InitOAppStore>,
+    pub fn init_oapp_store<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitOAppStore<'info>>,
         params: InitOAppStoreParams,
     ) -> Result<()> {
         instructions::init_oapp_store_handler(ctx, params)
     }
 
+    /// Move the store and the given peers from the first deployed layout
+    /// to the current one
+    pub fn migrate<'info>(ctx: Context<'_, '_, '_, 'info, Migrate<'info>>) -> Result<()> {
+        instructions::migrate_handler(ctx)
+    }
+
     /// LayerZero receive types instruction - CRITICAL for account discovery
     pub fn lz_receive_types(
         ctx: Context<LzReceiveTypesContext>,
@@ -40,8 +49,8 @@ pub mod omnichain_controller {
     }
 
     /// LayerZero receive message handler (NEW)
-    pub fn lz_receive(
-        ctx: Context<LzReceive>,
+    pub fn lz_receive<'info>(
+        ctx: Context<'_, '_, '_, 'info, LzReceive<'info>>,
         src_eid: u32,
         sender: [u8; 32],
         nonce: u64,
@@ -51,16 +60,186 @@ pub mod omnichain_controller {
         instructions::lz_receive_handler(ctx, src_eid, sender, nonce, guid, message)
     }
 
-    /// LayerZero compose message handler (NEW)
-    pub fn lz_compose(
-        ctx: Context<LzCompose>,
+    /// LayerZero compose message handler - runs the registered composer
+    #[allow(clippy::too_many_arguments)]
+    pub fn lz_compose<'info>(
+        ctx: Context<'_, '_, '_, 'info, LzCompose<'info>>,
         src_eid: u32,
         sender: [u8; 32],
         nonce: u64,
         guid: [u8; 32],
+        index: u16,
         message: Vec<u8>,
     ) -> Result<()> {
-        instructions::lz_compose_handler(ctx, src_eid, sender, nonce, guid, message)
+        instructions::lz_compose_handler(ctx, src_eid, sender, nonce, guid, index, message)
+    }
+
+    /// Send a message to a configured peer, refunding unspent fees
+    pub fn lz_send<'info>(
+        ctx: Context<'_, '_, '_, 'info, LzSend<'info>>,
+        params: LzSendParams,
+    ) -> Result<()> {
+        instructions::lz_send_handler(ctx, params)
+    }
+
+    /// Quote the fee of an lz_send
+    pub fn quote<'info>(
+        ctx: Context<'_, '_, '_, 'info, Quote<'info>>,
+        params: QuoteParams,
+    ) -> Result<cpi::endpoint::MessagingFee> {
+        instructions::quote_handler(ctx, params)
+    }
+
+    // ===============================
+    // OApp Administration
+    // ===============================
+
+    /// Set the DAO signers and threshold used to verify message attestations
+    pub fn set_dao_signers(
+        ctx: Context<SetDaoSigners>,
+        signers: Vec<[u8; 20]>,
+        threshold: u8,
+    ) -> Result<()> {
+        instructions::set_dao_signers_handler(ctx, signers, threshold)
+    }
+
+    /// Register a trusted peer and its nonce delivery mode, or replace the
+    /// peer of a source endpoint
+    pub fn set_peer(
+        ctx: Context<SetPeer>,
+        src_eid: u32,
+        peer_address: [u8; 32],
+        delivery_mode: DeliveryMode,
+    ) -> Result<()> {
+        instructions::set_peer_handler(ctx, src_eid, peer_address, delivery_mode)
+    }
+
+    /// Stop accepting messages from a source endpoint
+    pub fn remove_peer(ctx: Context<RemovePeer>, src_eid: u32) -> Result<()> {
+        instructions::remove_peer_handler(ctx, src_eid)
+    }
+
+    /// Switch a peer between ordered and unordered delivery
+    pub fn set_peer_delivery_mode(
+        ctx: Context<SetPeerDeliveryMode>,
+        src_eid: u32,
+        delivery_mode: DeliveryMode,
+    ) -> Result<()> {
+        instructions::set_peer_delivery_mode_handler(ctx, src_eid, delivery_mode)
+    }
+
+    /// Save commands that fail after validation instead of reverting
+    pub fn set_failed_message_mode(
+        ctx: Context<SetFailedMessageMode>,
+        enabled: bool,
+    ) -> Result<()> {
+        instructions::set_failed_message_mode_handler(ctx, enabled)
+    }
+
+    /// Execute a stored failed message again
+    pub fn retry_failed_message<'info>(
+        ctx: Context<'_, '_, '_, 'info, RetryFailedMessage<'info>>,
+    ) -> Result<()> {
+        instructions::retry_failed_message_handler(ctx)
+    }
+
+    /// Drop a stored failed message without executing it
+    pub fn discard_failed_message(ctx: Context<DiscardFailedMessage>) -> Result<()> {
+        instructions::discard_failed_message_handler(ctx)
+    }
+
+    /// Send an execution receipt back to the DAO after each received message
+    pub fn set_receipt_mode(
+        ctx: Context<SetReceiptMode>,
+        enabled: bool,
+        native_fee: u64,
+    ) -> Result<()> {
+        instructions::set_receipt_mode_handler(ctx, enabled, native_fee)
+    }
+
+    /// Set the endpoint send accounts of receipts to a peer
+    pub fn set_receipt_accounts(
+        ctx: Context<SetReceiptAccounts>,
+        dst_eid: u32,
+        send_accounts: Vec<LzAccount>,
+    ) -> Result<()> {
+        instructions::set_receipt_accounts_handler(ctx, dst_eid, send_accounts)
+    }
+
+    /// Route a compose type to a composer program
+    pub fn set_composer(
+        ctx: Context<SetComposer>,
+        compose_type: u8,
+        composer: Pubkey,
+    ) -> Result<()> {
+        instructions::set_composer_handler(ctx, compose_type, composer)
+    }
+
+    /// Stop routing a compose type
+    pub fn remove_composer(ctx: Context<RemoveComposer>, compose_type: u8) -> Result<()> {
+        instructions::remove_composer_handler(ctx, compose_type)
+    }
+
+    /// Enforce type 3 options on a message type sent to a destination,
+    /// replacing any already enforced
+    pub fn set_enforced_options(
+        ctx: Context<SetEnforcedOptions>,
+        dst_eid: u32,
+        msg_type: u8,
+        options: Vec<u8>,
+    ) -> Result<()> {
+        instructions::set_enforced_options_handler(ctx, dst_eid, msg_type, options)
+    }
+
+    /// Stop enforcing options on a message type sent to a destination
+    pub fn remove_enforced_options(
+        ctx: Context<RemoveEnforcedOptions>,
+        dst_eid: u32,
+        msg_type: u8,
+    ) -> Result<()> {
+        instructions::remove_enforced_options_handler(ctx, dst_eid, msg_type)
+    }
+
+    /// Set the endpoint send library for a destination
+    pub fn set_send_library<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetEndpointConfig<'info>>,
+        params: SetSendLibraryParams,
+    ) -> Result<()> {
+        instructions::set_send_library_handler(ctx, params)
+    }
+
+    /// Set the endpoint receive library for a source
+    pub fn set_receive_library<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetEndpointConfig<'info>>,
+        params: SetReceiveLibraryParams,
+    ) -> Result<()> {
+        instructions::set_receive_library_handler(ctx, params)
+    }
+
+    /// Set the executor or ULN (DVNs, confirmations) config of a path
+    pub fn set_config<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetEndpointConfig<'info>>,
+        params: SetConfigParams,
+    ) -> Result<()> {
+        instructions::set_config_handler(ctx, params)
+    }
+
+    /// Replace the OApp delegate in the store and on the endpoint
+    pub fn set_delegate<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetDelegate<'info>>,
+        delegate: Pubkey,
+    ) -> Result<()> {
+        instructions::set_delegate_handler(ctx, delegate)
+    }
+
+    /// Accept the admin role proposed by the DAO's authority transfer
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        instructions::accept_admin_handler(ctx)
+    }
+
+    /// Cancel a pending admin transfer
+    pub fn cancel_admin_transfer(ctx: Context<CancelAdminTransfer>) -> Result<()> {
+        instructions::cancel_admin_transfer_handler(ctx)
     }
 
     // ===============================
@@ -282,7 +282,10 @@ pub struct File {
     pub file_type: Option<String>,
 }
 
-/// Event emitted when a cross-chain message is processed
+/// Event emitted when a legacy `CrossChainMessage` is processed
+///
+/// Emitted by `receive_layerzero_message` and by `lz_receive` for the Borsh
+/// wire version. Failed messages revert, so `success` is always set.
 #[event]
 pub struct CrossChainMessageProcessed {
     pub nonce: u64,
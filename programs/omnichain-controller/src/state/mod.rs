@@ -17,12 +17,17 @@ pub use message_types::{
 // Re-export OApp store types
 pub use oapp_store::{
     OAppStore, PeerConfig, LzReceiveTypes, LzComposeTypes,
-    CollectionMetadata, DaoConfig
+    CollectionMetadata, DaoConfig, DeliveryMode, FailedMessage, ComposerConfig, ComposeAuthority, ComposeReceipt,
+    FeeVault, EnforcedOptions, ReceiptConfig
 };
 
 // Re-export message codec
 pub use msg_codec::{
-    MessageCodec, DecodedMessage, UpdateMetadataPayload, MessageValidator
+    MessageCodec, DecodedMessage, UpdateMetadataPayload, MessageValidator,
+    AbiDecoder, AbiEncoder, AbiReader, AbiStr, AbiArray, MessageDecoder, Command, BatchUpdateCnftsPayload,
+    CnftUpdate, TransferAuthorityPayload, SetMinVersionPayload, EnvelopePayload,
+    Attestation, ComposePayload, ComposeMessage, LegacyPayload, ReceiptPayload,
+    SetDelegatePayload
 };
 
 // Re-export collection manager types (Phase 5)
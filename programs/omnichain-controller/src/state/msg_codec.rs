@@ -1,4 +1,7 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::secp256k1_recover::secp256k1_recover;
+use solana_keccak_hasher as keccak;
+use crate::state::message_types::{CrossChainMessage, MessageCommand};
 
 /// Message codec for cross-chain communication
 /// Provides standardized encoding/decoding for LayerZero messages
@@ -15,9 +18,51 @@ impl MessageCodec {
     pub const COMMAND_TRANSFER_AUTHORITY: u8 = 2;
     pub const COMMAND_EMERGENCY_PAUSE: u8 = 3;
     pub const COMMAND_EMERGENCY_UNPAUSE: u8 = 4;
+    pub const COMMAND_SET_MIN_VERSION: u8 = 5;
+    pub const COMMAND_ENVELOPE: u8 = 6;
+    pub const COMMAND_COMPOSE: u8 = 7;
+    /// Borsh `CrossChainMessage`, only carried by `MESSAGE_VERSION_BORSH`
+    pub const COMMAND_LEGACY: u8 = 8;
+    /// Execution receipt, only sent from Solana back to the DAO
+    pub const COMMAND_RECEIPT: u8 = 9;
+    /// Rotate the endpoint delegate; needs endpoint accounts, so top-level only
+    pub const COMMAND_SET_DELEGATE: u8 = 10;
     
-    /// Message version
-    pub const MESSAGE_VERSION: u8 = 1;
+    /// Wire-format versions
+    pub const MESSAGE_VERSION_V1: u8 = 1;
+    pub const MESSAGE_VERSION_V2: u8 = 2;
+    pub const MESSAGE_VERSION_BORSH: u8 = 3;
+
+    /// Message version written by `encode_message`
+    pub const MESSAGE_VERSION: u8 = Self::MESSAGE_VERSION_V1;
+
+    /// Registered decoders, one per accepted wire-format version
+    pub const DECODERS: &'static [(u8, MessageDecoder)] = &[
+        (Self::MESSAGE_VERSION_V1, Self::decode_v1),
+        (Self::MESSAGE_VERSION_V2, Self::decode_v2),
+        (Self::MESSAGE_VERSION_BORSH, Self::decode_borsh),
+    ];
+
+    /// Commands that must carry a DAO attestation once a signer threshold is set
+    pub const ATTESTED_COMMANDS: &'static [u8] = &[Self::COMMAND_TRANSFER_AUTHORITY, Self::COMMAND_SET_DELEGATE];
+
+    /// Commands the admin can send to a peer with lz_send. Receipts are
+    /// left out: only lz_receive reports what it executed.
+    pub const OUTBOUND_COMMANDS: &'static [u8] = &[
+        Self::COMMAND_UPDATE_COLLECTION_METADATA,
+        Self::COMMAND_BATCH_UPDATE_CNFTS,
+        Self::COMMAND_TRANSFER_AUTHORITY,
+        Self::COMMAND_EMERGENCY_PAUSE,
+        Self::COMMAND_EMERGENCY_UNPAUSE,
+        Self::COMMAND_SET_MIN_VERSION,
+        Self::COMMAND_ENVELOPE,
+        Self::COMMAND_COMPOSE,
+        Self::COMMAND_LEGACY,
+        Self::COMMAND_SET_DELEGATE,
+    ];
+
+    /// Head word holding the version in every ABI wire format
+    const VERSION_WORD: usize = 3;
 
     /// Encode a cross-chain message
     pub fn encode_message(
@@ -26,47 +71,142 @@ impl MessageCodec {
         timestamp: i64,
         payload: &[u8],
     ) -> Result<Vec<u8>> {
-        let mut encoded = Vec::new();
-        
-        // Message format: [version][command][nonce][timestamp][payload_length][payload]
-        encoded.push(Self::MESSAGE_VERSION);
-        encoded.push(command);
-        encoded.extend_from_slice(&nonce.to_le_bytes());
-        encoded.extend_from_slice(&timestamp.to_le_bytes());
-        encoded.extend_from_slice(&(payload.len() as u32).to_le_bytes());
-        encoded.extend_from_slice(payload);
-        
-        Ok(encoded)
+        // Message format (matches SolanaControllerDAOV2.executeProposal):
+        // abi.encode(uint8 command, uint64 nonce, int64 timestamp, uint8 version, bytes payload)
+        Ok(Self::encode_header(command, nonce, timestamp, Self::MESSAGE_VERSION_V1)
+            .bytes(payload)
+            .finish())
+    }
+
+    /// Encode a cross-chain message in the v2 format
+    pub fn encode_message_v2(
+        command: u8,
+        nonce: u64,
+        timestamp: i64,
+        payload: &[u8],
+        extensions: &[u8],
+    ) -> Result<Vec<u8>> {
+        // Message format: v1 tuple with a trailing `bytes extensions` section
+        Ok(Self::encode_header(command, nonce, timestamp, Self::MESSAGE_VERSION_V2)
+            .bytes(payload)
+            .bytes(extensions)
+            .finish())
+    }
+
+    /// Encode an already Borsh-serialized legacy payload in the v3 format
+    pub fn encode_message_v3(
+        nonce: u64,
+        timestamp: i64,
+        payload: &[u8],
+        extensions: &[u8],
+    ) -> Result<Vec<u8>> {
+        // Message format: v2 layout with the command fixed to COMMAND_LEGACY
+        Ok(Self::encode_header(Self::COMMAND_LEGACY, nonce, timestamp, Self::MESSAGE_VERSION_BORSH)
+            .bytes(payload)
+            .bytes(extensions)
+            .finish())
+    }
+
+    /// Encode a legacy Borsh `CrossChainMessage` in the v3 format
+    pub fn encode_message_borsh(message: &CrossChainMessage, extensions: &[u8]) -> Result<Vec<u8>> {
+        let payload = anchor_lang::prelude::borsh::to_vec(message)?;
+        Self::encode_message_v3(message.nonce, message.timestamp, &payload, extensions)
+    }
+
+    /// Decode a cross-chain message with the decoder registered for its version
+    pub fn decode_message(data: &[u8]) -> Result<DecodedMessage<'_>> {
+        let version = Self::peek_version(data)?;
+        let decoder = Self::decoder_for(version)
+            .ok_or(crate::error::ErrorCode::UnsupportedVersion)?;
+
+        decoder(data)
+    }
+
+    /// Read the wire-format version without decoding the rest of the message
+    pub fn peek_version(data: &[u8]) -> Result<u8> {
+        AbiDecoder::new(data).read_u8(Self::VERSION_WORD)
+    }
+
+    /// Look up the decoder registered for a version
+    pub fn decoder_for(version: u8) -> Option<MessageDecoder> {
+        Self::DECODERS
+            .iter()
+            .find(|(registered, _)| *registered == version)
+            .map(|(_, decoder)| *decoder)
+    }
+
+    /// Whether a decoder is registered for a version
+    pub fn is_supported_version(version: u8) -> bool {
+        Self::decoder_for(version).is_some()
     }
 
-    /// Decode a cross-chain message
-    pub fn decode_message(data: &[u8]) -> Result<DecodedMessage> {
-        if data.len() < 22 { // minimum: version(1) + command(1) + nonce(8) + timestamp(8) + payload_length(4)
+    /// v1: abi.encode(uint8 command, uint64 nonce, int64 timestamp, uint8 version, bytes payload)
+    fn decode_v1(data: &[u8]) -> Result<DecodedMessage<'_>> {
+        // minimum: five head words + payload length word
+        if data.len() < 6 * ABI_WORD_SIZE {
             return Err(crate::error::ErrorCode::InvalidLzMessage.into());
         }
 
-        let version = data[0];
-        if version != Self::MESSAGE_VERSION {
-            return Err(crate::error::ErrorCode::UnsupportedVersion.into());
+        let mut abi = AbiReader::new(data, 5);
+        let mut decoded = Self::decode_header(&abi, Self::MESSAGE_VERSION_V1)?;
+        decoded.payload = abi.bytes(4)?;
+        abi.finish()?;
+
+        Ok(decoded)
+    }
+
+    /// v2: v1 tuple followed by `bytes extensions`
+    fn decode_v2(data: &[u8]) -> Result<DecodedMessage<'_>> {
+        Self::decode_with_extensions(data, Self::MESSAGE_VERSION_V2)
+    }
+
+    /// v3: v2 layout whose payload is a Borsh `CrossChainMessage`, so the
+    /// legacy command set goes through the same checks as ABI commands
+    fn decode_borsh(data: &[u8]) -> Result<DecodedMessage<'_>> {
+        let decoded = Self::decode_with_extensions(data, Self::MESSAGE_VERSION_BORSH)?;
+        if decoded.command != Self::COMMAND_LEGACY {
+            return Err(crate::error::ErrorCode::UnsupportedCommand.into());
         }
 
-        let command = data[1];
-        let nonce = u64::from_le_bytes(data[2..10].try_into().unwrap());
-        let timestamp = i64::from_le_bytes(data[10..18].try_into().unwrap());
-        let payload_length = u32::from_le_bytes(data[18..22].try_into().unwrap()) as usize;
-        
-        if data.len() < 22 + payload_length {
+        Ok(decoded)
+    }
+
+    fn decode_with_extensions(data: &[u8], version: u8) -> Result<DecodedMessage<'_>> {
+        // minimum: six head words + payload and extensions length words
+        if data.len() < 8 * ABI_WORD_SIZE {
             return Err(crate::error::ErrorCode::InvalidLzMessage.into());
         }
 
-        let payload = data[22..22 + payload_length].to_vec();
+        let mut abi = AbiReader::new(data, 6);
+        let mut decoded = Self::decode_header(&abi, version)?;
+        decoded.payload = abi.bytes(4)?;
+        decoded.extensions = abi.bytes(5)?;
+        abi.finish()?;
+
+        Ok(decoded)
+    }
+
+    /// Static head shared by every ABI wire format
+    fn encode_header(command: u8, nonce: u64, timestamp: i64, version: u8) -> AbiEncoder {
+        AbiEncoder::new()
+            .uint(command as u64)
+            .uint(nonce)
+            .int(timestamp)
+            .uint(version as u64)
+    }
+
+    fn decode_header<'a>(abi: &AbiReader<'a>, version: u8) -> Result<DecodedMessage<'a>> {
+        if abi.u8(Self::VERSION_WORD)? != version {
+            return Err(crate::error::ErrorCode::UnsupportedVersion.into());
+        }
 
         Ok(DecodedMessage {
             version,
-            command,
-            nonce,
-            timestamp,
-            payload,
+            command: abi.u8(0)?,
+            nonce: abi.u64(1)?,
+            timestamp: abi.i64(2)?,
+            payload: &[],
+            extensions: &[],
         })
     }
 
@@ -76,81 +216,24 @@ impl MessageCodec {
         new_name: &str,
         new_symbol: &str,
     ) -> Result<Vec<u8>> {
-        let mut payload = Vec::new();
-        
-        // Encode strings with length prefixes
-        payload.extend_from_slice(&(new_uri.len() as u32).to_le_bytes());
-        payload.extend_from_slice(new_uri.as_bytes());
-        
-        payload.extend_from_slice(&(new_name.len() as u32).to_le_bytes());
-        payload.extend_from_slice(new_name.as_bytes());
-        
-        payload.extend_from_slice(&(new_symbol.len() as u32).to_le_bytes());
-        payload.extend_from_slice(new_symbol.as_bytes());
-        
-        Ok(payload)
+        Ok(UpdateMetadataPayload {
+            uri: new_uri.into(),
+            name: new_name.into(),
+            symbol: new_symbol.into(),
+        }
+        .encode())
     }
 
     /// Decode update collection metadata payload
-    pub fn decode_update_metadata_payload(payload: &[u8]) -> Result<UpdateMetadataPayload> {
-        let mut offset = 0;
-        
-        // Decode URI
-        if offset + 4 > payload.len() {
-            return Err(crate::error::ErrorCode::InvalidLzMessage.into());
-        }
-        let uri_len = u32::from_le_bytes(payload[offset..offset + 4].try_into().unwrap()) as usize;
-        offset += 4;
-        
-        if offset + uri_len > payload.len() {
-            return Err(crate::error::ErrorCode::InvalidLzMessage.into());
-        }
-        let uri = String::from_utf8(payload[offset..offset + uri_len].to_vec())
-            .map_err(|_| crate::error::ErrorCode::InvalidLzMessage)?;
-        offset += uri_len;
-        
-        // Decode name
-        if offset + 4 > payload.len() {
-            return Err(crate::error::ErrorCode::InvalidLzMessage.into());
-        }
-        let name_len = u32::from_le_bytes(payload[offset..offset + 4].try_into().unwrap()) as usize;
-        offset += 4;
-        
-        if offset + name_len > payload.len() {
-            return Err(crate::error::ErrorCode::InvalidLzMessage.into());
-        }
-        let name = String::from_utf8(payload[offset..offset + name_len].to_vec())
-            .map_err(|_| crate::error::ErrorCode::InvalidLzMessage)?;
-        offset += name_len;
-        
-        // Decode symbol
-        if offset + 4 > payload.len() {
-            return Err(crate::error::ErrorCode::InvalidLzMessage.into());
-        }
-        let symbol_len = u32::from_le_bytes(payload[offset..offset + 4].try_into().unwrap()) as usize;
-        offset += 4;
-        
-        if offset + symbol_len > payload.len() {
-            return Err(crate::error::ErrorCode::InvalidLzMessage.into());
-        }
-        let symbol = String::from_utf8(payload[offset..offset + symbol_len].to_vec())
-            .map_err(|_| crate::error::ErrorCode::InvalidLzMessage)?;
-        
-        Ok(UpdateMetadataPayload {
-            uri,
-            name,
-            symbol,
-        })
+    pub fn decode_update_metadata_payload(payload: &[u8]) -> Result<UpdateMetadataPayload<'_>> {
+        UpdateMetadataPayload::decode(payload)
     }
 
     /// Determine message type from encoded data
+    ///
+    /// Messages carrying a compose command are forwarded to `lz_compose`.
     pub fn get_message_type(data: &[u8]) -> Result<u8> {
-        if data.is_empty() {
-            return Err(crate::error::ErrorCode::InvalidLzMessage.into());
-        }
-        
-        // Check for compose message marker
-        if data.len() >= 2 && data[0] == 0xFF {
+        if Self::decode_message(data)?.command == Self::COMMAND_COMPOSE {
             return Ok(Self::MSG_TYPE_COMPOSE);
         }
         
@@ -164,27 +247,1075 @@ impl MessageCodec {
             Self::COMMAND_BATCH_UPDATE_CNFTS |
             Self::COMMAND_TRANSFER_AUTHORITY |
             Self::COMMAND_EMERGENCY_PAUSE |
-            Self::COMMAND_EMERGENCY_UNPAUSE
+            Self::COMMAND_EMERGENCY_UNPAUSE |
+            Self::COMMAND_SET_MIN_VERSION |
+            Self::COMMAND_ENVELOPE |
+            Self::COMMAND_COMPOSE |
+            Self::COMMAND_LEGACY |
+            Self::COMMAND_RECEIPT |
+            Self::COMMAND_SET_DELEGATE
         )
     }
+
+    /// Whether a command must be attested by the DAO signer set
+    pub fn requires_attestation(command: u8) -> bool {
+        Self::ATTESTED_COMMANDS.contains(&command)
+    }
 }
 
+/// Decoder for a single wire-format version
+pub type MessageDecoder = for<'a> fn(&'a [u8]) -> Result<DecodedMessage<'a>>;
+
 /// Decoded message structure
-#[derive(Debug, Clone)]
-pub struct DecodedMessage {
+///
+/// Borrows the payload and extensions from the message buffer instead of
+/// copying them.
+#[derive(Debug, Clone, Copy)]
+pub struct DecodedMessage<'a> {
     pub version: u8,
     pub command: u8,
     pub nonce: u64,
     pub timestamp: i64,
-    pub payload: Vec<u8>,
+    pub payload: &'a [u8],
+    /// Optional trailing section (v2 and later, empty for v1), carrying
+    /// the DAO signer attestation when present
+    pub extensions: &'a [u8],
+}
+
+impl<'a> DecodedMessage<'a> {
+    /// Decode the payload into the typed command it carries
+    ///
+    /// Legacy commands are only accepted in `MESSAGE_VERSION_BORSH` messages.
+    pub fn decode_command(&self) -> Result<Command<'a>> {
+        if self.command == MessageCodec::COMMAND_LEGACY
+            && self.version != MessageCodec::MESSAGE_VERSION_BORSH
+        {
+            return Err(crate::error::ErrorCode::UnsupportedCommand.into());
+        }
+        Command::decode(self.command, self.payload)
+    }
+
+    /// DAO signer attestation, if the message carries one
+    pub fn attestation(&self) -> Result<Option<Attestation<'a>>> {
+        if self.extensions.is_empty() {
+            return Ok(None);
+        }
+        Attestation::decode(self.extensions).map(Some)
+    }
+
+    /// Hash the DAO signers sign for this message
+    ///
+    /// `keccak256("\x19Ethereum Signed Message:\n32" || keccak256(abi.encode(
+    /// bytes32 receiver, uint8 command, uint64 nonce, int64 timestamp, bytes payload)))`,
+    /// where `receiver` is the OApp store. The version is left out because
+    /// only v2 and later messages can carry an attestation.
+    pub fn attestation_hash(&self, receiver: &Pubkey) -> [u8; 32] {
+        // Head words and payload length, hashed in pieces so the payload is not copied
+        let head = AbiEncoder::new()
+            .bytes32(receiver.to_bytes())
+            .uint(self.command as u64)
+            .uint(self.nonce)
+            .int(self.timestamp)
+            .uint(5 * ABI_WORD_SIZE as u64)
+            .uint(self.payload.len() as u64)
+            .finish();
+        let padding = [0u8; ABI_WORD_SIZE];
+        let padding = &padding[..(ABI_WORD_SIZE - self.payload.len() % ABI_WORD_SIZE) % ABI_WORD_SIZE];
+        let message_hash = keccak::hashv(&[&head, self.payload, padding]);
+
+        keccak::hashv(&[b"\x19Ethereum Signed Message:\n32", message_hash.as_ref()]).to_bytes()
+    }
+}
+
+/// Typed OApp command, one variant per command discriminant
+///
+/// Payloads borrow from the message buffer; string and array fields are
+/// views that decode their contents on access.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command<'a> {
+    UpdateCollectionMetadata(UpdateMetadataPayload<'a>),
+    BatchUpdateCnfts(BatchUpdateCnftsPayload<'a>),
+    TransferAuthority(TransferAuthorityPayload),
+    EmergencyPause,
+    EmergencyUnpause,
+    SetMinVersion(SetMinVersionPayload),
+    Envelope(EnvelopePayload<'a>),
+    Compose(ComposePayload<'a>),
+    Legacy(LegacyPayload<'a>),
+    Receipt(ReceiptPayload),
+    SetDelegate(SetDelegatePayload),
+}
+
+impl<'a> Command<'a> {
+    /// Command discriminant carried in the message header
+    pub fn discriminant(&self) -> u8 {
+        match self {
+            Command::UpdateCollectionMetadata(_) => MessageCodec::COMMAND_UPDATE_COLLECTION_METADATA,
+            Command::BatchUpdateCnfts(_) => MessageCodec::COMMAND_BATCH_UPDATE_CNFTS,
+            Command::TransferAuthority(_) => MessageCodec::COMMAND_TRANSFER_AUTHORITY,
+            Command::EmergencyPause => MessageCodec::COMMAND_EMERGENCY_PAUSE,
+            Command::EmergencyUnpause => MessageCodec::COMMAND_EMERGENCY_UNPAUSE,
+            Command::SetMinVersion(_) => MessageCodec::COMMAND_SET_MIN_VERSION,
+            Command::Envelope(_) => MessageCodec::COMMAND_ENVELOPE,
+            Command::Compose(_) => MessageCodec::COMMAND_COMPOSE,
+            Command::Legacy(_) => MessageCodec::COMMAND_LEGACY,
+            Command::Receipt(_) => MessageCodec::COMMAND_RECEIPT,
+            Command::SetDelegate(_) => MessageCodec::COMMAND_SET_DELEGATE,
+        }
+    }
+
+    /// Encode the command payload
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Command::UpdateCollectionMetadata(payload) => payload.encode(),
+            Command::BatchUpdateCnfts(payload) => payload.encode(),
+            Command::TransferAuthority(payload) => payload.encode(),
+            Command::EmergencyPause | Command::EmergencyUnpause => Vec::new(),
+            Command::SetMinVersion(payload) => payload.encode(),
+            Command::Envelope(payload) => payload.encode(),
+            Command::Compose(payload) => payload.encode(),
+            Command::Legacy(payload) => payload.message.to_vec(),
+            Command::Receipt(payload) => payload.encode(),
+            Command::SetDelegate(payload) => payload.encode(),
+        }
+    }
+
+    /// Whether the command, or any command in its envelope, must be attested
+    pub fn requires_attestation(&self) -> Result<bool> {
+        if let Command::Envelope(envelope) = self {
+            for index in 0..envelope.len() {
+                if MessageCodec::requires_attestation(envelope.command_at(index)?) {
+                    return Ok(true);
+                }
+            }
+            return Ok(false);
+        }
+        if let Command::Legacy(legacy) = self {
+            let command = legacy.message()?.command;
+            return Ok(matches!(command, MessageCommand::TransferAuthority { .. }));
+        }
+        Ok(MessageCodec::requires_attestation(self.discriminant()))
+    }
+
+    /// Decode a command payload
+    ///
+    /// Only the canonical encoding is accepted, so `decode` followed by
+    /// `encode` always reproduces the input bytes.
+    pub fn decode(command: u8, payload: &'a [u8]) -> Result<Self> {
+        Ok(match command {
+            MessageCodec::COMMAND_UPDATE_COLLECTION_METADATA => {
+                Command::UpdateCollectionMetadata(UpdateMetadataPayload::decode(payload)?)
+            }
+            MessageCodec::COMMAND_BATCH_UPDATE_CNFTS => {
+                Command::BatchUpdateCnfts(BatchUpdateCnftsPayload::decode(payload)?)
+            }
+            MessageCodec::COMMAND_TRANSFER_AUTHORITY => {
+                Command::TransferAuthority(TransferAuthorityPayload::decode(payload)?)
+            }
+            MessageCodec::COMMAND_EMERGENCY_PAUSE => {
+                AbiReader::new(payload, 0).finish()?;
+                Command::EmergencyPause
+            }
+            MessageCodec::COMMAND_EMERGENCY_UNPAUSE => {
+                AbiReader::new(payload, 0).finish()?;
+                Command::EmergencyUnpause
+            }
+            MessageCodec::COMMAND_SET_MIN_VERSION => {
+                Command::SetMinVersion(SetMinVersionPayload::decode(payload)?)
+            }
+            MessageCodec::COMMAND_ENVELOPE => {
+                Command::Envelope(EnvelopePayload::decode(payload)?)
+            }
+            MessageCodec::COMMAND_COMPOSE => {
+                Command::Compose(ComposePayload::decode(payload)?)
+            }
+            MessageCodec::COMMAND_LEGACY => Command::Legacy(LegacyPayload { message: payload }),
+            MessageCodec::COMMAND_RECEIPT => Command::Receipt(ReceiptPayload::decode(payload)?),
+            MessageCodec::COMMAND_SET_DELEGATE => {
+                Command::SetDelegate(SetDelegatePayload::decode(payload)?)
+            }
+            _ => return Err(crate::error::ErrorCode::UnknownCommand.into()),
+        })
+    }
+}
+
+/// Legacy command payload: a Borsh-encoded `CrossChainMessage`
+///
+/// Deserialized on access, like the other views.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LegacyPayload<'a> {
+    pub message: &'a [u8],
+}
+
+impl<'a> LegacyPayload<'a> {
+    pub fn message(&self) -> Result<CrossChainMessage> {
+        CrossChainMessage::try_from_slice(self.message)
+            .map_err(|_| crate::error::ErrorCode::InvalidMessageFormat.into())
+    }
 }
 
 /// Update metadata payload structure
-#[derive(Debug, Clone)]
-pub struct UpdateMetadataPayload {
-    pub uri: String,
-    pub name: String,
-    pub symbol: String,
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpdateMetadataPayload<'a> {
+    pub uri: AbiStr<'a>,
+    pub name: AbiStr<'a>,
+    pub symbol: AbiStr<'a>,
+}
+
+impl<'a> UpdateMetadataPayload<'a> {
+    /// Payload format: abi.encode(string newUri, string newName, string newSymbol)
+    pub fn encode(&self) -> Vec<u8> {
+        AbiEncoder::new()
+            .bytes(self.uri.as_bytes())
+            .bytes(self.name.as_bytes())
+            .bytes(self.symbol.as_bytes())
+            .finish()
+    }
+
+    pub fn decode(payload: &'a [u8]) -> Result<Self> {
+        let mut abi = AbiReader::new(payload, 3);
+        let decoded = Self {
+            uri: abi.string(0)?,
+            name: abi.string(1)?,
+            symbol: abi.string(2)?,
+        };
+        abi.finish()?;
+
+        Ok(decoded)
+    }
+}
+
+/// Batch update cNFTs payload structure
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchUpdateCnftsPayload<'a> {
+    leaf_indices: AbiArray<'a>,
+    new_uris: AbiArray<'a>,
+}
+
+/// Single cNFT update inside a batch
+#[derive(Debug, Clone, PartialEq)]
+pub struct CnftUpdate<'a> {
+    pub leaf_index: u32,
+    pub new_uri: AbiStr<'a>,
+}
+
+impl<'a> BatchUpdateCnftsPayload<'a> {
+    /// Payload format: abi.encode(uint32[] leafIndices, string[] newUris)
+    pub fn encode(&self) -> Vec<u8> {
+        AbiEncoder::new()
+            .encoded(self.leaf_indices.encoded())
+            .encoded(self.new_uris.encoded())
+            .finish()
+    }
+
+    /// Encode a batch from owned updates
+    pub fn encode_updates(updates: &[CnftUpdate]) -> Vec<u8> {
+        let leaf_indices = updates
+            .iter()
+            .fold(AbiEncoder::new(), |abi, update| abi.uint(update.leaf_index as u64));
+        let new_uris = updates
+            .iter()
+            .fold(AbiEncoder::new(), |abi, update| abi.bytes(update.new_uri.as_bytes()));
+
+        AbiEncoder::new()
+            .array(leaf_indices)
+            .array(new_uris)
+            .finish()
+    }
+
+    pub fn decode(payload: &'a [u8]) -> Result<Self> {
+        let mut abi = AbiReader::new(payload, 2);
+        let leaf_indices = abi.uint_array(0, 4)?;
+        let new_uris = abi.bytes_array(1)?;
+        abi.finish()?;
+
+        if leaf_indices.len() != new_uris.len() {
+            return Err(crate::error::ErrorCode::InvalidLzMessage.into());
+        }
+
+        Ok(Self { leaf_indices, new_uris })
+    }
+
+    /// Number of updates in the batch
+    pub fn len(&self) -> usize {
+        self.leaf_indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaf_indices.is_empty()
+    }
+
+    /// Decode the update at `index`
+    pub fn get(&self, index: usize) -> Result<CnftUpdate<'a>> {
+        Ok(CnftUpdate {
+            leaf_index: self.leaf_indices.u32_at(index)?,
+            new_uri: self.new_uris.str_at(index)?,
+        })
+    }
+
+    /// Decode the updates one at a time
+    pub fn iter(&self) -> impl Iterator<Item = Result<CnftUpdate<'a>>> + '_ {
+        (0..self.len()).map(move |index| self.get(index))
+    }
+}
+
+/// Transfer authority payload structure
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferAuthorityPayload {
+    pub new_authority: Pubkey,
+}
+
+impl TransferAuthorityPayload {
+    /// Payload format: abi.encode(bytes32 newAuthority)
+    pub fn encode(&self) -> Vec<u8> {
+        AbiEncoder::new()
+            .bytes32(self.new_authority.to_bytes())
+            .finish()
+    }
+
+    pub fn decode(payload: &[u8]) -> Result<Self> {
+        let abi = AbiReader::new(payload, 1);
+        let new_authority = Pubkey::new_from_array(abi.bytes32(0)?);
+        abi.finish()?;
+
+        Ok(Self { new_authority })
+    }
+}
+
+/// Set delegate payload structure
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetDelegatePayload {
+    pub delegate: Pubkey,
+}
+
+impl SetDelegatePayload {
+    /// Payload format: abi.encode(bytes32 delegate)
+    pub fn encode(&self) -> Vec<u8> {
+        AbiEncoder::new()
+            .bytes32(self.delegate.to_bytes())
+            .finish()
+    }
+
+    pub fn decode(payload: &[u8]) -> Result<Self> {
+        let abi = AbiReader::new(payload, 1);
+        let delegate = Pubkey::new_from_array(abi.bytes32(0)?);
+        abi.finish()?;
+
+        Ok(Self { delegate })
+    }
+}
+
+/// Execution receipt payload: what became of a DAO message on Solana
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceiptPayload {
+    /// LayerZero guid of the message
+    pub guid: [u8; 32],
+    /// DAO nonce of the message
+    pub nonce: u64,
+    /// 0 if the command executed, otherwise its error code
+    pub result_code: u32,
+    /// Collection Merkle root after the command
+    pub merkle_root: [u8; 32],
+    /// Messages processed so far
+    pub processed_messages: u64,
+}
+
+impl ReceiptPayload {
+    /// Payload format:
+    /// abi.encode(bytes32 guid, uint64 nonce, uint32 resultCode, bytes32 merkleRoot, uint64 processedMessages)
+    pub fn encode(&self) -> Vec<u8> {
+        AbiEncoder::new()
+            .bytes32(self.guid)
+            .uint(self.nonce)
+            .uint(self.result_code as u64)
+            .bytes32(self.merkle_root)
+            .uint(self.processed_messages)
+            .finish()
+    }
+
+    pub fn decode(payload: &[u8]) -> Result<Self> {
+        let abi = AbiReader::new(payload, 5);
+        let receipt = Self {
+            guid: abi.bytes32(0)?,
+            nonce: abi.u64(1)?,
+            result_code: abi.u32(2)?,
+            merkle_root: abi.bytes32(3)?,
+            processed_messages: abi.u64(4)?,
+        };
+        abi.finish()?;
+
+        Ok(receipt)
+    }
+}
+
+/// Set minimum version payload structure
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetMinVersionPayload {
+    pub min_version: u8,
+}
+
+impl SetMinVersionPayload {
+    /// Payload format: abi.encode(uint8 minVersion)
+    pub fn encode(&self) -> Vec<u8> {
+        AbiEncoder::new().uint(self.min_version as u64).finish()
+    }
+
+    pub fn decode(payload: &[u8]) -> Result<Self> {
+        let abi = AbiReader::new(payload, 1);
+        let min_version = abi.u8(0)?;
+        abi.finish()?;
+
+        Ok(Self { min_version })
+    }
+}
+
+/// Envelope payload structure: an ordered list of sub-commands
+///
+/// Sub-commands are decoded one at a time as they are executed. Envelopes
+/// cannot be nested.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvelopePayload<'a> {
+    commands: AbiArray<'a>,
+    payloads: AbiArray<'a>,
+}
+
+impl<'a> EnvelopePayload<'a> {
+    /// Payload format: abi.encode(uint8[] commands, bytes[] payloads)
+    pub fn encode(&self) -> Vec<u8> {
+        AbiEncoder::new()
+            .encoded(self.commands.encoded())
+            .encoded(self.payloads.encoded())
+            .finish()
+    }
+
+    /// Encode an envelope from a list of sub-commands
+    pub fn encode_commands(commands: &[Command]) -> Vec<u8> {
+        let discriminants = commands
+            .iter()
+            .fold(AbiEncoder::new(), |abi, command| abi.uint(command.discriminant() as u64));
+        let payloads = commands
+            .iter()
+            .fold(AbiEncoder::new(), |abi, command| abi.bytes(&command.encode()));
+
+        AbiEncoder::new()
+            .array(discriminants)
+            .array(payloads)
+            .finish()
+    }
+
+    pub fn decode(payload: &'a [u8]) -> Result<Self> {
+        let mut abi = AbiReader::new(payload, 2);
+        let commands = abi.uint_array(0, 1)?;
+        let payloads = abi.bytes_array(1)?;
+        abi.finish()?;
+
+        if commands.len() != payloads.len() {
+            return Err(crate::error::ErrorCode::InvalidLzMessage.into());
+        }
+        for index in 0..commands.len() {
+            match commands.u8_at(index)? {
+                MessageCodec::COMMAND_ENVELOPE => {
+                    return Err(crate::error::ErrorCode::NestedEnvelope.into());
+                }
+                MessageCodec::COMMAND_LEGACY => {
+                    return Err(crate::error::ErrorCode::UnsupportedCommand.into());
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self { commands, payloads })
+    }
+
+    /// Number of sub-commands in the envelope
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Discriminant of the sub-command at `index`
+    pub fn command_at(&self, index: usize) -> Result<u8> {
+        self.commands.u8_at(index)
+    }
+
+    /// Decode the sub-command at `index`
+    pub fn get(&self, index: usize) -> Result<Command<'a>> {
+        Command::decode(self.commands.u8_at(index)?, self.payloads.bytes_at(index)?)
+    }
+
+    /// Decode the sub-commands in order
+    pub fn iter(&self) -> impl Iterator<Item = Result<Command<'a>>> + '_ {
+        (0..self.len()).map(move |index| self.get(index))
+    }
+}
+
+/// Compose payload structure: the message to hand to `lz_compose`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComposePayload<'a> {
+    /// Selects the registered composer program that runs the message
+    pub compose_type: u8,
+    pub compose_msg: &'a [u8],
+}
+
+impl<'a> ComposePayload<'a> {
+    /// Payload format: abi.encode(uint8 composeType, bytes composeMsg)
+    pub fn encode(&self) -> Vec<u8> {
+        AbiEncoder::new()
+            .uint(self.compose_type as u64)
+            .bytes(self.compose_msg)
+            .finish()
+    }
+
+    pub fn decode(payload: &'a [u8]) -> Result<Self> {
+        let mut abi = AbiReader::new(payload, 2);
+        let compose_type = abi.u8(0)?;
+        let compose_msg = abi.bytes(1)?;
+        abi.finish()?;
+
+        Ok(Self { compose_type, compose_msg })
+    }
+}
+
+/// Compose envelope queued with `send_compose`, laid out like LayerZero's
+/// compose message codec (big-endian, packed):
+///
+/// `nonce (u64) | src_eid (u32) | amount_ld (u64) | compose_from ([u8; 32]) | compose_msg`
+///
+/// This OApp moves no tokens, so `amount_ld` is always zero when it composes.
+/// Its own `compose_msg` starts with the compose type, see `compose_type`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComposeMessage<'a> {
+    /// Nonce of the packet that triggered the compose
+    pub nonce: u64,
+    /// Source endpoint of that packet
+    pub src_eid: u32,
+    pub amount_ld: u64,
+    /// Peer that sent the packet
+    pub compose_from: [u8; 32],
+    pub compose_msg: &'a [u8],
+}
+
+impl<'a> ComposeMessage<'a> {
+    const NONCE_OFFSET: usize = 0;
+    const SRC_EID_OFFSET: usize = 8;
+    const AMOUNT_LD_OFFSET: usize = 12;
+    const COMPOSE_FROM_OFFSET: usize = 20;
+    const COMPOSE_MSG_OFFSET: usize = 52;
+
+    /// `compose_msg` for a compose payload: `compose_type (u8) | compose_msg`
+    pub fn typed_compose_msg(payload: &ComposePayload) -> Vec<u8> {
+        let mut compose_msg = Vec::with_capacity(1 + payload.compose_msg.len());
+        compose_msg.push(payload.compose_type);
+        compose_msg.extend_from_slice(payload.compose_msg);
+        compose_msg
+    }
+
+    /// Split `compose_msg` into the compose type and the composer's message
+    pub fn compose_type(&self) -> Result<(u8, &'a [u8])> {
+        self.compose_msg
+            .split_first()
+            .map(|(compose_type, message)| (*compose_type, message))
+            .ok_or(crate::error::ErrorCode::InvalidLzMessage.into())
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(Self::COMPOSE_MSG_OFFSET + self.compose_msg.len());
+        data.extend_from_slice(&self.nonce.to_be_bytes());
+        data.extend_from_slice(&self.src_eid.to_be_bytes());
+        data.extend_from_slice(&self.amount_ld.to_be_bytes());
+        data.extend_from_slice(&self.compose_from);
+        data.extend_from_slice(self.compose_msg);
+        data
+    }
+
+    pub fn decode(data: &'a [u8]) -> Result<Self> {
+        if data.len() < Self::COMPOSE_MSG_OFFSET {
+            return Err(crate::error::ErrorCode::InvalidLzMessage.into());
+        }
+
+        let field = |start: usize, end: usize| &data[start..end];
+        Ok(Self {
+            nonce: u64::from_be_bytes(field(Self::NONCE_OFFSET, Self::SRC_EID_OFFSET).try_into().unwrap()),
+            src_eid: u32::from_be_bytes(field(Self::SRC_EID_OFFSET, Self::AMOUNT_LD_OFFSET).try_into().unwrap()),
+            amount_ld: u64::from_be_bytes(field(Self::AMOUNT_LD_OFFSET, Self::COMPOSE_FROM_OFFSET).try_into().unwrap()),
+            compose_from: field(Self::COMPOSE_FROM_OFFSET, Self::COMPOSE_MSG_OFFSET).try_into().unwrap(),
+            compose_msg: &data[Self::COMPOSE_MSG_OFFSET..],
+        })
+    }
+}
+
+/// DAO signer attestation carried in the message extensions
+///
+/// Each signature is 65 bytes `r || s || v` over `DecodedMessage::attestation_hash`,
+/// as produced by `eth_sign`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attestation<'a> {
+    signatures: AbiArray<'a>,
+}
+
+impl<'a> Attestation<'a> {
+    pub const SIGNATURE_LEN: usize = 65;
+
+    /// Extensions format: abi.encode(bytes[] signatures)
+    pub fn encode_signatures(signatures: &[[u8; 65]]) -> Vec<u8> {
+        let signatures = signatures
+            .iter()
+            .fold(AbiEncoder::new(), |abi, signature| abi.bytes(signature));
+
+        AbiEncoder::new().array(signatures).finish()
+    }
+
+    pub fn decode(extensions: &'a [u8]) -> Result<Self> {
+        let mut abi = AbiReader::new(extensions, 1);
+        let signatures = abi.bytes_array(0)?;
+        abi.finish()?;
+
+        for index in 0..signatures.len() {
+            if signatures.bytes_at(index)?.len() != Self::SIGNATURE_LEN {
+                return Err(crate::error::ErrorCode::InvalidAttestation.into());
+            }
+        }
+
+        Ok(Self { signatures })
+    }
+
+    /// Number of signatures
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+
+    pub fn signature_at(&self, index: usize) -> Result<&'a [u8; 65]> {
+        self.signatures
+            .bytes_at(index)?
+            .try_into()
+            .map_err(|_| crate::error::ErrorCode::InvalidAttestation.into())
+    }
+}
+
+/// Size of a Solidity ABI word
+pub const ABI_WORD_SIZE: usize = 32;
+
+/// Reader for data produced by Solidity's `abi.encode`
+///
+/// Elements are addressed by their index in the encoded tuple. Every read
+/// checks the word padding and keeps dynamic offsets inside the buffer, so
+/// malformed input is rejected with `InvalidLzMessage` instead of panicking.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AbiDecoder<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> AbiDecoder<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Read a `uint8` element
+    pub fn read_u8(&self, index: usize) -> Result<u8> {
+        Ok(Self::uint_from_word(self.head(index)?, 1)? as u8)
+    }
+
+    /// Read a `uint32` element
+    pub fn read_u32(&self, index: usize) -> Result<u32> {
+        Ok(Self::uint_from_word(self.head(index)?, 4)? as u32)
+    }
+
+    /// Read a `uint64` element
+    pub fn read_u64(&self, index: usize) -> Result<u64> {
+        Self::uint_from_word(self.head(index)?, 8)
+    }
+
+    /// Read an `int64` element (sign-extended to 32 bytes)
+    pub fn read_i64(&self, index: usize) -> Result<i64> {
+        let word = self.head(index)?;
+        let mut value = [0u8; 8];
+        value.copy_from_slice(&word[ABI_WORD_SIZE - 8..]);
+        let value = i64::from_be_bytes(value);
+
+        let sign = if value < 0 { 0xFF } else { 0x00 };
+        if word[..ABI_WORD_SIZE - 8].iter().any(|b| *b != sign) {
+            return Err(crate::error::ErrorCode::InvalidLzMessage.into());
+        }
+
+        Ok(value)
+    }
+
+    /// Read a `bytes32` element
+    pub fn read_bytes32(&self, index: usize) -> Result<[u8; ABI_WORD_SIZE]> {
+        Ok(*self.head(index)?)
+    }
+
+    /// Read a dynamic `bytes` element
+    pub fn read_bytes(&self, index: usize) -> Result<&'a [u8]> {
+        let offset = self.tail_offset(index)?;
+        let len = Self::usize_from_word(self.word_at(offset)?)?;
+
+        let start = offset + ABI_WORD_SIZE;
+        let end = len
+            .div_ceil(ABI_WORD_SIZE)
+            .checked_mul(ABI_WORD_SIZE)
+            .and_then(|padded| start.checked_add(padded))
+            .ok_or(crate::error::ErrorCode::InvalidLzMessage)?;
+        let region = self
+            .data
+            .get(start..end)
+            .ok_or(crate::error::ErrorCode::InvalidLzMessage)?;
+
+        // Right padding must be zeroed
+        if region[len..].iter().any(|b| *b != 0) {
+            return Err(crate::error::ErrorCode::InvalidLzMessage.into());
+        }
+
+        Ok(&region[..len])
+    }
+
+    /// Read a dynamic `string` element
+    pub fn read_string(&self, index: usize) -> Result<&'a str> {
+        core::str::from_utf8(self.read_bytes(index)?)
+            .map_err(|_| crate::error::ErrorCode::InvalidLzMessage.into())
+    }
+
+    /// Head word of the `index`-th tuple element
+    fn head(&self, index: usize) -> Result<&'a [u8; ABI_WORD_SIZE]> {
+        let offset = index
+            .checked_mul(ABI_WORD_SIZE)
+            .ok_or(crate::error::ErrorCode::InvalidLzMessage)?;
+        self.word_at(offset)
+    }
+
+    /// Word starting at a byte offset
+    fn word_at(&self, offset: usize) -> Result<&'a [u8; ABI_WORD_SIZE]> {
+        offset
+            .checked_add(ABI_WORD_SIZE)
+            .and_then(|end| self.data.get(offset..end))
+            .and_then(|word| word.try_into().ok())
+            .ok_or_else(|| crate::error::ErrorCode::InvalidLzMessage.into())
+    }
+
+    /// Resolve the offset stored in a dynamic element's head word
+    fn tail_offset(&self, index: usize) -> Result<usize> {
+        let offset = Self::usize_from_word(self.head(index)?)?;
+        if offset % ABI_WORD_SIZE != 0 || offset >= self.data.len() {
+            return Err(crate::error::ErrorCode::InvalidLzMessage.into());
+        }
+        Ok(offset)
+    }
+
+    /// Unsigned integer held in the low `width` bytes of a word
+    fn uint_from_word(word: &[u8; ABI_WORD_SIZE], width: usize) -> Result<u64> {
+        let (padding, value) = word.split_at(ABI_WORD_SIZE - width);
+        if padding.iter().any(|b| *b != 0) {
+            return Err(crate::error::ErrorCode::InvalidLzMessage.into());
+        }
+
+        let mut buf = [0u8; 8];
+        buf[8 - width..].copy_from_slice(value);
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    fn usize_from_word(word: &[u8; ABI_WORD_SIZE]) -> Result<usize> {
+        usize::try_from(Self::uint_from_word(word, 8)?)
+            .map_err(|_| crate::error::ErrorCode::InvalidLzMessage.into())
+    }
+}
+
+/// Sequential reader accepting only the canonical `abi.encode` layout
+///
+/// Dynamic elements must be read in index order: each tail has to start
+/// where the previous one ended, and `finish` rejects trailing bytes. Input
+/// accepted here re-encodes to exactly the same bytes. Contents are validated
+/// in place, nothing is copied.
+pub struct AbiReader<'a> {
+    abi: AbiDecoder<'a>,
+    len: usize,
+    next_tail: usize,
+}
+
+impl<'a> AbiReader<'a> {
+    pub fn new(data: &'a [u8], head_words: usize) -> Self {
+        Self {
+            abi: AbiDecoder::new(data),
+            len: data.len(),
+            next_tail: head_words.saturating_mul(ABI_WORD_SIZE),
+        }
+    }
+
+    pub fn u8(&self, index: usize) -> Result<u8> {
+        self.abi.read_u8(index)
+    }
+
+    pub fn u32(&self, index: usize) -> Result<u32> {
+        self.abi.read_u32(index)
+    }
+
+    pub fn u64(&self, index: usize) -> Result<u64> {
+        self.abi.read_u64(index)
+    }
+
+    pub fn i64(&self, index: usize) -> Result<i64> {
+        self.abi.read_i64(index)
+    }
+
+    pub fn bytes32(&self, index: usize) -> Result<[u8; ABI_WORD_SIZE]> {
+        self.abi.read_bytes32(index)
+    }
+
+    /// Read a dynamic `bytes` element
+    pub fn bytes(&mut self, index: usize) -> Result<&'a [u8]> {
+        self.claim_tail(index)?;
+        let bytes = self.abi.read_bytes(index)?;
+        self.next_tail += ABI_WORD_SIZE + bytes.len().div_ceil(ABI_WORD_SIZE) * ABI_WORD_SIZE;
+        Ok(bytes)
+    }
+
+    /// Read a dynamic `string` element, deferring UTF-8 validation
+    pub fn string(&mut self, index: usize) -> Result<AbiStr<'a>> {
+        self.bytes(index).map(AbiStr)
+    }
+
+    /// Read an array of unsigned integers at most `width` bytes wide
+    pub fn uint_array(&mut self, index: usize, width: usize) -> Result<AbiArray<'a>> {
+        let offset = self.claim_tail(index)?;
+        let array = self.array_at(offset)?;
+
+        for i in 0..array.len {
+            AbiDecoder::uint_from_word(array.elements.head(i)?, width)?;
+        }
+
+        self.next_tail += ABI_WORD_SIZE + array.len * ABI_WORD_SIZE;
+        array.with_encoded_len(self.next_tail - offset)
+    }
+
+    /// Read an array of `bytes` or `string` elements
+    pub fn bytes_array(&mut self, index: usize) -> Result<AbiArray<'a>> {
+        let offset = self.claim_tail(index)?;
+        let array = self.array_at(offset)?;
+
+        let mut elements = AbiReader::new(array.elements.data, array.len);
+        for i in 0..array.len {
+            elements.bytes(i)?;
+        }
+
+        self.next_tail += ABI_WORD_SIZE + elements.next_tail;
+        array.with_encoded_len(self.next_tail - offset)
+    }
+
+    /// Check that the last tail ends exactly at the end of the input
+    pub fn finish(&self) -> Result<()> {
+        if self.next_tail != self.len {
+            return Err(crate::error::ErrorCode::InvalidLzMessage.into());
+        }
+        Ok(())
+    }
+
+    /// Require the next dynamic element to start where the previous ended
+    fn claim_tail(&self, index: usize) -> Result<usize> {
+        let offset = self.abi.tail_offset(index)?;
+        if offset != self.next_tail {
+            return Err(crate::error::ErrorCode::InvalidLzMessage.into());
+        }
+        Ok(offset)
+    }
+
+    fn array_at(&self, offset: usize) -> Result<AbiArray<'a>> {
+        let len = AbiDecoder::usize_from_word(self.abi.word_at(offset)?)?;
+        let elements = &self.abi.data[offset + ABI_WORD_SIZE..];
+
+        // Every element occupies at least one head word
+        if len > elements.len() / ABI_WORD_SIZE {
+            return Err(crate::error::ErrorCode::InvalidLzMessage.into());
+        }
+
+        Ok(AbiArray {
+            encoded: &self.abi.data[offset..],
+            elements: AbiDecoder::new(elements),
+            len,
+        })
+    }
+}
+
+/// View over an ABI `string`, validated as UTF-8 only when read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AbiStr<'a>(&'a [u8]);
+
+impl<'a> AbiStr<'a> {
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+
+    pub fn as_str(&self) -> Result<&'a str> {
+        core::str::from_utf8(self.0).map_err(|_| crate::error::ErrorCode::InvalidLzMessage.into())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<'a> From<&'a str> for AbiStr<'a> {
+    fn from(value: &'a str) -> Self {
+        Self(value.as_bytes())
+    }
+}
+
+/// View over an already validated ABI array; elements decode on access
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AbiArray<'a> {
+    /// Canonical encoding (length word and elements)
+    encoded: &'a [u8],
+    elements: AbiDecoder<'a>,
+    len: usize,
+}
+
+impl<'a> AbiArray<'a> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn u8_at(&self, index: usize) -> Result<u8> {
+        self.check_index(index)?;
+        self.elements.read_u8(index)
+    }
+
+    pub fn u32_at(&self, index: usize) -> Result<u32> {
+        self.check_index(index)?;
+        self.elements.read_u32(index)
+    }
+
+    pub fn bytes_at(&self, index: usize) -> Result<&'a [u8]> {
+        self.check_index(index)?;
+        self.elements.read_bytes(index)
+    }
+
+    pub fn str_at(&self, index: usize) -> Result<AbiStr<'a>> {
+        self.bytes_at(index).map(AbiStr)
+    }
+
+    /// Canonical encoding of the array, for re-encoding without copies
+    pub fn encoded(&self) -> &'a [u8] {
+        self.encoded
+    }
+
+    fn check_index(&self, index: usize) -> Result<()> {
+        if index >= self.len {
+            return Err(crate::error::ErrorCode::InvalidLzMessage.into());
+        }
+        Ok(())
+    }
+
+    fn with_encoded_len(mut self, encoded_len: usize) -> Result<Self> {
+        self.encoded = self
+            .encoded
+            .get(..encoded_len)
+            .ok_or(crate::error::ErrorCode::InvalidLzMessage)?;
+        Ok(self)
+    }
+}
+
+/// Writer producing the same layout as Solidity's `abi.encode`
+#[derive(Default)]
+pub struct AbiEncoder {
+    elements: Vec<AbiElement>,
+}
+
+enum AbiElement {
+    Static([u8; ABI_WORD_SIZE]),
+    Dynamic(Vec<u8>),
+}
+
+impl AbiEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an unsigned integer element (`uint8` .. `uint64`)
+    pub fn uint(mut self, value: u64) -> Self {
+        self.elements.push(AbiElement::Static(Self::uint_word(value)));
+        self
+    }
+
+    /// Append a signed integer element (`int64`)
+    pub fn int(mut self, value: i64) -> Self {
+        let mut word = if value < 0 { [0xFF; ABI_WORD_SIZE] } else { [0u8; ABI_WORD_SIZE] };
+        word[ABI_WORD_SIZE - 8..].copy_from_slice(&value.to_be_bytes());
+        self.elements.push(AbiElement::Static(word));
+        self
+    }
+
+    /// Append a `bytes32` element
+    pub fn bytes32(mut self, value: [u8; ABI_WORD_SIZE]) -> Self {
+        self.elements.push(AbiElement::Static(value));
+        self
+    }
+
+    /// Append a dynamic array whose elements were written to `elements`
+    pub fn array(mut self, elements: AbiEncoder) -> Self {
+        let mut tail = Self::uint_word(elements.elements.len() as u64).to_vec();
+        tail.extend_from_slice(&elements.finish());
+        self.elements.push(AbiElement::Dynamic(tail));
+        self
+    }
+
+    /// Append a dynamic element that is already ABI-encoded
+    pub fn encoded(mut self, tail: &[u8]) -> Self {
+        self.elements.push(AbiElement::Dynamic(tail.to_vec()));
+        self
+    }
+
+    /// Append a dynamic `bytes` element
+    pub fn bytes(mut self, value: &[u8]) -> Self {
+        let mut tail = Self::uint_word(value.len() as u64).to_vec();
+        tail.extend_from_slice(value);
+        tail.resize(ABI_WORD_SIZE + value.len().div_ceil(ABI_WORD_SIZE) * ABI_WORD_SIZE, 0);
+        self.elements.push(AbiElement::Dynamic(tail));
+        self
+    }
+
+    /// Append a dynamic `string` element
+    pub fn string(self, value: &str) -> Self {
+        self.bytes(value.as_bytes())
+    }
+
+    /// Lay out the head words followed by the dynamic tails
+    pub fn finish(self) -> Vec<u8> {
+        let head_len = self.elements.len() * ABI_WORD_SIZE;
+        let mut head = Vec::with_capacity(head_len);
+        let mut tail = Vec::new();
+
+        for element in self.elements {
+            match element {
+                AbiElement::Static(word) => head.extend_from_slice(&word),
+                AbiElement::Dynamic(data) => {
+                    head.extend_from_slice(&Self::uint_word((head_len + tail.len()) as u64));
+                    tail.extend_from_slice(&data);
+                }
+            }
+        }
+
+        head.extend_from_slice(&tail);
+        head
+    }
+
+    fn uint_word(value: u64) -> [u8; ABI_WORD_SIZE] {
+        let mut word = [0u8; ABI_WORD_SIZE];
+        word[ABI_WORD_SIZE - 8..].copy_from_slice(&value.to_be_bytes());
+        word
+    }
 }
 
 /// Message validation helper
@@ -200,7 +1331,7 @@ impl MessageValidator {
         diff <= 3600
     }
 
-    /// Validate message nonce (should be sequential)
+    /// Whether `message_nonce` is newer than `current_nonce`
     pub fn validate_nonce(current_nonce: u64, message_nonce: u64) -> bool {
         message_nonce > current_nonce
     }
@@ -210,4 +1341,562 @@ impl MessageValidator {
         // Max message size: 64KB
         data.len() <= 65536
     }
+
+    /// Verify that at least `threshold` distinct DAO signers signed `hash`
+    ///
+    /// Every signature must recover to a member of `signers`; unknown or
+    /// repeated signers invalidate the whole attestation.
+    pub fn verify_attestation(
+        hash: &[u8; 32],
+        attestation: &Attestation,
+        signers: &[[u8; 20]],
+        threshold: u8,
+    ) -> Result<()> {
+        let mut signed = vec![false; signers.len()];
+
+        for index in 0..attestation.len() {
+            let signer = Self::recover_signer(hash, attestation.signature_at(index)?)?;
+            let position = signers
+                .iter()
+                .position(|candidate| *candidate == signer)
+                .ok_or(crate::error::ErrorCode::InvalidAttestation)?;
+
+            if signed[position] {
+                return Err(crate::error::ErrorCode::InvalidAttestation.into());
+            }
+            signed[position] = true;
+        }
+
+        if attestation.len() < threshold as usize {
+            return Err(crate::error::ErrorCode::InsufficientAttestations.into());
+        }
+        Ok(())
+    }
+
+    /// Recover the Ethereum address that produced a `r || s || v` signature
+    pub fn recover_signer(hash: &[u8; 32], signature: &[u8; 65]) -> Result<[u8; 20]> {
+        // secp256k1 group order divided by two
+        const HALF_ORDER: [u8; 32] = [
+            0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+        ];
+
+        // Reject malleable high-s signatures, as Ethereum does
+        if signature[32..64] > HALF_ORDER[..] {
+            return Err(crate::error::ErrorCode::InvalidAttestation.into());
+        }
+
+        let recovery_id = match signature[64] {
+            0 | 1 => signature[64],
+            27 | 28 => signature[64] - 27,
+            _ => return Err(crate::error::ErrorCode::InvalidAttestation.into()),
+        };
+
+        let public_key = secp256k1_recover(hash, recovery_id, &signature[..64])
+            .map_err(|_| crate::error::ErrorCode::InvalidAttestation)?;
+        let public_key_hash = keccak::hash(&public_key.to_bytes()).to_bytes();
+
+        let mut address = [0u8; 20];
+        address.copy_from_slice(&public_key_hash[12..]);
+        Ok(address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    fn word(value: u64) -> Vec<u8> {
+        let mut word = vec![0u8; 32];
+        word[24..].copy_from_slice(&value.to_be_bytes());
+        word
+    }
+
+    /// Bytes of `abi.encode(uint8(0), uint64(7), int64(1_700_000_000), uint8(1), hex"c0ffee")`
+    fn dao_message() -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend(word(0)); // command
+        data.extend(word(7)); // nonce
+        data.extend(word(1_700_000_000)); // timestamp
+        data.extend(word(1)); // version
+        data.extend(word(0xa0)); // payload offset
+        data.extend(word(3)); // payload length
+        let mut payload = vec![0xc0, 0xff, 0xee];
+        payload.resize(32, 0);
+        data.extend(payload);
+        data
+    }
+
+    #[test]
+    fn test_decode_dao_message() {
+        let message = dao_message();
+        let decoded = MessageCodec::decode_message(&message).unwrap();
+        assert_eq!(decoded.version, 1);
+        assert_eq!(decoded.command, MessageCodec::COMMAND_UPDATE_COLLECTION_METADATA);
+        assert_eq!(decoded.nonce, 7);
+        assert_eq!(decoded.timestamp, 1_700_000_000);
+        assert_eq!(decoded.payload, [0xc0, 0xff, 0xee]);
+
+        let encoded = MessageCodec::encode_message(0, 7, 1_700_000_000, &[0xc0, 0xff, 0xee]).unwrap();
+        assert_eq!(encoded, dao_message());
+    }
+
+    #[test]
+    fn test_update_metadata_payload_round_trip() {
+        let payload = MessageCodec::encode_update_metadata_payload(
+            "https://example.com/collection.json",
+            "Golden Ticket",
+            "GOLD",
+        )
+        .unwrap();
+        // three offsets, then (length, padded data) for each string
+        assert_eq!(&payload[..32], word(0x60).as_slice());
+        assert_eq!(&payload[32..64], word(0xc0).as_slice());
+        assert_eq!(payload.len(), 3 * 32 + (32 + 64) + (32 + 32) + (32 + 32));
+
+        let decoded = MessageCodec::decode_update_metadata_payload(&payload).unwrap();
+        assert_eq!(decoded.uri.as_str().unwrap(), "https://example.com/collection.json");
+        assert_eq!(decoded.name.as_str().unwrap(), "Golden Ticket");
+        assert_eq!(decoded.symbol.as_str().unwrap(), "GOLD");
+    }
+
+    #[test]
+    fn test_negative_timestamp_is_sign_extended() {
+        let encoded = MessageCodec::encode_message(3, 1, -5, &[]).unwrap();
+        assert!(encoded[64..88].iter().all(|b| *b == 0xFF));
+        assert_eq!(MessageCodec::decode_message(&encoded).unwrap().timestamp, -5);
+    }
+
+    #[test]
+    fn test_rejects_malformed_messages() {
+        // dirty padding in the uint8 command word
+        let mut data = dao_message();
+        data[0] = 1;
+        assert_eq!(MessageCodec::decode_message(&data).unwrap_err(), ErrorCode::InvalidLzMessage.into());
+
+        // payload offset pointing past the end
+        let mut data = dao_message();
+        data[128..160].copy_from_slice(&word(0x1000));
+        assert_eq!(MessageCodec::decode_message(&data).unwrap_err(), ErrorCode::InvalidLzMessage.into());
+
+        // payload length running past the end
+        let mut data = dao_message();
+        data[160..192].copy_from_slice(&word(33));
+        assert_eq!(MessageCodec::decode_message(&data).unwrap_err(), ErrorCode::InvalidLzMessage.into());
+
+        // non-zero bytes in the payload padding
+        let mut data = dao_message();
+        data[200] = 1;
+        assert_eq!(MessageCodec::decode_message(&data).unwrap_err(), ErrorCode::InvalidLzMessage.into());
+
+        // truncated
+        let data = dao_message();
+        assert_eq!(MessageCodec::decode_message(&data[..150]).unwrap_err(), ErrorCode::InvalidLzMessage.into());
+    }
+
+    #[test]
+    fn test_rejects_unsupported_version() {
+        let mut data = dao_message();
+        data[127] = 9;
+        assert_eq!(MessageCodec::decode_message(&data).unwrap_err(), ErrorCode::UnsupportedVersion.into());
+    }
+
+    #[test]
+    fn test_rejects_invalid_utf8_string() {
+        let mut payload = MessageCodec::encode_update_metadata_payload("uri", "name", "sym").unwrap();
+        // first byte of the uri data
+        let uri_data = 3 * 32 + 32;
+        payload[uri_data] = 0xFF;
+
+        // the view is only checked when read
+        let decoded = MessageCodec::decode_update_metadata_payload(&payload).unwrap();
+        assert_eq!(decoded.uri.as_str().unwrap_err(), ErrorCode::InvalidLzMessage.into());
+        assert_eq!(decoded.name.as_str().unwrap(), "name");
+    }
+
+    #[test]
+    fn test_v2_round_trip() {
+        let encoded = MessageCodec::encode_message_v2(4, 9, 1_700_000_000, &[1, 2], &[3; 40]).unwrap();
+        assert_eq!(MessageCodec::peek_version(&encoded).unwrap(), MessageCodec::MESSAGE_VERSION_V2);
+
+        let decoded = MessageCodec::decode_message(&encoded).unwrap();
+        assert_eq!(decoded.version, MessageCodec::MESSAGE_VERSION_V2);
+        assert_eq!(decoded.command, 4);
+        assert_eq!(decoded.nonce, 9);
+        assert_eq!(decoded.payload, [1, 2]);
+        assert_eq!(decoded.extensions, [3; 40]);
+    }
+
+    #[test]
+    fn test_versions_decode_side_by_side() {
+        let v1 = MessageCodec::encode_message(0, 1, 0, &[7]).unwrap();
+        let v2 = MessageCodec::encode_message_v2(0, 1, 0, &[7], &[]).unwrap();
+
+        let v1 = MessageCodec::decode_message(&v1).unwrap();
+        let v2 = MessageCodec::decode_message(&v2).unwrap();
+        assert_eq!(v1.version, 1);
+        assert_eq!(v2.version, 2);
+        assert_eq!(v1.payload, v2.payload);
+        assert!(v1.extensions.is_empty() && v2.extensions.is_empty());
+
+        assert!(MessageCodec::is_supported_version(1));
+        assert!(MessageCodec::is_supported_version(2));
+        assert!(MessageCodec::is_supported_version(3));
+        assert!(!MessageCodec::is_supported_version(4));
+    }
+
+    fn sample_commands() -> Vec<(u8, Vec<u8>)> {
+        let commands = [
+            Command::UpdateCollectionMetadata(UpdateMetadataPayload {
+                uri: "ipfs://golden".into(),
+                name: "Golden Ticket".into(),
+                symbol: "GOLD".into(),
+            }),
+            Command::TransferAuthority(TransferAuthorityPayload {
+                new_authority: Pubkey::new_from_array([7; 32]),
+            }),
+            Command::EmergencyPause,
+            Command::EmergencyUnpause,
+            Command::SetMinVersion(SetMinVersionPayload { min_version: 2 }),
+            Command::Compose(ComposePayload { compose_type: 2, compose_msg: &[9; 40] }),
+            Command::Receipt(ReceiptPayload {
+                guid: [3; 32],
+                nonce: 42,
+                result_code: 6001,
+                merkle_root: [4; 32],
+                processed_messages: 43,
+            }),
+            Command::SetDelegate(SetDelegatePayload {
+                delegate: Pubkey::new_from_array([8; 32]),
+            }),
+        ];
+        let batches = [
+            BatchUpdateCnftsPayload::encode_updates(&[
+                CnftUpdate { leaf_index: 0, new_uri: "ipfs://0".into() },
+                CnftUpdate { leaf_index: 1_048_575, new_uri: "".into() },
+            ]),
+            BatchUpdateCnftsPayload::encode_updates(&[]),
+        ];
+
+        commands
+            .iter()
+            .map(|command| (command.discriminant(), command.encode()))
+            .chain(batches.into_iter().map(|payload| (MessageCodec::COMMAND_BATCH_UPDATE_CNFTS, payload)))
+            .collect()
+    }
+
+    #[test]
+    fn test_command_round_trip() {
+        for (discriminant, payload) in sample_commands() {
+            let decoded = Command::decode(discriminant, &payload).unwrap();
+            assert_eq!(decoded.discriminant(), discriminant);
+            assert_eq!(decoded.encode(), payload);
+        }
+    }
+
+    #[test]
+    fn test_batch_views_decode_lazily() {
+        let payload = BatchUpdateCnftsPayload::encode_updates(&[
+            CnftUpdate { leaf_index: 4, new_uri: "ipfs://4".into() },
+            CnftUpdate { leaf_index: 9, new_uri: "ipfs://9".into() },
+        ]);
+        let batch = BatchUpdateCnftsPayload::decode(&payload).unwrap();
+        assert_eq!(batch.len(), 2);
+
+        let second = batch.get(1).unwrap();
+        assert_eq!(second.leaf_index, 9);
+        assert_eq!(second.new_uri.as_str().unwrap(), "ipfs://9");
+        // the view points into the payload, nothing was copied
+        assert!(payload.as_ptr_range().contains(&second.new_uri.as_bytes().as_ptr()));
+
+        let leaves: Vec<u32> = batch.iter().map(|update| update.unwrap().leaf_index).collect();
+        assert_eq!(leaves, vec![4, 9]);
+        assert!(batch.get(2).is_err());
+    }
+
+    #[test]
+    fn test_decoded_message_borrows_input() {
+        let message = dao_message();
+        let decoded = MessageCodec::decode_message(&message).unwrap();
+        assert_eq!(decoded.payload.as_ptr(), message[192..].as_ptr());
+    }
+
+    #[test]
+    fn test_command_through_message() {
+        let command = Command::SetMinVersion(SetMinVersionPayload { min_version: 2 });
+        let message = MessageCodec::encode_message(command.discriminant(), 3, 0, &command.encode()).unwrap();
+        let decoded = MessageCodec::decode_message(&message).unwrap();
+        assert_eq!(decoded.decode_command().unwrap(), command);
+    }
+
+    #[test]
+    fn test_envelope_round_trip() {
+        let commands = [
+            Command::UpdateCollectionMetadata(UpdateMetadataPayload {
+                uri: "ipfs://theme".into(),
+                name: "Golden Ticket".into(),
+                symbol: "GOLD".into(),
+            }),
+            Command::EmergencyUnpause,
+            Command::SetMinVersion(SetMinVersionPayload { min_version: 2 }),
+        ];
+        let payload = EnvelopePayload::encode_commands(&commands);
+
+        let decoded = Command::decode(MessageCodec::COMMAND_ENVELOPE, &payload).unwrap();
+        assert_eq!(decoded.encode(), payload);
+
+        let Command::Envelope(envelope) = decoded else {
+            panic!("expected an envelope");
+        };
+        assert_eq!(envelope.len(), 3);
+        assert_eq!(envelope.command_at(1).unwrap(), MessageCodec::COMMAND_EMERGENCY_UNPAUSE);
+        let decoded: Vec<Command> = envelope.iter().map(|command| command.unwrap()).collect();
+        assert_eq!(decoded, commands);
+    }
+
+    #[test]
+    fn test_envelope_rejects_nesting_and_reports_bad_entries() {
+        let inner = EnvelopePayload::encode_commands(&[Command::EmergencyPause]);
+        let payload = AbiEncoder::new()
+            .array(AbiEncoder::new().uint(MessageCodec::COMMAND_ENVELOPE as u64))
+            .array(AbiEncoder::new().bytes(&inner))
+            .finish();
+        assert_eq!(
+            EnvelopePayload::decode(&payload).unwrap_err(),
+            ErrorCode::NestedEnvelope.into()
+        );
+
+        // a malformed sub-command only fails when it is reached
+        let payload = AbiEncoder::new()
+            .array(AbiEncoder::new().uint(3).uint(0xEE))
+            .array(AbiEncoder::new().bytes(&[]).bytes(&[]))
+            .finish();
+        let envelope = EnvelopePayload::decode(&payload).unwrap();
+        assert_eq!(envelope.get(0).unwrap(), Command::EmergencyPause);
+        assert_eq!(envelope.get(1).unwrap_err(), ErrorCode::UnknownCommand.into());
+    }
+
+    fn legacy_message(nonce: u64, command: MessageCommand) -> CrossChainMessage {
+        CrossChainMessage { command, payload: vec![], nonce, timestamp: 1_700_000_000, sender: [0x11; 20] }
+    }
+
+    #[test]
+    fn test_legacy_round_trip() {
+        let message = legacy_message(5, MessageCommand::SetPaused { paused: true });
+        let encoded = MessageCodec::encode_message_borsh(&message, &[]).unwrap();
+        assert_eq!(MessageCodec::peek_version(&encoded).unwrap(), MessageCodec::MESSAGE_VERSION_BORSH);
+
+        let decoded = MessageCodec::decode_message(&encoded).unwrap();
+        assert_eq!(decoded.command, MessageCodec::COMMAND_LEGACY);
+        assert_eq!((decoded.nonce, decoded.timestamp), (message.nonce, message.timestamp));
+
+        let Command::Legacy(payload) = decoded.decode_command().unwrap() else {
+            panic!("expected a legacy command");
+        };
+        let inner = payload.message().unwrap();
+        assert_eq!(inner.sender, message.sender);
+        assert!(matches!(inner.command, MessageCommand::SetPaused { paused: true }));
+
+        let transfer = legacy_message(6, MessageCommand::TransferAuthority { new_authority: Pubkey::default() });
+        let encoded = MessageCodec::encode_message_borsh(&transfer, &[]).unwrap();
+        let command = MessageCodec::decode_message(&encoded).unwrap().decode_command().unwrap();
+        assert!(command.requires_attestation().unwrap());
+    }
+
+    #[test]
+    fn test_legacy_only_in_borsh_version() {
+        // legacy payloads are not accepted in the ABI versions
+        let v1 = MessageCodec::encode_message(MessageCodec::COMMAND_LEGACY, 1, 0, &[]).unwrap();
+        let v1 = MessageCodec::decode_message(&v1).unwrap();
+        assert_eq!(v1.decode_command().unwrap_err(), ErrorCode::UnsupportedCommand.into());
+
+        // and v3 carries nothing but legacy payloads
+        let mut v3 = MessageCodec::encode_message_v3(1, 0, &[], &[]).unwrap();
+        v3[31] = MessageCodec::COMMAND_EMERGENCY_PAUSE;
+        assert_eq!(MessageCodec::decode_message(&v3).unwrap_err(), ErrorCode::UnsupportedCommand.into());
+
+        // nor can an envelope smuggle one in
+        let payload = AbiEncoder::new()
+            .array(AbiEncoder::new().uint(MessageCodec::COMMAND_LEGACY as u64))
+            .array(AbiEncoder::new().bytes(&[]))
+            .finish();
+        assert_eq!(EnvelopePayload::decode(&payload).unwrap_err(), ErrorCode::UnsupportedCommand.into());
+
+        // a v3 payload that is not Borsh only fails when read
+        let v3 = MessageCodec::encode_message_v3(1, 0, &[0xFF], &[]).unwrap();
+        let Command::Legacy(payload) = MessageCodec::decode_message(&v3).unwrap().decode_command().unwrap() else {
+            panic!("expected a legacy command");
+        };
+        assert_eq!(payload.message().unwrap_err(), ErrorCode::InvalidMessageFormat.into());
+    }
+
+    fn dao_signer(seed: u8) -> (libsecp256k1::SecretKey, [u8; 20]) {
+        let secret_key = libsecp256k1::SecretKey::parse(&[seed; 32]).unwrap();
+        let public_key = libsecp256k1::PublicKey::from_secret_key(&secret_key).serialize();
+        let mut address = [0u8; 20];
+        address.copy_from_slice(&keccak::hash(&public_key[1..]).to_bytes()[12..]);
+        (secret_key, address)
+    }
+
+    fn eth_sign(hash: &[u8; 32], secret_key: &libsecp256k1::SecretKey) -> [u8; 65] {
+        let (signature, recovery_id) = libsecp256k1::sign(&libsecp256k1::Message::parse(hash), secret_key);
+        let mut signed = [0u8; 65];
+        signed[..64].copy_from_slice(&signature.serialize());
+        signed[64] = 27 + recovery_id.serialize();
+        signed
+    }
+
+    #[test]
+    fn test_attestation_threshold() {
+        let signers: Vec<_> = (1..=3).map(dao_signer).collect();
+        let addresses: Vec<[u8; 20]> = signers.iter().map(|(_, address)| *address).collect();
+        let receiver = Pubkey::new_from_array([9; 32]);
+
+        let payload = TransferAuthorityPayload { new_authority: Pubkey::new_from_array([7; 32]) }.encode();
+        let unsigned = MessageCodec::encode_message_v2(MessageCodec::COMMAND_TRANSFER_AUTHORITY, 1, 0, &payload, &[]).unwrap();
+        let hash = MessageCodec::decode_message(&unsigned).unwrap().attestation_hash(&receiver);
+
+        let sign_with = |keys: &[usize]| {
+            let signatures: Vec<[u8; 65]> = keys.iter().map(|i| eth_sign(&hash, &signers[*i].0)).collect();
+            let extensions = Attestation::encode_signatures(&signatures);
+            MessageCodec::encode_message_v2(MessageCodec::COMMAND_TRANSFER_AUTHORITY, 1, 0, &payload, &extensions).unwrap()
+        };
+        let verify = |message: &[u8], receiver: &Pubkey| {
+            let decoded = MessageCodec::decode_message(message).unwrap();
+            let attestation = decoded.attestation().unwrap().unwrap();
+            MessageValidator::verify_attestation(&decoded.attestation_hash(receiver), &attestation, &addresses, 2)
+        };
+
+        assert!(verify(&sign_with(&[2, 0]), &receiver).is_ok());
+        assert_eq!(verify(&sign_with(&[1]), &receiver).unwrap_err(), ErrorCode::InsufficientAttestations.into());
+        assert_eq!(verify(&sign_with(&[1, 1]), &receiver).unwrap_err(), ErrorCode::InvalidAttestation.into());
+        // signatures are bound to the receiving store
+        let other = Pubkey::new_from_array([8; 32]);
+        assert_eq!(verify(&sign_with(&[0, 1]), &other).unwrap_err(), ErrorCode::InvalidAttestation.into());
+
+        // a signer outside the set
+        let (outsider, _) = dao_signer(4);
+        let signatures = [eth_sign(&hash, &signers[0].0), eth_sign(&hash, &outsider)];
+        let message = MessageCodec::encode_message_v2(
+            MessageCodec::COMMAND_TRANSFER_AUTHORITY, 1, 0, &payload, &Attestation::encode_signatures(&signatures),
+        )
+        .unwrap();
+        assert_eq!(verify(&message, &receiver).unwrap_err(), ErrorCode::InvalidAttestation.into());
+    }
+
+    #[test]
+    fn test_attestation_rejects_malleable_and_malformed_signatures() {
+        let (secret_key, address) = dao_signer(1);
+        let hash = [5u8; 32];
+        let signature = eth_sign(&hash, &secret_key);
+        assert_eq!(MessageValidator::recover_signer(&hash, &signature).unwrap(), address);
+
+        let mut high_s = signature;
+        high_s[32] = 0xff;
+        assert!(MessageValidator::recover_signer(&hash, &high_s).is_err());
+
+        let mut bad_v = signature;
+        bad_v[64] = 29;
+        assert!(MessageValidator::recover_signer(&hash, &bad_v).is_err());
+
+        let short = AbiEncoder::new().array(AbiEncoder::new().bytes(&[0; 64])).finish();
+        assert_eq!(Attestation::decode(&short).unwrap_err(), ErrorCode::InvalidAttestation.into());
+    }
+
+    #[test]
+    fn test_attested_commands() {
+        let transfer = Command::TransferAuthority(TransferAuthorityPayload { new_authority: Pubkey::default() });
+        assert!(transfer.requires_attestation().unwrap());
+        assert!(!Command::EmergencyPause.requires_attestation().unwrap());
+        let set_delegate = Command::SetDelegate(SetDelegatePayload { delegate: Pubkey::default() });
+        assert!(set_delegate.requires_attestation().unwrap());
+
+        let payload = EnvelopePayload::encode_commands(&[Command::EmergencyPause, transfer]);
+        let envelope = Command::decode(MessageCodec::COMMAND_ENVELOPE, &payload).unwrap();
+        assert!(envelope.requires_attestation().unwrap());
+    }
+
+    #[test]
+    fn test_compose_message_layout() {
+        let compose = ComposeMessage {
+            nonce: 0x0102030405060708,
+            src_eid: 30101,
+            amount_ld: 0,
+            compose_from: [0xAB; 32],
+            compose_msg: &[0xde, 0xad],
+        };
+        let encoded = compose.encode();
+
+        assert_eq!(encoded.len(), 52 + 2);
+        assert_eq!(encoded[..8], [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(encoded[8..12], 30101u32.to_be_bytes());
+        assert_eq!(encoded[12..20], [0; 8]);
+        assert_eq!(encoded[20..52], [0xAB; 32]);
+        assert_eq!(ComposeMessage::decode(&encoded).unwrap(), compose);
+
+        assert_eq!(ComposeMessage::decode(&encoded[..51]).unwrap_err(), ErrorCode::InvalidLzMessage.into());
+
+        // the compose type leads our own compose messages
+        let payload = ComposePayload { compose_type: 4, compose_msg: &[0xbe, 0xef] };
+        let compose_msg = ComposeMessage::typed_compose_msg(&payload);
+        let compose = ComposeMessage { compose_msg: &compose_msg, ..compose };
+        assert_eq!(compose.compose_type().unwrap(), (4, &[0xbe, 0xef][..]));
+
+        let empty = ComposeMessage { compose_msg: &[], ..compose };
+        assert_eq!(empty.compose_type().unwrap_err(), ErrorCode::InvalidLzMessage.into());
+    }
+
+    #[test]
+    fn test_message_type_from_command() {
+        let compose = ComposePayload { compose_type: 1, compose_msg: &[1, 2, 3] }.encode();
+        let message = MessageCodec::encode_message(MessageCodec::COMMAND_COMPOSE, 1, 0, &compose).unwrap();
+        assert_eq!(MessageCodec::get_message_type(&message).unwrap(), MessageCodec::MSG_TYPE_COMPOSE);
+
+        // a version or command byte of 0xFF no longer implies compose
+        let message = MessageCodec::encode_message(MessageCodec::COMMAND_EMERGENCY_PAUSE, 0xFF, 0, &[]).unwrap();
+        assert_eq!(MessageCodec::get_message_type(&message).unwrap(), MessageCodec::MSG_TYPE_REGULAR);
+        assert!(MessageCodec::get_message_type(&[0xFF, 0]).is_err());
+    }
+
+    #[test]
+    fn test_unknown_command() {
+        assert_eq!(Command::decode(0xEE, &[]).unwrap_err(), ErrorCode::UnknownCommand.into());
+    }
+
+    #[test]
+    fn test_rejects_non_canonical_payloads() {
+        // trailing bytes after a payload-less command
+        assert_eq!(
+            Command::decode(MessageCodec::COMMAND_EMERGENCY_PAUSE, &[0]).unwrap_err(),
+            ErrorCode::InvalidLzMessage.into()
+        );
+
+        // batch arrays of different lengths
+        let payload = AbiEncoder::new()
+            .array(AbiEncoder::new().uint(1).uint(2))
+            .array(AbiEncoder::new().string("only one"))
+            .finish();
+        assert_eq!(
+            Command::decode(MessageCodec::COMMAND_BATCH_UPDATE_CNFTS, &payload).unwrap_err(),
+            ErrorCode::InvalidLzMessage.into()
+        );
+
+        // leaf index wider than uint32
+        let payload = AbiEncoder::new()
+            .array(AbiEncoder::new().uint(u32::MAX as u64 + 1))
+            .array(AbiEncoder::new().string("uri"))
+            .finish();
+        assert!(Command::decode(MessageCodec::COMMAND_BATCH_UPDATE_CNFTS, &payload).is_err());
+
+        // strings laid out out of order
+        let mut payload = MessageCodec::encode_update_metadata_payload("a", "b", "c").unwrap();
+        let (first, second) = (payload[..32].to_vec(), payload[32..64].to_vec());
+        payload[..32].copy_from_slice(&second);
+        payload[32..64].copy_from_slice(&first);
+        assert_eq!(
+            Command::decode(MessageCodec::COMMAND_UPDATE_COLLECTION_METADATA, &payload).unwrap_err(),
+            ErrorCode::InvalidLzMessage.into()
+        );
+    }
 }
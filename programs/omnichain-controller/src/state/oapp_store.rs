@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use crate::instructions::lz_receive_types::LzAccount;
 
 /// LayerZero OApp Store - Main configuration PDA
 #[account]
@@ -15,10 +16,26 @@ pub struct OAppStore {
     pub collection_metadata: CollectionMetadata,
     /// DAO configuration
     pub dao_config: DaoConfig,
-    /// Nonce for message ordering
+    /// Nonce of the last processed DAO message
     pub nonce: u64,
     /// Replay protection
     pub processed_messages: u64,
+    /// Lowest wire-format version accepted from peers
+    pub min_message_version: u8,
+    /// Set by the DAO's emergency pause; stops sends, composes and every
+    /// inbound command but unpause. Admin configuration stays available so
+    /// the OApp can be repaired while paused.
+    pub paused: bool,
+    /// Admin proposed by the DAO, waiting to accept
+    pub pending_admin: Option<Pubkey>,
+    /// Unix timestamp after which the pending admin can no longer accept
+    pub pending_admin_deadline: i64,
+    /// Save commands that fail after validation instead of reverting
+    pub store_failed_messages: bool,
+    /// Send an execution receipt back to the peer after each command
+    pub send_receipts: bool,
+    /// Most native fee the fee vault pays per receipt
+    pub receipt_native_fee: u64,
 }
 
 impl OAppStore {
@@ -30,7 +47,14 @@ impl OAppStore {
         CollectionMetadata::LEN + // collection_metadata
         DaoConfig::LEN + // dao_config
         8 + // nonce
-        8; // processed_messages
+        8 + // processed_messages
+        1 + // min_message_version
+        1 + // paused
+        1 + 32 + // pending_admin
+        8 + // pending_admin_deadline
+        1 + // store_failed_messages
+        1 + // send_receipts
+        8; // receipt_native_fee
 
     pub const SEEDS: &'static [u8] = b"Store";
 
@@ -40,6 +64,14 @@ impl OAppStore {
             &crate::ID,
         )
     }
+
+    /// Fail if the OApp is paused
+    pub fn require_not_paused(&self) -> Result<()> {
+        if self.paused {
+            return Err(crate::error::ErrorCode::ControllerPaused.into());
+        }
+        Ok(())
+    }
 }
 
 /// Cross-chain peer configuration
@@ -53,6 +85,18 @@ pub struct PeerConfig {
     pub trusted: bool,
     /// Bump seed for PDA
     pub bump: u8,
+    /// How inbound nonces from this peer must arrive
+    pub delivery_mode: DeliveryMode,
+    /// Highest inbound nonce executed from this peer
+    pub inbound_nonce: u64,
+    /// Bit `i` is set if nonce `inbound_nonce - i` has been executed
+    pub nonce_bitmap: u64,
+    /// Nonce of the last message sent to this peer
+    pub outbound_nonce: u64,
+    /// Highest DAO message nonce executed from this peer
+    pub dao_nonce: u64,
+    /// Bit `i` is set if DAO nonce `dao_nonce - i` has been executed
+    pub dao_nonce_bitmap: u64,
 }
 
 impl PeerConfig {
@@ -60,16 +104,340 @@ impl PeerConfig {
         4 + // src_eid
         32 + // peer_address
         1 + // trusted
-        1; // bump
+        1 + // bump
+        1 + // delivery_mode
+        8 + // inbound_nonce
+        8 + // nonce_bitmap
+        8 + // outbound_nonce
+        8 + // dao_nonce
+        8; // dao_nonce_bitmap
 
     pub const SEEDS: &'static [u8] = b"Peer";
 
+    /// Nonces this far below `inbound_nonce` can no longer be executed
+    pub const NONCE_WINDOW: u64 = u64::BITS as u64;
+
     pub fn find_pda(store: &Pubkey, src_eid: u32) -> (Pubkey, u8) {
         Pubkey::find_program_address(
             &[Self::SEEDS, store.as_ref(), &src_eid.to_le_bytes()],
             &crate::ID,
         )
     }
+
+    /// Record an inbound nonce, failing if it cannot be executed
+    ///
+    /// Ordered peers must deliver the next nonce exactly. Unordered peers may
+    /// deliver any nonce not yet executed within the last `NONCE_WINDOW`.
+    pub fn accept_nonce(&mut self, nonce: u64) -> Result<()> {
+        let in_order = self.inbound_nonce.checked_add(1) == Some(nonce);
+        if self.delivery_mode == DeliveryMode::Ordered && !in_order {
+            return Err(crate::error::ErrorCode::InvalidNonce.into());
+        }
+        Self::mark_nonce(&mut self.inbound_nonce, &mut self.nonce_bitmap, nonce)
+    }
+    
+    /// Record the DAO nonce of an inbound message, failing if it was
+    /// already executed
+    ///
+    /// The DAO numbers messages when proposals are created and sends them as
+    /// they pass, so DAO nonces may skip or arrive out of order whatever the
+    /// delivery mode. Each is executed at most once within the last
+    /// `NONCE_WINDOW`, even when resent in a new packet.
+    pub fn accept_dao_nonce(&mut self, nonce: u64) -> Result<()> {
+        Self::mark_nonce(&mut self.dao_nonce, &mut self.dao_nonce_bitmap, nonce)
+    }
+    
+    /// Mark `nonce` as executed in a window ending at `highest`
+    fn mark_nonce(highest: &mut u64, bitmap: &mut u64, nonce: u64) -> Result<()> {
+        if crate::state::MessageValidator::validate_nonce(*highest, nonce) {
+            let shift = nonce - *highest;
+            *bitmap = if shift >= Self::NONCE_WINDOW {
+                0
+            } else {
+                *bitmap << shift
+            };
+            *bitmap |= 1;
+            *highest = nonce;
+            return Ok(());
+        }
+        
+        // Nonce 0 is never sent, and older nonces fall outside the window
+        let offset = *highest - nonce;
+        if nonce == 0 || offset >= Self::NONCE_WINDOW || *bitmap & (1 << offset) != 0 {
+            return Err(crate::error::ErrorCode::InvalidNonce.into());
+        }
+        *bitmap |= 1 << offset;
+        Ok(())
+    }
+}
+
+/// System-owned PDA holding the native fee during `lz_send`
+///
+/// It has no data; whatever the endpoint does not charge is refunded.
+pub struct FeeVault;
+
+impl FeeVault {
+    pub const SEEDS: &'static [u8] = b"FeeVault";
+
+    pub fn find_pda(store: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[Self::SEEDS, store.as_ref()],
+            &crate::ID,
+        )
+    }
+}
+
+/// A validated message whose command failed, kept for retry
+#[account]
+pub struct FailedMessage {
+    /// OApp store reference
+    pub store: Pubkey,
+    /// LayerZero message guid
+    pub guid: [u8; 32],
+    /// Source endpoint ID
+    pub src_eid: u32,
+    /// Peer that sent the message
+    pub sender: [u8; 32],
+    /// DAO message nonce
+    pub nonce: u64,
+    /// Nonce of the packet that delivered the message
+    pub packet_nonce: u64,
+    /// Error code of the failed command
+    pub error_code: u32,
+    /// Account refunded when the message is retried or discarded
+    pub payer: Pubkey,
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Raw message as delivered to lz_receive
+    pub message: Vec<u8>,
+}
+
+impl FailedMessage {
+    pub const SEEDS: &'static [u8] = b"FailedMessage";
+
+    pub fn space(message_len: usize) -> usize {
+        8 + // discriminator
+        32 + // store
+        32 + // guid
+        4 + // src_eid
+        32 + // sender
+        8 + // nonce
+        8 + // packet_nonce
+        4 + // error_code
+        32 + // payer
+        1 + // bump
+        4 + message_len // message
+    }
+
+    pub fn find_pda(store: &Pubkey, guid: &[u8; 32]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[Self::SEEDS, store.as_ref(), guid],
+            &crate::ID,
+        )
+    }
+}
+
+/// Composer program the admin registered for a compose type
+#[account]
+pub struct ComposerConfig {
+    /// OApp store reference
+    pub store: Pubkey,
+    /// Compose type routed to the composer
+    pub compose_type: u8,
+    /// Program called by lz_compose
+    pub composer: Pubkey,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl ComposerConfig {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // store
+        1 + // compose_type
+        32 + // composer
+        1; // bump
+
+    pub const SEEDS: &'static [u8] = b"Composer";
+
+    pub fn find_pda(store: &Pubkey, compose_type: u8) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[Self::SEEDS, store.as_ref(), &[compose_type]],
+            &crate::ID,
+        )
+    }
+}
+
+/// Signer of the calls lz_compose makes to composers
+///
+/// Holds no data. Composers check this PDA rather than the store, whose
+/// signature would also let them act as the OApp on the endpoint.
+pub struct ComposeAuthority;
+
+impl ComposeAuthority {
+    pub const SEEDS: &'static [u8] = b"compose";
+
+    pub fn find_pda(store: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[Self::SEEDS, store.as_ref()],
+            &crate::ID,
+        )
+    }
+}
+
+/// Record of an executed compose message
+///
+/// Its existence marks the (guid, index) pair as executed.
+#[account]
+pub struct ComposeReceipt {
+    /// OApp store reference
+    pub store: Pubkey,
+    /// LayerZero message guid
+    pub guid: [u8; 32],
+    /// Compose index within the message
+    pub index: u16,
+    /// Compose type the message was routed by
+    pub compose_type: u8,
+    /// Program that executed the compose
+    pub composer: Pubkey,
+    /// Return data of the composer, if any
+    pub result: Vec<u8>,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl ComposeReceipt {
+    /// Longest composer return data a receipt records
+    pub const MAX_RESULT_LEN: usize = 256;
+
+    pub const LEN: usize = 8 + // discriminator
+        32 + // store
+        32 + // guid
+        2 + // index
+        1 + // compose_type
+        32 + // composer
+        4 + Self::MAX_RESULT_LEN + // result
+        1; // bump
+
+    pub const SEEDS: &'static [u8] = b"ComposeReceipt";
+
+    pub fn find_pda(store: &Pubkey, guid: &[u8; 32], index: u16) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[Self::SEEDS, store.as_ref(), guid, &index.to_le_bytes()],
+            &crate::ID,
+        )
+    }
+}
+
+/// Endpoint send accounts of the receipts sent to a peer
+///
+/// What the endpoint and its send library need depends on the library
+/// configured for the path, so the admin lists the accounts here and
+/// `lz_receive_types` hands them to the executor.
+#[account]
+pub struct ReceiptConfig {
+    /// OApp store reference
+    pub store: Pubkey,
+    /// Endpoint ID of the peer
+    pub dst_eid: u32,
+    /// Accounts following the OApp and the fee vault in the endpoint send
+    pub send_accounts: Vec<LzAccount>,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl ReceiptConfig {
+    /// Most send accounts a receipt config holds
+    pub const MAX_SEND_ACCOUNTS: usize = 32;
+
+    pub const LEN: usize = 8 + // discriminator
+        32 + // store
+        4 + // dst_eid
+        4 + Self::MAX_SEND_ACCOUNTS * (32 + 1 + 1) + // send_accounts
+        1; // bump
+
+    pub const SEEDS: &'static [u8] = b"ReceiptConfig";
+
+    pub fn find_pda(store: &Pubkey, dst_eid: u32) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[Self::SEEDS, store.as_ref(), &dst_eid.to_le_bytes()],
+            &crate::ID,
+        )
+    }
+}
+
+/// Options every message of a type to a destination is sent with
+///
+/// Merged with the caller's options by `combine`, as LayerZero's
+/// `OAppOptionsType3` does.
+#[account]
+pub struct EnforcedOptions {
+    /// OApp store reference
+    pub store: Pubkey,
+    /// Destination endpoint ID
+    pub dst_eid: u32,
+    /// Command of the messages the options apply to
+    pub msg_type: u8,
+    /// Type 3 options
+    pub options: Vec<u8>,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl EnforcedOptions {
+    /// Longest enforced options a destination can have
+    pub const MAX_OPTIONS_LEN: usize = 256;
+
+    pub const LEN: usize = 8 + // discriminator
+        32 + // store
+        4 + // dst_eid
+        1 + // msg_type
+        4 + Self::MAX_OPTIONS_LEN + // options
+        1; // bump
+
+    pub const SEEDS: &'static [u8] = b"EnforcedOptions";
+
+    /// Type of options made of executor, DVN and other worker options
+    pub const OPTIONS_TYPE_3: u16 = 3;
+
+    pub fn find_pda(store: &Pubkey, dst_eid: u32, msg_type: u8) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[Self::SEEDS, store.as_ref(), &dst_eid.to_le_bytes(), &[msg_type]],
+            &crate::ID,
+        )
+    }
+
+    /// Fail unless `options` start with the type 3 header
+    pub fn assert_type_3(options: &[u8]) -> Result<()> {
+        match options {
+            [high, low, ..] if u16::from_be_bytes([*high, *low]) == Self::OPTIONS_TYPE_3 => Ok(()),
+            _ => Err(crate::error::ErrorCode::InvalidOptions.into()),
+        }
+    }
+
+    /// Merge enforced options with the caller's
+    ///
+    /// Either side alone is used as is. Otherwise the caller's options must
+    /// be type 3 and their worker options are appended to the enforced ones,
+    /// dropping their header.
+    pub fn combine(enforced: &[u8], extra: &[u8]) -> Result<Vec<u8>> {
+        if enforced.is_empty() {
+            return Ok(extra.to_vec());
+        }
+        if extra.is_empty() {
+            return Ok(enforced.to_vec());
+        }
+        Self::assert_type_3(extra)?;
+        Ok([enforced, &extra[2..]].concat())
+    }
+}
+
+/// Inbound nonce policy for a peer
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeliveryMode {
+    /// Nonces execute strictly in sequence
+    Ordered,
+    /// Nonces execute in any order, once each
+    Unordered,
 }
 
 /// LayerZero receive types configuration
@@ -113,11 +481,14 @@ pub struct LzComposeTypes {
 impl LzComposeTypes {
     pub const LEN: usize = 8 + // discriminator
         32 + // store
-        4 + 32 + // compose_types (Vec<u8> with max 32 types)
+        4 + Self::MAX_COMPOSE_TYPES + // compose_types
         1; // bump
 
     pub const SEEDS: &'static [u8] = b"LzComposeTypes";
 
+    /// Most compose types that can be registered
+    pub const MAX_COMPOSE_TYPES: usize = 32;
+
     pub fn find_pda(store: &Pubkey) -> (Pubkey, u8) {
         Pubkey::find_program_address(
             &[Self::SEEDS, store.as_ref()],
@@ -164,14 +535,22 @@ pub struct DaoConfig {
     pub voting_period: u64,
     /// Quorum percentage (0-100)
     pub quorum: u8,
+    /// Ethereum addresses allowed to attest messages
+    pub signers: Vec<[u8; 20]>,
+    /// Attestations required for attested commands (0 disables the check)
+    pub signer_threshold: u8,
 }
 
 impl DaoConfig {
+    pub const MAX_SIGNERS: usize = 10;
+
     pub const LEN: usize = 
         20 + // authorized_dao
         4 + // ethereum_eid
         8 + // voting_period
-        1; // quorum
+        1 + // quorum
+        4 + 20 * Self::MAX_SIGNERS + // signers
+        1; // signer_threshold
 }
 
 /// Tree configuration for cNFT collection
@@ -197,3 +576,125 @@ impl TreeConfig {
         32 + // tree_delegate
         32; // merkle_tree
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(delivery_mode: DeliveryMode) -> PeerConfig {
+        PeerConfig {
+            src_eid: 30101,
+            peer_address: [1; 32],
+            trusted: true,
+            bump: 255,
+            delivery_mode,
+            inbound_nonce: 0,
+            nonce_bitmap: 0,
+            outbound_nonce: 0,
+            dao_nonce: 0,
+            dao_nonce_bitmap: 0,
+        }
+    }
+
+    fn accepts(peer: &mut PeerConfig, nonce: u64) -> bool {
+        peer.accept_nonce(nonce).is_ok()
+    }
+
+    #[test]
+    fn test_ordered_delivery() {
+        let mut peer = peer(DeliveryMode::Ordered);
+
+        assert!(!accepts(&mut peer, 0));
+        assert!(!accepts(&mut peer, 2), "skipped nonce");
+        assert!(accepts(&mut peer, 1));
+        assert!(!accepts(&mut peer, 1), "replayed nonce");
+        assert!(accepts(&mut peer, 2));
+        assert_eq!(peer.inbound_nonce, 2);
+    }
+
+    #[test]
+    fn test_unordered_delivery() {
+        let mut peer = peer(DeliveryMode::Unordered);
+
+        assert!(!accepts(&mut peer, 0));
+        assert!(accepts(&mut peer, 3));
+        assert!(accepts(&mut peer, 1));
+        assert!(!accepts(&mut peer, 1), "replayed nonce");
+        assert!(!accepts(&mut peer, 3), "replayed highest nonce");
+        assert!(accepts(&mut peer, 2));
+        assert!(accepts(&mut peer, 4));
+        assert_eq!(peer.inbound_nonce, 4);
+        assert_eq!(peer.nonce_bitmap, 0b1111);
+    }
+
+    #[test]
+    fn test_unordered_window() {
+        let mut peer = peer(DeliveryMode::Unordered);
+
+        assert!(accepts(&mut peer, 100));
+        assert!(accepts(&mut peer, 100 - PeerConfig::NONCE_WINDOW + 1));
+        assert!(!accepts(&mut peer, 100 - PeerConfig::NONCE_WINDOW), "outside the window");
+
+        // A jump past the window forgets everything older
+        assert!(accepts(&mut peer, 1_000));
+        assert_eq!(peer.nonce_bitmap, 1);
+        assert!(!accepts(&mut peer, 100));
+    }
+
+    #[test]
+    fn test_switching_to_ordered_continues_from_highest_nonce() {
+        let mut peer = peer(DeliveryMode::Unordered);
+        assert!(accepts(&mut peer, 5));
+
+        peer.delivery_mode = DeliveryMode::Ordered;
+        assert!(!accepts(&mut peer, 4));
+        assert!(accepts(&mut peer, 6));
+    }
+
+    #[test]
+    fn test_dao_nonce_is_executed_once() {
+        for delivery_mode in [DeliveryMode::Ordered, DeliveryMode::Unordered] {
+            let mut peer = peer(delivery_mode);
+            assert!(accepts(&mut peer, 1));
+            assert!(peer.accept_dao_nonce(5).is_ok());
+
+            // The same DAO message resent in a new packet
+            assert!(accepts(&mut peer, 2));
+            assert!(peer.accept_dao_nonce(5).is_err(), "replayed DAO nonce");
+
+            // Proposals pass in any order
+            assert!(peer.accept_dao_nonce(3).is_ok());
+            assert!(peer.accept_dao_nonce(9).is_ok());
+            assert!(peer.accept_dao_nonce(0).is_err());
+            assert_eq!(peer.dao_nonce, 9);
+        }
+    }
+
+    #[test]
+    fn test_combine_options() {
+        // Executor lzReceive option: worker 1, 17 bytes, gas 200k
+        let enforced = [&[0, 3, 1, 0, 17, 1][..], &[0; 13], &[3, 13, 64]].concat();
+        let extra = [0, 3, 1, 0, 1, 4];
+
+        assert_eq!(EnforcedOptions::combine(&enforced, &[]).unwrap(), enforced);
+        assert_eq!(EnforcedOptions::combine(&[], &extra).unwrap(), extra);
+        assert_eq!(
+            EnforcedOptions::combine(&enforced, &extra).unwrap(),
+            [&enforced[..], &extra[2..]].concat()
+        );
+        // A bare header adds nothing
+        assert_eq!(EnforcedOptions::combine(&enforced, &[0, 3]).unwrap(), enforced);
+    }
+
+    #[test]
+    fn test_combine_rejects_other_option_types() {
+        let enforced = [0, 3, 1, 0, 1, 4];
+
+        for extra in [&[0, 1, 0][..], &[0, 2], &[3]] {
+            let error = EnforcedOptions::combine(&enforced, extra).err().unwrap();
+            assert_eq!(error, crate::error::ErrorCode::InvalidOptions.into());
+        }
+        assert!(EnforcedOptions::assert_type_3(&[0, 3]).is_ok());
+        assert!(EnforcedOptions::assert_type_3(&[]).is_err());
+    }
+}
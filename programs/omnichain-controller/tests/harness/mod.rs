@@ -0,0 +1,141 @@
+//! Decoder checks shared by the property tests and the cargo-fuzz targets.
+//!
+//! Each check feeds arbitrary bytes to one decoder. Rejection is fine; a
+//! panic is not, and anything accepted must re-encode to exactly the input.
+
+#![allow(dead_code)]
+
+use omnichain_controller::state::msg_codec::{
+    Attestation, BatchUpdateCnftsPayload, Command, ComposeMessage, ComposePayload, EnvelopePayload,
+    MessageCodec, UpdateMetadataPayload,
+};
+
+pub fn decode_message(data: &[u8]) {
+    let Ok(decoded) = MessageCodec::decode_message(data) else {
+        return;
+    };
+
+    let encoded = match decoded.version {
+        MessageCodec::MESSAGE_VERSION_V1 => {
+            assert!(decoded.extensions.is_empty());
+            MessageCodec::encode_message(decoded.command, decoded.nonce, decoded.timestamp, decoded.payload)
+        }
+        MessageCodec::MESSAGE_VERSION_V2 => MessageCodec::encode_message_v2(
+            decoded.command,
+            decoded.nonce,
+            decoded.timestamp,
+            decoded.payload,
+            decoded.extensions,
+        ),
+        MessageCodec::MESSAGE_VERSION_BORSH => {
+            MessageCodec::encode_message_v3(decoded.nonce, decoded.timestamp, decoded.payload, decoded.extensions)
+        }
+        version => panic!("decoded unregistered version {version}"),
+    };
+    assert_eq!(encoded.unwrap(), data);
+
+    decode_command(decoded.command, decoded.payload);
+    decode_attestation(decoded.extensions);
+}
+
+pub fn decode_command(command: u8, payload: &[u8]) {
+    let Ok(decoded) = Command::decode(command, payload) else {
+        return;
+    };
+
+    assert_eq!(decoded.discriminant(), command);
+    assert_eq!(decoded.encode(), payload);
+    walk_command(&decoded);
+}
+
+pub fn decode_update_metadata_payload(payload: &[u8]) {
+    let Ok(decoded) = MessageCodec::decode_update_metadata_payload(payload) else {
+        return;
+    };
+
+    assert_eq!(decoded.encode(), payload);
+    walk_metadata(&decoded);
+}
+
+pub fn decode_batch_update_cnfts(payload: &[u8]) {
+    let Ok(decoded) = BatchUpdateCnftsPayload::decode(payload) else {
+        return;
+    };
+
+    assert_eq!(decoded.encode(), payload);
+    walk_batch(&decoded);
+}
+
+pub fn decode_envelope(payload: &[u8]) {
+    let Ok(decoded) = EnvelopePayload::decode(payload) else {
+        return;
+    };
+
+    assert_eq!(decoded.encode(), payload);
+    walk_envelope(&decoded);
+}
+
+pub fn decode_attestation(extensions: &[u8]) {
+    let Ok(decoded) = Attestation::decode(extensions) else {
+        return;
+    };
+
+    let signatures: Vec<[u8; 65]> = (0..decoded.len())
+        .map(|index| *decoded.signature_at(index).unwrap())
+        .collect();
+    assert_eq!(Attestation::encode_signatures(&signatures), extensions);
+}
+
+pub fn decode_compose_message(data: &[u8]) {
+    let Ok(decoded) = ComposeMessage::decode(data) else {
+        return;
+    };
+
+    assert_eq!(decoded.encode(), data);
+
+    if let Ok((compose_type, compose_msg)) = decoded.compose_type() {
+        let payload = ComposePayload { compose_type, compose_msg };
+        assert_eq!(ComposeMessage::typed_compose_msg(&payload), decoded.compose_msg);
+    }
+}
+
+/// Touch every lazy view; element access on validated views must not fail
+/// except for UTF-8 checks.
+fn walk_command(command: &Command) {
+    match command {
+        Command::UpdateCollectionMetadata(payload) => walk_metadata(payload),
+        Command::BatchUpdateCnfts(payload) => walk_batch(payload),
+        Command::Envelope(payload) => walk_envelope(payload),
+        Command::Legacy(payload) => {
+            let _ = payload.message();
+        }
+        Command::TransferAuthority(_)
+        | Command::EmergencyPause
+        | Command::EmergencyUnpause
+        | Command::SetMinVersion(_)
+        | Command::Compose(_)
+        | Command::Receipt(_)
+        | Command::SetDelegate(_) => {}
+    }
+}
+
+fn walk_metadata(payload: &UpdateMetadataPayload) {
+    for field in [&payload.uri, &payload.name, &payload.symbol] {
+        let _ = field.as_str();
+    }
+}
+
+fn walk_batch(payload: &BatchUpdateCnftsPayload) {
+    for update in payload.iter() {
+        let _ = update.unwrap().new_uri.as_str();
+    }
+}
+
+fn walk_envelope(payload: &EnvelopePayload) {
+    for index in 0..payload.len() {
+        assert_ne!(payload.command_at(index).unwrap(), MessageCodec::COMMAND_ENVELOPE);
+        if let Ok(command) = payload.get(index) {
+            walk_command(&command);
+        }
+    }
+}
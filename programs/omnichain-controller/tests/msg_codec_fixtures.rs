@@ -0,0 +1,192 @@
+//! Golden vectors shared with the DAO's Hardhat tests (`fixtures/messages`).
+
+use anchor_lang::error::Error;
+use omnichain_controller::state::msg_codec::{Command, MessageCodec};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../fixtures/messages");
+
+/// Keys describing the vector rather than its decoded contents
+const METADATA_KEYS: &[&str] = &["description", "valid", "dao"];
+
+struct Fixture {
+    name: String,
+    data: Vec<u8>,
+    expected: Value,
+}
+
+fn fixtures() -> Vec<Fixture> {
+    let mut names: Vec<String> = fs::read_dir(FIXTURES)
+        .expect("fixtures directory")
+        .filter_map(|entry| {
+            let path = entry.unwrap().path();
+            (path.extension()? == "json").then(|| path.file_stem()?.to_str().map(str::to_string))?
+        })
+        .collect();
+    names.sort();
+
+    names
+        .into_iter()
+        .map(|name| {
+            let path = Path::new(FIXTURES).join(&name);
+            let data = fs::read_to_string(path.with_extension("hex")).unwrap();
+            let expected = fs::read_to_string(path.with_extension("json")).unwrap();
+            Fixture {
+                data: hex::decode(data.trim()).unwrap(),
+                expected: serde_json::from_str(&expected).unwrap(),
+                name,
+            }
+        })
+        .collect()
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn error_name(error: &Error) -> String {
+    match error {
+        Error::AnchorError(error) => error.error_name.clone(),
+        Error::ProgramError(error) => error.program_error.to_string(),
+    }
+}
+
+/// Fully decode a message, reading every lazy view, into the fixture layout
+fn decode(data: &[u8]) -> anchor_lang::Result<Value> {
+    let decoded = MessageCodec::decode_message(data)?;
+    let command = command_json(&decoded.decode_command()?)?;
+    let attestation = match decoded.attestation()? {
+        Some(attestation) => {
+            let signatures = (0..attestation.len())
+                .map(|index| Ok(hex_string(attestation.signature_at(index)?)))
+                .collect::<anchor_lang::Result<Vec<_>>>()?;
+            json!(signatures)
+        }
+        None => Value::Null,
+    };
+
+    Ok(json!({
+        "version": decoded.version,
+        "command": decoded.command,
+        "nonce": decoded.nonce.to_string(),
+        "timestamp": decoded.timestamp.to_string(),
+        "payload": hex_string(decoded.payload),
+        "extensions": hex_string(decoded.extensions),
+        "decoded": command,
+        "attestation": attestation,
+    }))
+}
+
+fn command_json(command: &Command) -> anchor_lang::Result<Value> {
+    Ok(match command {
+        Command::UpdateCollectionMetadata(payload) => json!({
+            "type": "UpdateCollectionMetadata",
+            "uri": payload.uri.as_str()?,
+            "name": payload.name.as_str()?,
+            "symbol": payload.symbol.as_str()?,
+        }),
+        Command::BatchUpdateCnfts(payload) => {
+            let updates = payload
+                .iter()
+                .map(|update| {
+                    let update = update?;
+                    Ok(json!({ "leafIndex": update.leaf_index, "newUri": update.new_uri.as_str()? }))
+                })
+                .collect::<anchor_lang::Result<Vec<_>>>()?;
+            json!({ "type": "BatchUpdateCnfts", "updates": updates })
+        }
+        Command::TransferAuthority(payload) => json!({
+            "type": "TransferAuthority",
+            "newAuthority": hex_string(&payload.new_authority.to_bytes()),
+        }),
+        Command::EmergencyPause => json!({ "type": "EmergencyPause" }),
+        Command::EmergencyUnpause => json!({ "type": "EmergencyUnpause" }),
+        Command::SetMinVersion(payload) => json!({
+            "type": "SetMinVersion",
+            "minVersion": payload.min_version,
+        }),
+        Command::Compose(payload) => json!({
+            "type": "Compose",
+            "composeType": payload.compose_type,
+            "composeMsg": hex_string(payload.compose_msg),
+        }),
+        Command::Receipt(payload) => json!({
+            "type": "Receipt",
+            "guid": hex_string(&payload.guid),
+            "nonce": payload.nonce.to_string(),
+            "resultCode": payload.result_code,
+            "merkleRoot": hex_string(&payload.merkle_root),
+            "processedMessages": payload.processed_messages.to_string(),
+        }),
+        Command::SetDelegate(payload) => json!({
+            "type": "SetDelegate",
+            "delegate": hex_string(&payload.delegate.to_bytes()),
+        }),
+        Command::Legacy(payload) => json!({
+            "type": "Legacy",
+            "message": hex_string(payload.message),
+        }),
+        Command::Envelope(payload) => {
+            let commands = payload
+                .iter()
+                .map(|command| command_json(&command?))
+                .collect::<anchor_lang::Result<Vec<_>>>()?;
+            json!({ "type": "Envelope", "commands": commands })
+        }
+    })
+}
+
+#[test]
+fn test_golden_vectors() {
+    let fixtures = fixtures();
+    assert!(!fixtures.is_empty(), "no fixtures found in {FIXTURES}");
+
+    for Fixture { name, data, expected } in fixtures {
+        let result = decode(&data);
+
+        if expected["valid"] == Value::Bool(false) {
+            let error = result.expect_err(&name);
+            assert_eq!(error_name(&error), expected["error"].as_str().unwrap(), "{name}");
+            continue;
+        }
+
+        let mut expected = expected;
+        for key in METADATA_KEYS {
+            expected.as_object_mut().unwrap().remove(*key);
+        }
+        let actual = result.unwrap_or_else(|error| panic!("{name}: {error}"));
+        assert_eq!(actual, expected, "{name}");
+    }
+}
+
+#[test]
+fn test_golden_vectors_re_encode() {
+    for Fixture { name, data, expected } in fixtures() {
+        if expected["valid"] == Value::Bool(false) {
+            continue;
+        }
+
+        let decoded = MessageCodec::decode_message(&data).unwrap();
+        let encoded = match decoded.version {
+            MessageCodec::MESSAGE_VERSION_V1 => {
+                MessageCodec::encode_message(decoded.command, decoded.nonce, decoded.timestamp, decoded.payload)
+            }
+            MessageCodec::MESSAGE_VERSION_BORSH => {
+                MessageCodec::encode_message_v3(decoded.nonce, decoded.timestamp, decoded.payload, decoded.extensions)
+            }
+            _ => MessageCodec::encode_message_v2(
+                decoded.command,
+                decoded.nonce,
+                decoded.timestamp,
+                decoded.payload,
+                decoded.extensions,
+            ),
+        };
+        assert_eq!(encoded.unwrap(), data, "{name}");
+
+        let command = decoded.decode_command().unwrap();
+        assert_eq!(command.encode(), decoded.payload, "{name}");
+    }
+}
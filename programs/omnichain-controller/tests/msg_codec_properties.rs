@@ -0,0 +1,310 @@
+//! Property and edge-case tests for `state::msg_codec`.
+//!
+//! The arbitrary-input properties run the same checks as the cargo-fuzz
+//! targets in `fuzz/`, so `cargo test` covers them offline.
+
+mod harness;
+
+use omnichain_controller::error::ErrorCode;
+use omnichain_controller::state::msg_codec::{
+    AbiEncoder, Attestation, BatchUpdateCnftsPayload, CnftUpdate, Command, ComposeMessage,
+    ComposePayload, EnvelopePayload, MessageCodec, ReceiptPayload, SetDelegatePayload, SetMinVersionPayload,
+    TransferAuthorityPayload, UpdateMetadataPayload,
+    ABI_WORD_SIZE,
+};
+use anchor_lang::prelude::Pubkey;
+use proptest::prelude::*;
+
+/// A command discriminant with a canonically encoded payload
+fn command_payload() -> impl Strategy<Value = (u8, Vec<u8>)> {
+    prop_oneof![
+        (any::<String>(), any::<String>(), any::<String>()).prop_map(|(uri, name, symbol)| {
+            let payload = UpdateMetadataPayload {
+                uri: uri.as_str().into(),
+                name: name.as_str().into(),
+                symbol: symbol.as_str().into(),
+            };
+            (MessageCodec::COMMAND_UPDATE_COLLECTION_METADATA, payload.encode())
+        }),
+        prop::collection::vec((any::<u32>(), any::<String>()), 0..8).prop_map(|updates| {
+            (MessageCodec::COMMAND_BATCH_UPDATE_CNFTS, encode_batch(&updates))
+        }),
+        any::<[u8; 32]>().prop_map(|key| {
+            let payload = TransferAuthorityPayload { new_authority: Pubkey::new_from_array(key) };
+            (MessageCodec::COMMAND_TRANSFER_AUTHORITY, payload.encode())
+        }),
+        Just((MessageCodec::COMMAND_EMERGENCY_PAUSE, Vec::new())),
+        Just((MessageCodec::COMMAND_EMERGENCY_UNPAUSE, Vec::new())),
+        any::<u8>().prop_map(|min_version| {
+            (MessageCodec::COMMAND_SET_MIN_VERSION, SetMinVersionPayload { min_version }.encode())
+        }),
+        (any::<u8>(), prop::collection::vec(any::<u8>(), 0..100)).prop_map(|(compose_type, compose_msg)| {
+            (MessageCodec::COMMAND_COMPOSE, ComposePayload { compose_type, compose_msg: &compose_msg }.encode())
+        }),
+        (any::<[u8; 32]>(), any::<u64>(), any::<u32>(), any::<[u8; 32]>(), any::<u64>()).prop_map(
+            |(guid, nonce, result_code, merkle_root, processed_messages)| {
+                let payload = ReceiptPayload { guid, nonce, result_code, merkle_root, processed_messages };
+                (MessageCodec::COMMAND_RECEIPT, payload.encode())
+            }
+        ),
+        any::<[u8; 32]>().prop_map(|key| {
+            let payload = SetDelegatePayload { delegate: Pubkey::new_from_array(key) };
+            (MessageCodec::COMMAND_SET_DELEGATE, payload.encode())
+        }),
+    ]
+}
+
+fn encode_batch(updates: &[(u32, String)]) -> Vec<u8> {
+    let updates: Vec<CnftUpdate> = updates
+        .iter()
+        .map(|(leaf_index, new_uri)| CnftUpdate { leaf_index: *leaf_index, new_uri: new_uri.as_str().into() })
+        .collect();
+    BatchUpdateCnftsPayload::encode_updates(&updates)
+}
+
+fn encode_envelope(commands: &[(u8, Vec<u8>)]) -> Vec<u8> {
+    let discriminants = commands
+        .iter()
+        .fold(AbiEncoder::new(), |abi, (command, _)| abi.uint(*command as u64));
+    let payloads = commands
+        .iter()
+        .fold(AbiEncoder::new(), |abi, (_, payload)| abi.bytes(payload));
+    AbiEncoder::new().array(discriminants).array(payloads).finish()
+}
+
+fn word(value: u64) -> [u8; ABI_WORD_SIZE] {
+    let mut word = [0u8; ABI_WORD_SIZE];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+proptest! {
+    #[test]
+    fn prop_message_v1_round_trip(
+        command in any::<u8>(),
+        nonce in any::<u64>(),
+        timestamp in any::<i64>(),
+        payload in prop::collection::vec(any::<u8>(), 0..300),
+    ) {
+        let encoded = MessageCodec::encode_message(command, nonce, timestamp, &payload).unwrap();
+        let decoded = MessageCodec::decode_message(&encoded).unwrap();
+
+        prop_assert_eq!(decoded.version, MessageCodec::MESSAGE_VERSION_V1);
+        prop_assert_eq!(decoded.command, command);
+        prop_assert_eq!(decoded.nonce, nonce);
+        prop_assert_eq!(decoded.timestamp, timestamp);
+        prop_assert_eq!(decoded.payload, payload.as_slice());
+        prop_assert!(decoded.extensions.is_empty());
+    }
+
+    #[test]
+    fn prop_message_v2_round_trip(
+        command in any::<u8>(),
+        nonce in any::<u64>(),
+        timestamp in any::<i64>(),
+        payload in prop::collection::vec(any::<u8>(), 0..300),
+        extensions in prop::collection::vec(any::<u8>(), 0..300),
+    ) {
+        let encoded = MessageCodec::encode_message_v2(command, nonce, timestamp, &payload, &extensions).unwrap();
+        let decoded = MessageCodec::decode_message(&encoded).unwrap();
+
+        prop_assert_eq!(decoded.version, MessageCodec::MESSAGE_VERSION_V2);
+        prop_assert_eq!(decoded.command, command);
+        prop_assert_eq!(decoded.nonce, nonce);
+        prop_assert_eq!(decoded.timestamp, timestamp);
+        prop_assert_eq!(decoded.payload, payload.as_slice());
+        prop_assert_eq!(decoded.extensions, extensions.as_slice());
+    }
+
+    #[test]
+    fn prop_update_metadata_round_trip(uri in any::<String>(), name in any::<String>(), symbol in any::<String>()) {
+        let payload = MessageCodec::encode_update_metadata_payload(&uri, &name, &symbol).unwrap();
+        let decoded = MessageCodec::decode_update_metadata_payload(&payload).unwrap();
+
+        prop_assert_eq!(decoded.uri.as_str().unwrap(), uri.as_str());
+        prop_assert_eq!(decoded.name.as_str().unwrap(), name.as_str());
+        prop_assert_eq!(decoded.symbol.as_str().unwrap(), symbol.as_str());
+        prop_assert_eq!(decoded.encode(), payload);
+    }
+
+    #[test]
+    fn prop_batch_update_round_trip(updates in prop::collection::vec((any::<u32>(), any::<String>()), 0..16)) {
+        let payload = encode_batch(&updates);
+        let decoded = BatchUpdateCnftsPayload::decode(&payload).unwrap();
+
+        prop_assert_eq!(decoded.len(), updates.len());
+        for (update, (leaf_index, new_uri)) in decoded.iter().zip(&updates) {
+            let update = update.unwrap();
+            prop_assert_eq!(update.leaf_index, *leaf_index);
+            prop_assert_eq!(update.new_uri.as_str().unwrap(), new_uri.as_str());
+        }
+        prop_assert_eq!(decoded.encode(), payload);
+    }
+
+    #[test]
+    fn prop_command_round_trip((command, payload) in command_payload()) {
+        let decoded = Command::decode(command, &payload).unwrap();
+        prop_assert_eq!(decoded.discriminant(), command);
+        prop_assert_eq!(decoded.encode(), payload);
+    }
+
+    #[test]
+    fn prop_envelope_round_trip(commands in prop::collection::vec(command_payload(), 0..6)) {
+        let payload = encode_envelope(&commands);
+        let decoded = EnvelopePayload::decode(&payload).unwrap();
+
+        prop_assert_eq!(decoded.len(), commands.len());
+        for (index, (command, sub_payload)) in commands.iter().enumerate() {
+            let sub_command = decoded.get(index).unwrap();
+            prop_assert_eq!(sub_command.discriminant(), *command);
+            prop_assert_eq!(&sub_command.encode(), sub_payload);
+        }
+        prop_assert_eq!(decoded.encode(), payload);
+    }
+
+    #[test]
+    fn prop_attestation_round_trip(signatures in prop::collection::vec(prop::collection::vec(any::<u8>(), 65), 0..6)) {
+        let signatures: Vec<[u8; 65]> = signatures.iter().map(|s| s.as_slice().try_into().unwrap()).collect();
+        let extensions = Attestation::encode_signatures(&signatures);
+        let decoded = Attestation::decode(&extensions).unwrap();
+
+        prop_assert_eq!(decoded.len(), signatures.len());
+        for (index, signature) in signatures.iter().enumerate() {
+            prop_assert_eq!(decoded.signature_at(index).unwrap(), signature);
+        }
+    }
+
+    #[test]
+    fn prop_compose_message_round_trip(
+        nonce in any::<u64>(),
+        src_eid in any::<u32>(),
+        amount_ld in any::<u64>(),
+        compose_from in any::<[u8; 32]>(),
+        compose_msg in prop::collection::vec(any::<u8>(), 0..200),
+    ) {
+        let compose = ComposeMessage { nonce, src_eid, amount_ld, compose_from, compose_msg: &compose_msg };
+        let encoded = compose.encode();
+        prop_assert_eq!(ComposeMessage::decode(&encoded).unwrap(), compose);
+    }
+
+    #[test]
+    fn prop_arbitrary_bytes_never_panic(command in any::<u8>(), data in prop::collection::vec(any::<u8>(), 0..512)) {
+        harness::decode_message(&data);
+        harness::decode_command(command, &data);
+        harness::decode_update_metadata_payload(&data);
+        harness::decode_batch_update_cnfts(&data);
+        harness::decode_envelope(&data);
+        harness::decode_attestation(&data);
+        harness::decode_compose_message(&data);
+    }
+
+    #[test]
+    fn prop_mutated_messages_never_panic(
+        (command, payload) in command_payload(),
+        extensions in prop::collection::vec(any::<u8>(), 0..100),
+        position in any::<prop::sample::Index>(),
+        value in any::<u8>(),
+    ) {
+        let mut message = MessageCodec::encode_message_v2(command, 1, 0, &payload, &extensions).unwrap();
+        let position = position.index(message.len());
+        message[position] = value;
+
+        harness::decode_message(&message);
+        harness::decode_message(&message[..position]);
+    }
+}
+
+#[test]
+fn test_truncated_input_is_rejected() {
+    let payload = MessageCodec::encode_update_metadata_payload("ipfs://uri", "name", "SYM").unwrap();
+    let v1 = MessageCodec::encode_message(0, 1, 2, &payload).unwrap();
+    let v2 = MessageCodec::encode_message_v2(0, 1, 2, &payload, &[1; 65]).unwrap();
+
+    for len in 0..v1.len() {
+        assert!(MessageCodec::decode_message(&v1[..len]).is_err(), "v1 prefix of {len} bytes");
+    }
+    for len in 0..v2.len() {
+        assert!(MessageCodec::decode_message(&v2[..len]).is_err(), "v2 prefix of {len} bytes");
+    }
+    for len in 0..payload.len() {
+        assert!(MessageCodec::decode_update_metadata_payload(&payload[..len]).is_err());
+    }
+
+    let envelope = encode_envelope(&[(MessageCodec::COMMAND_EMERGENCY_PAUSE, Vec::new())]);
+    for len in 0..envelope.len() {
+        assert!(EnvelopePayload::decode(&envelope[..len]).is_err());
+    }
+}
+
+#[test]
+fn test_trailing_bytes_are_rejected() {
+    let mut message = MessageCodec::encode_message(0, 1, 2, &[7]).unwrap();
+    message.extend_from_slice(&[0; ABI_WORD_SIZE]);
+    assert_eq!(MessageCodec::decode_message(&message).unwrap_err(), ErrorCode::InvalidLzMessage.into());
+
+    let mut payload = MessageCodec::encode_update_metadata_payload("a", "b", "c").unwrap();
+    payload.push(0);
+    assert_eq!(
+        MessageCodec::decode_update_metadata_payload(&payload).unwrap_err(),
+        ErrorCode::InvalidLzMessage.into()
+    );
+}
+
+#[test]
+fn test_length_overflow_is_rejected() {
+    let message = MessageCodec::encode_message(0, 1, 2, &[7]).unwrap();
+    let payload_offset = 4 * ABI_WORD_SIZE;
+    let payload_len = 5 * ABI_WORD_SIZE;
+
+    let huge_words = [
+        word(u64::MAX),
+        // rounds up past usize::MAX when padded to a whole word
+        word(u64::MAX - 16),
+        word(message.len() as u64),
+        {
+            // wider than 64 bits
+            let mut word = word(1);
+            word[0] = 1;
+            word
+        },
+    ];
+
+    for huge in &huge_words {
+        let mut data = message.clone();
+        data[payload_len..payload_len + ABI_WORD_SIZE].copy_from_slice(huge);
+        assert_eq!(MessageCodec::decode_message(&data).unwrap_err(), ErrorCode::InvalidLzMessage.into());
+
+        let mut data = message.clone();
+        data[payload_offset..payload_offset + ABI_WORD_SIZE].copy_from_slice(huge);
+        assert_eq!(MessageCodec::decode_message(&data).unwrap_err(), ErrorCode::InvalidLzMessage.into());
+    }
+
+    // array lengths claiming more elements than the payload can hold
+    let batch = encode_batch(&[(1, "uri".to_string())]);
+    let leaf_indices_len = 2 * ABI_WORD_SIZE;
+    for huge in [word(u64::MAX), word(batch.len() as u64)] {
+        let mut data = batch.clone();
+        data[leaf_indices_len..leaf_indices_len + ABI_WORD_SIZE].copy_from_slice(&huge);
+        assert_eq!(
+            BatchUpdateCnftsPayload::decode(&data).unwrap_err(),
+            ErrorCode::InvalidLzMessage.into()
+        );
+    }
+
+    // misaligned and self-referencing offsets
+    for offset in [payload_len as u64 + 1, 0] {
+        let mut data = message.clone();
+        data[payload_offset..payload_offset + ABI_WORD_SIZE].copy_from_slice(&word(offset));
+        assert_eq!(MessageCodec::decode_message(&data).unwrap_err(), ErrorCode::InvalidLzMessage.into());
+    }
+}
+
+#[test]
+fn test_nested_envelope_inside_command_is_rejected() {
+    let inner = EnvelopePayload::encode_commands(&[Command::EmergencyPause]);
+    let outer = encode_envelope(&[(MessageCodec::COMMAND_ENVELOPE, inner)]);
+    assert_eq!(
+        Command::decode(MessageCodec::COMMAND_ENVELOPE, &outer).unwrap_err(),
+        ErrorCode::NestedEnvelope.into()
+    );
+}
@@ -0,0 +1,206 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import { OmnichainController } from "../target/types/omnichain_controller";
+import { MockEndpoint } from "../target/types/mock_endpoint";
+import {
+  ComputeBudgetProgram,
+  PublicKey,
+  SystemProgram,
+  Transaction,
+} from "@solana/web3.js";
+import { ethers } from "ethers";
+import { expect } from "chai";
+
+// Compute units lz_receive consumes, by message size
+//
+// Runs verify -> lz_receive_types -> lz_receive against the mock endpoint and
+// reads the units from each confirmed transaction. Start a validator with
+// both programs deployed (`anchor localnet`), then `npm run bench:compute`.
+describe("lz_receive compute units", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace
+    .omnichainController as Program<OmnichainController>;
+  const endpoint = anchor.workspace.mockEndpoint as Program<MockEndpoint>;
+  const admin = provider.wallet.publicKey;
+
+  // Each run gets its own path, so it can share a validator with other tests
+  const srcEid = 40000 + Math.floor(Math.random() * 10000);
+  const peer = Buffer.concat([Buffer.alloc(12), Buffer.from(ethers.utils.randomBytes(20))]);
+
+  // The default compute limit of an instruction; lz_receive must stay within it
+  const COMPUTE_BUDGET = 200_000;
+  const COMMAND_BATCH_UPDATE_CNFTS = 1;
+
+  const endpointPda = (...seeds: Buffer[]) =>
+    PublicKey.findProgramAddressSync(seeds, endpoint.programId)[0];
+  const [store] = PublicKey.findProgramAddressSync([Buffer.from("Store")], program.programId);
+  const [lzReceiveTypes] = PublicKey.findProgramAddressSync(
+    [Buffer.from("LzReceiveTypes"), store.toBuffer()],
+    program.programId
+  );
+  const eidBytes = (eid: number, littleEndian: boolean) => {
+    const bytes = Buffer.alloc(4);
+    if (littleEndian) {
+      bytes.writeUInt32LE(eid);
+    } else {
+      bytes.writeUInt32BE(eid);
+    }
+    return bytes;
+  };
+  const endpointConfig = endpointPda(Buffer.from("EndpointConfig"));
+  const nonce = endpointPda(Buffer.from("Nonce"), store.toBuffer(), eidBytes(srcEid, false), peer);
+
+  let packetNonce = 0;
+
+  // A v1 batch update message with `updates` URIs of 32 characters
+  const batchMessage = (daoNonce: number, updates: number) => {
+    const coder = ethers.utils.defaultAbiCoder;
+    const payload = coder.encode(
+      ["uint32[]", "string[]"],
+      [
+        Array.from({ length: updates }, (_, index) => index),
+        Array.from({ length: updates }, (_, index) => `https://example.com/${String(index).padStart(12, "0")}`),
+      ]
+    );
+    const timestamp = Math.floor(Date.now() / 1000);
+    return Buffer.from(
+      ethers.utils.arrayify(
+        coder.encode(
+          ["uint8", "uint64", "int64", "uint8", "bytes"],
+          [COMMAND_BATCH_UPDATE_CNFTS, daoNonce, timestamp, 1, payload]
+        )
+      )
+    );
+  };
+
+  // Deliver `message` as the next packet and return the units lz_receive used
+  const receive = async (message: Buffer): Promise<number> => {
+    packetNonce += 1;
+    const guid = Buffer.from(ethers.utils.randomBytes(32));
+    const params = {
+      receiver: store,
+      srcEid,
+      sender: Array.from(peer),
+      nonce: new BN(packetNonce),
+      guid: Array.from(guid),
+      message,
+    };
+    await endpoint.methods
+      .verify(params)
+      .accountsPartial({
+        endpointConfig,
+        admin,
+        nonce,
+        payloadHash: endpointPda(Buffer.from("PayloadHash"), guid),
+      })
+      .rpc();
+
+    // The executor asks the OApp for the accounts, then passes them on
+    const accounts = await program.methods
+      .lzReceiveTypes({ ...params })
+      .accountsPartial({ store, lzReceiveTypes, receiptConfig: null })
+      .view();
+    // Named accounts: store, peer, receive types, receipt config, endpoint
+    // and the two event CPI accounts
+    const remaining = accounts.slice(7).map((account: any) => ({
+      pubkey: account.pubkey,
+      isSigner: account.isSigner,
+      isWritable: account.isWritable,
+    }));
+    const [peerConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from("Peer"), store.toBuffer(), eidBytes(srcEid, true)],
+      program.programId
+    );
+    const instruction = await program.methods
+      .lzReceive(srcEid, params.sender, params.nonce, params.guid, message)
+      .accountsPartial({
+        store,
+        peerConfig,
+        lzReceiveTypes,
+        receiptConfig: null,
+        endpoint: endpoint.programId,
+      })
+      .remainingAccounts(remaining)
+      .instruction();
+
+    const transaction = new Transaction().add(
+      ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 }),
+      instruction
+    );
+    const signature = await provider.sendAndConfirm(transaction, [], { commitment: "confirmed" });
+    const confirmed = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    // The compute budget instruction itself costs 150 units
+    return (confirmed?.meta?.computeUnitsConsumed ?? 0) - 150;
+  };
+
+  before(async () => {
+    if (!(await provider.connection.getAccountInfo(endpointConfig))) {
+      await endpoint.methods
+        .initEndpoint({ eid: 30168, nativeFee: new BN(0), lzTokenFee: new BN(0) })
+        .accountsPartial({ endpointConfig, admin, systemProgram: SystemProgram.programId })
+        .rpc();
+    }
+
+    if (!(await provider.connection.getAccountInfo(store))) {
+      await program.methods
+        .initOappStore({
+          endpoint: endpoint.programId,
+          delegate: admin,
+          authorizedDao: Array.from(peer.subarray(12)),
+          ethereumEid: srcEid,
+          collectionMetadata: {
+            name: "Benchmark",
+            symbol: "BENCH",
+            uri: "https://example.com/collection.json",
+            mintAuthority: admin,
+            updateAuthority: admin,
+            treeConfig: {
+              maxDepth: 14,
+              maxBufferSize: 64,
+              treeCreator: admin,
+              treeDelegate: admin,
+              merkleTree: PublicKey.default,
+            },
+          },
+        })
+        .accountsPartial({ store, lzReceiveTypes, admin, endpoint: endpoint.programId })
+        .remainingAccounts([
+          { pubkey: endpointPda(Buffer.from("OApp"), store.toBuffer()), isSigner: false, isWritable: true },
+          { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        ])
+        .rpc();
+    }
+
+    await program.methods
+      .setPeer(srcEid, Array.from(peer), { ordered: {} })
+      .accountsPartial({ store, payer: admin, admin })
+      .rpc();
+    await endpoint.methods
+      .initNonce({ localOapp: store, remoteEid: srcEid, remoteOapp: Array.from(peer) })
+      .accountsPartial({ payer: admin, nonce })
+      .rpc();
+  });
+
+  it("Should stay within the compute budget as messages grow", async () => {
+    const storeAccount = await program.account.oAppStore.fetch(store);
+    let daoNonce = storeAccount.nonce.toNumber();
+
+    const results: { updates: number; bytes: number; units: number }[] = [];
+    for (const updates of [0, 1, 2, 3]) {
+      daoNonce += 1;
+      const message = batchMessage(daoNonce, updates);
+      results.push({ updates, bytes: message.length, units: await receive(message) });
+    }
+    console.table(results);
+
+    for (const { units } of results) {
+      expect(units).to.be.greaterThan(0);
+      expect(units).to.be.lessThan(COMPUTE_BUDGET);
+    }
+  });
+});